@@ -0,0 +1,138 @@
+//! Baselines for the parsing and conversion pipeline.
+//!
+//! ```sh
+//! $ cargo bench
+//! ```
+
+use std::{hint::black_box, path::Path};
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use firstrun::{
+    conv::log_changes_to_chunks,
+    log::{EntryLog, Timestamp},
+    wpilog::{load::load_parts, parse::WpiLogFile},
+};
+use rerun::{ApplicationId, EntityPath, StoreId, StoreKind, Timeline};
+
+const EXAMPLE: &[u8] = include_bytes!("../test_data/FRC_TBD_d225b5377c70a88d.wpilog");
+
+/// Decodes every record of a log into an [`EntryLog`] with the loader.
+fn fill(parts: &[Vec<u8>]) -> EntryLog {
+    let mut log = EntryLog::new();
+    // decode failures are part of what's being measured
+    load_parts(parts, &mut log);
+
+    log
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(EXAMPLE.len() as u64));
+
+    group.bench_function("records", |b| {
+        b.iter(|| WpiLogFile::parse(black_box(EXAMPLE), |_| {}).unwrap());
+    });
+    let parts = [EXAMPLE.to_vec()];
+    group.bench_function("decode", |b| b.iter(|| fill(black_box(&parts))));
+
+    group.finish();
+}
+
+fn struct_decode(c: &mut Criterion) {
+    const SAMPLES: u64 = 10_000;
+
+    let schema_key = EntityPath::from_file_path(Path::new(".schema/struct:Translation2d"));
+    let key = EntityPath::from_file_path(Path::new("drive/translation"));
+    let values = (0..SAMPLES)
+        .map(|i| {
+            #[allow(clippy::cast_precision_loss)]
+            let x = i as f64;
+            [x.to_le_bytes(), (-x).to_le_bytes()].concat()
+        })
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("struct");
+    group.throughput(Throughput::Elements(SAMPLES));
+    group.bench_function("Translation2d", |b| {
+        b.iter_batched(
+            || {
+                let mut log = EntryLog::new();
+                log.add_entry(
+                    schema_key.clone(),
+                    Timestamp(0),
+                    "structschema",
+                    b"double x;double y",
+                )
+                .unwrap();
+                log
+            },
+            |mut log| {
+                for (i, value) in values.iter().enumerate() {
+                    log.add_entry(
+                        key.clone(),
+                        Timestamp(i as u64 + 1),
+                        "struct:Translation2d",
+                        value,
+                    )
+                    .unwrap();
+                }
+                log
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+fn conversion(c: &mut Criterion) {
+    let store_id = StoreId::random(StoreKind::Recording);
+    let application_id = ApplicationId::random();
+    let timeline = Timeline::new_duration("robotime");
+
+    let parts = [EXAMPLE.to_vec()];
+    c.bench_function("log_changes_to_chunks", |b| {
+        b.iter_batched(
+            || fill(&parts),
+            |mut log| log_changes_to_chunks(&store_id, &application_id, timeline, &mut log),
+            BatchSize::LargeInput,
+        );
+    });
+}
+
+fn scalar_chunks(c: &mut Criterion) {
+    const SAMPLES: u64 = 100_000;
+
+    let store_id = StoreId::random(StoreKind::Recording);
+    let application_id = ApplicationId::random();
+    let timeline = Timeline::new_duration("robotime");
+    let key = EntityPath::from_file_path(Path::new("drive/voltage"));
+
+    let mut group = c.benchmark_group("scalars");
+    group.throughput(Throughput::Elements(SAMPLES));
+    group.bench_function("log_changes_to_chunks", |b| {
+        b.iter_batched(
+            || {
+                let mut log = EntryLog::new();
+                for i in 0..SAMPLES {
+                    #[allow(clippy::cast_precision_loss)]
+                    let value = (i as f64 / 100.0).sin();
+                    // a 200Hz signal
+                    log.add_entry(
+                        key.clone(),
+                        Timestamp(i * 5_000),
+                        "double",
+                        &value.to_le_bytes(),
+                    )
+                    .unwrap();
+                }
+                log
+            },
+            |mut log| log_changes_to_chunks(&store_id, &application_id, timeline, &mut log),
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse, struct_decode, conversion, scalar_chunks);
+criterion_main!(benches);
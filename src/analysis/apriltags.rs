@@ -0,0 +1,209 @@
+//! AprilTag field layouts, like WPILib's `AprilTagFieldLayout` logged as JSON, drawn once as a
+//! marker at each tag's pose.
+
+use std::{collections::BTreeSet, path::Path};
+
+use rerun::{
+    Boxes3D, EntityPath, TimePoint,
+    external::{
+        anyhow,
+        arrow::{
+            self,
+            array::AsArray as _,
+            datatypes::{DataType, Float64Type},
+        },
+        re_log,
+    },
+    log::{Chunk, RowId},
+};
+
+use crate::log::{ARRAY_LENGTH, EntryLog, array_element, array_length};
+
+/// Where the tags of every layout in the log are drawn.
+pub const APRILTAGS_ENTITY: &str = "apriltags";
+
+/// The side of the 36h11 tags on an FRC field, in meters.
+const TAG_SIZE: f32 = 0.1651;
+
+/// A tag's id and field pose, its rotation an `[x, y, z, w]` quaternion.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AprilTag {
+    pub id: i64,
+    pub translation: [f64; 3],
+    pub rotation: [f64; 4],
+}
+
+fn child(parent: &EntityPath, path: &str) -> EntityPath {
+    parent.join(&EntityPath::from_file_path(Path::new(path)))
+}
+
+fn latest_f64(log: &EntryLog, key: &EntityPath) -> Option<f64> {
+    let (_, value) = log.get_latest_entry(key)?;
+    let value = arrow::compute::cast(value, &DataType::Float64).ok()?;
+    value.as_primitive::<Float64Type>().iter().next().flatten()
+}
+
+/// Finds the `tags` arrays of the layouts in the log, recognized by their elements' `ID`s.
+#[must_use]
+pub fn layouts(log: &EntryLog) -> BTreeSet<EntityPath> {
+    log.entity_paths()
+        .filter(|path| {
+            path.last()
+                .is_some_and(|p| p.unescaped_str() == ARRAY_LENGTH)
+        })
+        .filter_map(EntityPath::parent)
+        .filter(|tags| {
+            tags.last().is_some_and(|p| p.unescaped_str() == "tags")
+                && log
+                    .get_entry(&child(&array_element(tags, 0), "ID"))
+                    .is_some()
+        })
+        .collect()
+}
+
+/// Reads the latest tags of a layout, skipping any without an id or a position.
+///
+/// A tag without a rotation faces along the field's X axis.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn layout_tags(log: &EntryLog, tags: &EntityPath) -> Vec<AprilTag> {
+    let Some(length) = latest_f64(log, &array_length(tags)) else {
+        return Vec::new();
+    };
+
+    (0..length as usize)
+        .filter_map(|i| {
+            let tag = array_element(tags, i);
+            let get = |field: &str| latest_f64(log, &child(&tag, field));
+
+            let (Some(id), Some(x), Some(y), Some(z)) = (
+                get("ID"),
+                get("pose/translation/x"),
+                get("pose/translation/y"),
+                get("pose/translation/z"),
+            ) else {
+                re_log::warn!("skipping AprilTag {tag}: it has no id or position");
+                return None;
+            };
+
+            let quaternion =
+                ["X", "Y", "Z", "W"].map(|axis| get(&format!("pose/rotation/quaternion/{axis}")));
+            let rotation = if let [Some(x), Some(y), Some(z), Some(w)] = quaternion {
+                [x, y, z, w]
+            } else {
+                re_log::warn!("AprilTag {id} has no rotation, so it's drawn facing +X");
+                [0.0, 0.0, 0.0, 1.0]
+            };
+
+            Some(AprilTag {
+                id: id as i64,
+                translation: [x, y, z],
+                rotation,
+            })
+        })
+        .collect()
+}
+
+/// Draws the tags of every layout once, statically, as thin boxes labelled with their ids.
+///
+/// A tag's pose faces out of its printed side, along its X axis.
+#[allow(clippy::cast_possible_truncation)]
+pub fn apriltag_chunks(log: &EntryLog) -> Result<Vec<Chunk>, anyhow::Error> {
+    let tags = layouts(log)
+        .iter()
+        .flat_map(|layout| layout_tags(log, layout))
+        .collect::<Vec<_>>();
+    if tags.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let markers = Boxes3D::from_centers_and_half_sizes(
+        tags.iter().map(|tag| tag.translation.map(|v| v as f32)),
+        std::iter::repeat_n([0.005, TAG_SIZE / 2.0, TAG_SIZE / 2.0], tags.len()),
+    )
+    .with_quaternions(
+        tags.iter()
+            .map(|tag| rerun::Quaternion::from_xyzw(tag.rotation.map(|v| v as f32))),
+    )
+    .with_labels(tags.iter().map(|tag| format!("Tag {}", tag.id)));
+
+    let chunk = Chunk::builder(EntityPath::from_single_string(APRILTAGS_ENTITY))
+        .with_archetype(RowId::new(), TimePoint::default(), &markers)
+        .build()?;
+
+    Ok(vec![chunk])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rerun::{EntityPath, components::Text};
+
+    use super::AprilTag;
+    use crate::log::{EntryLog, Timestamp};
+
+    #[test]
+    fn two_tag_layout() {
+        let mut log = EntryLog::new();
+        let layout = br#"{
+            "tags": [
+                {
+                    "ID": 1,
+                    "pose": {
+                        "translation": {"x": 15.08, "y": 0.25, "z": 1.36},
+                        "rotation": {"quaternion": {"W": 0.5, "X": 0.0, "Y": 0.0, "Z": 0.866}}
+                    }
+                },
+                {
+                    "ID": 7,
+                    "pose": {"translation": {"x": -0.04, "y": 4.03, "z": 0.5}}
+                }
+            ],
+            "field": {"length": 16.54, "width": 8.21}
+        }"#;
+        log.add_entry(
+            EntityPath::from_file_path(Path::new("vision/layout")),
+            Timestamp(0),
+            "json",
+            layout,
+        )
+        .unwrap();
+
+        let layouts = super::layouts(&log);
+        assert_eq!(
+            layouts.iter().collect::<Vec<_>>(),
+            [&EntityPath::from_file_path(Path::new("vision/layout/tags"))]
+        );
+        assert_eq!(
+            super::layout_tags(&log, layouts.first().unwrap()),
+            [
+                AprilTag {
+                    id: 1,
+                    translation: [15.08, 0.25, 1.36],
+                    rotation: [0.0, 0.0, 0.866, 0.5],
+                },
+                // missing its rotation, but still drawn
+                AprilTag {
+                    id: 7,
+                    translation: [-0.04, 4.03, 0.5],
+                    rotation: [0.0, 0.0, 0.0, 1.0],
+                },
+            ]
+        );
+
+        let chunks = super::apriltag_chunks(&log).unwrap();
+        let [chunk] = &chunks[..] else {
+            panic!("expected one chunk, got {}", chunks.len());
+        };
+        assert!(chunk.is_static());
+        assert_eq!(
+            chunk
+                .iter_component::<Text>()
+                .flat_map(|batch| batch.to_vec())
+                .map(|label| label.as_str().to_owned())
+                .collect::<Vec<_>>(),
+            ["Tag 1", "Tag 7"]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! The commands the command scheduler is running, derived from its `Names` list.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use rerun::{
+    EntityPath, Scalars, TextLog, TextLogLevel, TimePoint, Timeline,
+    external::{anyhow, arrow::array::AsArray as _, re_log},
+    log::{Chunk, RowId},
+};
+use serde::Deserialize;
+
+use crate::{
+    config::EntityPattern,
+    log::{ARRAY_LENGTH, EntryLog, Timestamp, array_element, array_length},
+};
+
+/// The `string[]` entries listing the running commands, and where they're logged.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CommandsConfig {
+    /// Matches the scheduler's list of running command names.
+    pub entries: Vec<EntityPattern>,
+    /// The entity the running commands are logged under.
+    pub entity: String,
+}
+
+impl Default for CommandsConfig {
+    fn default() -> Self {
+        Self {
+            // published by WPILib's `CommandScheduler` sendable
+            entries: vec![EntityPattern("**/Scheduler/Names".to_owned())],
+            entity: "commands".to_owned(),
+        }
+    }
+}
+
+/// Reads each change of a scheduler's running command names.
+///
+/// Arrays are logged as a `$length` entity plus one entity per index, all at the same timestamp.
+#[must_use]
+pub fn running_commands(log: &EntryLog, names: &EntityPath) -> Vec<(Timestamp, Vec<String>)> {
+    let Some(lengths) = log.get_entry(&array_length(names)) else {
+        return Vec::new();
+    };
+
+    let mut changes: Vec<(Timestamp, Vec<String>)> = Vec::new();
+    for (timestamp, length) in lengths {
+        let Some(length) = length
+            .as_primitive_opt::<rerun::external::arrow::datatypes::Int64Type>()
+            .and_then(|length| length.iter().next().flatten())
+        else {
+            continue;
+        };
+
+        let commands = (0..length)
+            .filter_map(|i| {
+                let value = log
+                    .get_entry(&array_element(names, usize::try_from(i).ok()?))?
+                    .get(timestamp)?;
+                Some(value.as_string_opt::<i32>()?.iter().next()??.to_owned())
+            })
+            .collect::<Vec<_>>();
+
+        if changes.last().is_none_or(|(_, last)| *last != commands) {
+            changes.push((*timestamp, commands));
+        }
+    }
+
+    changes
+}
+
+/// Logs the running commands as a text log entry per change, and each command as a scalar that's
+/// 1 while it's running so its intervals can be plotted.
+pub fn command_chunks(
+    log: &EntryLog,
+    config: &CommandsConfig,
+    timeline: Timeline,
+) -> Result<Vec<Chunk>, anyhow::Error> {
+    let lists = log
+        .entity_paths()
+        .filter_map(|path| {
+            let parent = path.parent()?;
+            (path.last()?.unescaped_str() == ARRAY_LENGTH
+                && config.entries.iter().any(|p| p.matches(&parent)))
+            .then_some(parent)
+        })
+        .collect::<BTreeSet<_>>();
+
+    let root = EntityPath::from_file_path(Path::new(&config.entity));
+    let mut chunks = Vec::new();
+    for names in lists {
+        let changes = running_commands(log, &names);
+        if changes.is_empty() {
+            continue;
+        }
+
+        let mut text = Chunk::builder(root.clone());
+        let mut toggles = BTreeMap::<&str, Vec<(TimePoint, f64)>>::new();
+        let mut running = BTreeSet::<&str>::new();
+
+        for (timestamp, now) in &changes {
+            let Ok(time) = timestamp.time_point(timeline) else {
+                re_log::warn_once!("skipping running commands that don't fit on the timeline");
+                continue;
+            };
+            text = text.with_archetype(
+                RowId::new(),
+                time.clone(),
+                &TextLog::new(format!("running: {}", now.join(", ")))
+                    .with_level(TextLogLevel::INFO),
+            );
+
+            let now = now.iter().map(String::as_str).collect::<BTreeSet<_>>();
+            for (command, value) in running
+                .difference(&now)
+                .map(|c| (*c, 0.0))
+                .chain(now.difference(&running).map(|c| (*c, 1.0)))
+            {
+                toggles
+                    .entry(command)
+                    .or_default()
+                    .push((time.clone(), value));
+            }
+            running = now;
+        }
+
+        chunks.push(text.build()?);
+        for (command, toggles) in toggles {
+            let mut builder = Chunk::builder(root.join(&EntityPath::from_single_string(command)));
+            for (time, value) in toggles {
+                builder = builder.with_archetype(RowId::new(), time, &Scalars::new([value]));
+            }
+            chunks.push(builder.build()?);
+        }
+    }
+
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rerun::{EntityPath, Timeline};
+
+    use super::CommandsConfig;
+    use crate::log::{EntryLog, Timestamp};
+
+    fn string_array(strings: &[&str]) -> Vec<u8> {
+        let mut data = u32::try_from(strings.len()).unwrap().to_le_bytes().to_vec();
+        for string in strings {
+            data.extend(u32::try_from(string.len()).unwrap().to_le_bytes());
+            data.extend(string.as_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn changing_command_list() {
+        let mut log = EntryLog::new();
+        let names = EntityPath::from_file_path(Path::new("SmartDashboard/Scheduler/Names"));
+
+        for (timestamp, commands) in [
+            (0, &[][..]),
+            (1_000, &["Drive"][..]),
+            (2_000, &["Drive"][..]),
+            (3_000, &["Drive", "Intake"][..]),
+            (4_000, &["Intake"][..]),
+        ] {
+            log.add_entry(
+                names.clone(),
+                Timestamp(timestamp),
+                "string[]",
+                &string_array(commands),
+            )
+            .unwrap();
+        }
+
+        let changes = super::running_commands(&log, &names);
+        assert_eq!(
+            changes,
+            [
+                (Timestamp(0), vec![]),
+                (Timestamp(1_000), vec!["Drive".to_owned()]),
+                (
+                    Timestamp(3_000),
+                    vec!["Drive".to_owned(), "Intake".to_owned()]
+                ),
+                (Timestamp(4_000), vec!["Intake".to_owned()]),
+            ]
+        );
+
+        let chunks = super::command_chunks(
+            &log,
+            &CommandsConfig::default(),
+            Timeline::new_duration("robotime"),
+        )
+        .unwrap();
+        let drive = EntityPath::from_file_path(Path::new("commands/Drive"));
+        let drive = chunks
+            .iter()
+            .find(|c| c.entity_path() == &drive)
+            .expect("no chunk for the Drive command");
+        // started, then stopped
+        assert_eq!(drive.num_rows(), 2);
+    }
+}
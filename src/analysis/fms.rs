@@ -0,0 +1,248 @@
+//! The match a log was recorded in, read from the `FMSInfo` entries the driver station publishes.
+
+use std::path::Path;
+
+use rerun::{
+    ApplicationId, EntityPath,
+    external::arrow::{
+        array::{ArrayRef, AsArray as _},
+        datatypes::Int64Type,
+    },
+};
+
+use crate::log::EntryLog;
+
+/// The table the driver station publishes the match info under.
+pub const FMS_INFO_TABLE: &str = "FMSInfo";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchType {
+    None,
+    Practice,
+    Qualification,
+    Elimination,
+}
+
+impl MatchType {
+    const fn from_code(code: i64) -> Self {
+        match code {
+            1 => Self::Practice,
+            2 => Self::Qualification,
+            3 => Self::Elimination,
+            _ => Self::None,
+        }
+    }
+
+    /// The letter match numbers are usually prefixed with, as in `Q38`.
+    const fn prefix(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Practice => "P",
+            Self::Qualification => "Q",
+            Self::Elimination => "E",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchInfo {
+    pub event: String,
+    pub match_type: MatchType,
+    pub match_number: i64,
+    pub replay: i64,
+    pub red_alliance: Option<bool>,
+}
+
+impl MatchInfo {
+    /// Names the match like `CASJ_Q38`, with `_R2` appended for a replay.
+    #[must_use]
+    pub fn label(&self) -> String {
+        let mut label = format!("{}{}", self.match_type.prefix(), self.match_number);
+        if !self.event.is_empty() {
+            label = format!("{}_{label}", self.event);
+        }
+        if self.replay > 1 {
+            label = format!("{label}_R{}", self.replay);
+        }
+
+        label
+    }
+}
+
+/// The last value of an entry, as the info is filled in once the robot connects to the field.
+fn latest(log: &EntryLog, table: &EntityPath, field: &str) -> Option<ArrayRef> {
+    log.get_latest_entry(&table.join(&EntityPath::from_single_string(field)))
+        .map(|(_, value)| value.clone())
+}
+
+/// Where the driver station's table is, preferring the top-level `/FMSInfo` when another copy of
+/// it was logged too, and otherwise the shortest path, so the choice doesn't depend on the order
+/// entries were stored in.
+fn fms_table(log: &EntryLog) -> Option<EntityPath> {
+    log.entity_paths()
+        .filter_map(EntityPath::parent)
+        .filter(|parent| {
+            parent
+                .last()
+                .is_some_and(|p| p.unescaped_str() == FMS_INFO_TABLE)
+        })
+        .min_by_key(|parent| (parent.len(), parent.clone()))
+}
+
+fn is_red_alliance(log: &EntryLog, table: &EntityPath) -> Option<bool> {
+    latest(log, table, "IsRedAlliance")
+        .and_then(|value| value.as_boolean_opt()?.iter().next().flatten())
+}
+
+/// Whether the robot was on the red alliance, or `None` if the log doesn't say.
+///
+/// Unlike [`match_info`], this is known outside of matches too, as the driver station picks it.
+#[must_use]
+pub fn red_alliance(log: &EntryLog) -> Option<bool> {
+    is_red_alliance(log, &fms_table(log)?)
+}
+
+/// Reads the match info, or `None` if the log has none or wasn't recorded in a match.
+#[must_use]
+pub fn match_info(log: &EntryLog) -> Option<MatchInfo> {
+    let table = fms_table(log)?;
+
+    let int = |field: &str| {
+        latest(log, &table, field)?
+            .as_primitive_opt::<Int64Type>()?
+            .iter()
+            .next()
+            .flatten()
+    };
+
+    let info = MatchInfo {
+        event: latest(log, &table, "EventName")
+            .and_then(|value| {
+                Some(
+                    value
+                        .as_string_opt::<i32>()?
+                        .iter()
+                        .next()??
+                        .trim()
+                        .to_owned(),
+                )
+            })
+            .unwrap_or_default(),
+        match_type: MatchType::from_code(int("MatchType").unwrap_or_default()),
+        match_number: int("MatchNumber").unwrap_or_default(),
+        replay: int("ReplayNumber").unwrap_or_default(),
+        red_alliance: is_red_alliance(log, &table),
+    };
+
+    (info.match_type != MatchType::None && info.match_number > 0).then_some(info)
+}
+
+/// Identifies the recording by the match it was recorded in, falling back to the log's file
+/// name so reloading the same file is recognized as the same recording, and only then to a
+/// random id.
+#[must_use]
+pub fn application_id(log: &EntryLog, filepath: &Path) -> ApplicationId {
+    match_info(log)
+        .as_ref()
+        .map(MatchInfo::label)
+        .or_else(|| Some(filepath.file_stem()?.to_string_lossy().into_owned()))
+        .map_or_else(ApplicationId::random, ApplicationId::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rerun::{ApplicationId, EntityPath};
+
+    use super::MatchType;
+    use crate::log::{EntryLog, Timestamp};
+
+    fn fms_log(entries: &[(&str, &str, Vec<u8>)]) -> EntryLog {
+        let mut log = EntryLog::new();
+        for (field, ty, value) in entries {
+            log.add_entry(
+                EntityPath::from_file_path(Path::new("FMSInfo"))
+                    .join(&EntityPath::from_single_string(*field)),
+                Timestamp(1_000),
+                ty,
+                value,
+            )
+            .unwrap();
+        }
+        log
+    }
+
+    #[test]
+    fn application_id_from_fms_info() {
+        let log = fms_log(&[
+            ("EventName", "string", b"CASJ".to_vec()),
+            ("MatchType", "int64", 2i64.to_le_bytes().to_vec()),
+            ("MatchNumber", "int64", 38i64.to_le_bytes().to_vec()),
+            ("ReplayNumber", "int64", 1i64.to_le_bytes().to_vec()),
+            ("IsRedAlliance", "boolean", vec![1]),
+        ]);
+        let path = Path::new("logs/FRC_20250321_184359.wpilog");
+
+        let info = super::match_info(&log).unwrap();
+        assert_eq!(info.match_type, MatchType::Qualification);
+        assert_eq!(info.red_alliance, Some(true));
+        assert_eq!(
+            super::application_id(&log, path),
+            ApplicationId::from("CASJ_Q38")
+        );
+
+        // not in a match, so the file name is used instead
+        let log = fms_log(&[
+            ("EventName", "string", b"".to_vec()),
+            ("MatchType", "int64", 0i64.to_le_bytes().to_vec()),
+            ("MatchNumber", "int64", 0i64.to_le_bytes().to_vec()),
+        ]);
+        assert_eq!(
+            super::application_id(&log, path),
+            ApplicationId::from("FRC_20250321_184359")
+        );
+    }
+
+    #[test]
+    fn prefers_top_level_table() {
+        for nested_first in [true, false] {
+            let mut log = EntryLog::new();
+            let mut add = |table: &str, number: i64| {
+                for (field, value) in [("MatchType", 2i64), ("MatchNumber", number)] {
+                    log.add_entry(
+                        EntityPath::from_file_path(Path::new(table))
+                            .join(&EntityPath::from_single_string(field)),
+                        Timestamp(1_000),
+                        "int64",
+                        &value.to_le_bytes(),
+                    )
+                    .unwrap();
+                }
+            };
+            if nested_first {
+                add("Replay/FMSInfo", 12);
+                add("FMSInfo", 38);
+            } else {
+                add("FMSInfo", 38);
+                add("Replay/FMSInfo", 12);
+            }
+
+            assert_eq!(super::match_info(&log).unwrap().match_number, 38);
+        }
+    }
+
+    #[test]
+    fn reloads_keep_their_application_id() {
+        let path = Path::new("test_data/FRC_TBD_d225b5377c70a88d.wpilog");
+        let load = || {
+            let mut log = EntryLog::new();
+            crate::wpilog::load::load_parts(&[std::fs::read(path).unwrap()], &mut log);
+            super::application_id(&log, path)
+        };
+
+        let first = load();
+        assert_eq!(first, load());
+        assert_eq!(first, ApplicationId::from("FRC_TBD_d225b5377c70a88d"));
+    }
+}
@@ -0,0 +1,7 @@
+//! Passes over a fully-parsed [`EntryLog`](crate::log::EntryLog) that derive extra entities.
+
+pub mod apriltags;
+pub mod commands;
+pub mod fms;
+pub mod phase;
+pub mod trajectory;
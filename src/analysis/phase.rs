@@ -0,0 +1,370 @@
+//! Match phase (disabled/autonomous/teleop/test) derived from the driver station control entries.
+
+use std::{collections::BTreeMap, path::Path};
+
+use rerun::{
+    AnnotationContext, EntityPath, Scalars, SeriesLines, TextLog, TextLogLevel, TimePoint,
+    Timeline,
+    external::{anyhow, arrow::array::AsArray as _, re_log},
+    log::{Chunk, RowId},
+};
+use serde::Deserialize;
+
+use crate::{
+    conv::hex_color,
+    log::{EntryLog, Timestamp},
+};
+
+/// The entries the match phase is read from, and where it's logged.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MatchPhaseConfig {
+    pub enabled_entry: String,
+    pub autonomous_entry: String,
+    pub test_entry: String,
+    /// The entity the derived phase is logged under.
+    pub entity: String,
+    /// An entity the phase is also logged under as a series colored by phase, so plots of it
+    /// shade the log by match phase. Not logged if unset.
+    pub band_entity: Option<String>,
+    /// The color of each phase in the band.
+    pub band_colors: PhaseColors,
+}
+
+impl Default for MatchPhaseConfig {
+    fn default() -> Self {
+        Self {
+            enabled_entry: "DS:enabled".to_owned(),
+            autonomous_entry: "DS:autonomous".to_owned(),
+            test_entry: "DS:test".to_owned(),
+            entity: "match_phase".to_owned(),
+            band_entity: None,
+            band_colors: PhaseColors::default(),
+        }
+    }
+}
+
+/// A `#rrggbb` color for each match phase.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PhaseColors {
+    pub disabled: String,
+    pub autonomous: String,
+    pub teleop: String,
+    pub test: String,
+}
+
+impl Default for PhaseColors {
+    fn default() -> Self {
+        Self {
+            disabled: "#808080".to_owned(),
+            autonomous: "#e6b800".to_owned(),
+            teleop: "#2e8b57".to_owned(),
+            test: "#8a2be2".to_owned(),
+        }
+    }
+}
+
+impl PhaseColors {
+    /// The color of `phase`, or `None` with a warning if it isn't `#rrggbb`.
+    fn color(&self, phase: MatchPhase) -> Option<rerun::Color> {
+        let color = match phase {
+            MatchPhase::Disabled => &self.disabled,
+            MatchPhase::Autonomous => &self.autonomous,
+            MatchPhase::Teleop => &self.teleop,
+            MatchPhase::Test => &self.test,
+        };
+
+        let parsed = hex_color(color);
+        if parsed.is_none() {
+            re_log::warn_once!("the {phase} band color {color:?} isn't #rrggbb");
+        }
+        parsed
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum::Display, strum::IntoStaticStr)]
+pub enum MatchPhase {
+    Disabled = 0,
+    Autonomous = 1,
+    Teleop = 2,
+    Test = 3,
+}
+
+impl MatchPhase {
+    const ALL: [Self; 4] = [Self::Disabled, Self::Autonomous, Self::Teleop, Self::Test];
+
+    const fn from_flags(enabled: bool, autonomous: bool, test: bool) -> Self {
+        match (enabled, autonomous, test) {
+            (false, _, _) => Self::Disabled,
+            (true, _, true) => Self::Test,
+            (true, true, false) => Self::Autonomous,
+            (true, false, false) => Self::Teleop,
+        }
+    }
+}
+
+fn bool_entry(log: &EntryLog, name: &str) -> Option<BTreeMap<Timestamp, bool>> {
+    let entry = log.get_entry(&EntityPath::from_file_path(Path::new(name)))?;
+
+    Some(
+        entry
+            .iter()
+            .filter_map(|(timestamp, value)| {
+                let value = value.as_boolean_opt()?;
+                (!value.is_empty()).then(|| (*timestamp, value.value(0)))
+            })
+            .collect(),
+    )
+}
+
+/// Computes each change in match phase, or `None` if the log doesn't have the enabled entry.
+#[must_use]
+pub fn match_phases(
+    log: &EntryLog,
+    config: &MatchPhaseConfig,
+) -> Option<Vec<(Timestamp, MatchPhase)>> {
+    let enabled = bool_entry(log, &config.enabled_entry)?;
+    let autonomous = bool_entry(log, &config.autonomous_entry).unwrap_or_default();
+    let test = bool_entry(log, &config.test_entry).unwrap_or_default();
+
+    let latest = |entry: &BTreeMap<Timestamp, bool>, timestamp: Timestamp| {
+        entry
+            .range(..=timestamp)
+            .next_back()
+            .is_some_and(|(_, value)| *value)
+    };
+
+    let mut timestamps = enabled
+        .keys()
+        .chain(autonomous.keys())
+        .chain(test.keys())
+        .copied()
+        .collect::<Vec<_>>();
+    timestamps.sort_unstable();
+    timestamps.dedup();
+
+    let mut phases: Vec<(Timestamp, MatchPhase)> = Vec::new();
+    for timestamp in timestamps {
+        let phase = MatchPhase::from_flags(
+            latest(&enabled, timestamp),
+            latest(&autonomous, timestamp),
+            latest(&test, timestamp),
+        );
+
+        if phases.last().is_none_or(|(_, last)| *last != phase) {
+            phases.push((timestamp, phase));
+        }
+    }
+
+    Some(phases)
+}
+
+/// Logs each match phase change as a text log entry and a categorical scalar, and as a band
+/// colored by phase if one is configured.
+pub fn match_phase_chunks(
+    log: &EntryLog,
+    config: &MatchPhaseConfig,
+    timeline: Timeline,
+) -> Result<Vec<Chunk>, anyhow::Error> {
+    let Some(phases) = match_phases(log, config) else {
+        // no driver station data, nothing to mark
+        return Ok(Vec::new());
+    };
+
+    let mut builder = Chunk::builder(EntityPath::from_file_path(Path::new(&config.entity)));
+    for &(timestamp, phase) in &phases {
+        let Ok(time) = timestamp.time_point(timeline) else {
+            re_log::warn_once!("skipping match phases that don't fit on the timeline");
+            continue;
+        };
+
+        builder = builder
+            .with_archetype(
+                RowId::new(),
+                time.clone(),
+                &TextLog::new(phase.to_string()).with_level(TextLogLevel::INFO),
+            )
+            .with_archetype(RowId::new(), time, &Scalars::new([f64::from(phase as u8)]));
+    }
+    let mut chunks = vec![builder.build()?];
+
+    if let Some(band) = &config.band_entity {
+        chunks.extend(band_chunks(
+            EntityPath::from_file_path(Path::new(band)),
+            &phases,
+            &config.band_colors,
+            timeline,
+        )?);
+    }
+
+    Ok(chunks)
+}
+
+/// Logs the phases as a series that changes color with each one, and the phase each value
+/// stands for as a static annotation context.
+fn band_chunks(
+    entity: EntityPath,
+    phases: &[(Timestamp, MatchPhase)],
+    colors: &PhaseColors,
+    timeline: Timeline,
+) -> Result<Vec<Chunk>, anyhow::Error> {
+    let annotations = AnnotationContext::new(MatchPhase::ALL.map(|phase| {
+        (
+            phase as u16,
+            <&'static str>::from(phase),
+            colors
+                .color(phase)
+                .map_or_else(|| rerun::Rgba32::from_rgb(128, 128, 128), |color| color.0),
+        )
+    }));
+    let legend = Chunk::builder(entity.clone())
+        .with_archetype(RowId::new(), TimePoint::default(), &annotations)
+        .build()?;
+
+    let mut builder = Chunk::builder(entity);
+    for &(timestamp, phase) in phases {
+        let Ok(time) = timestamp.time_point(timeline) else {
+            continue;
+        };
+
+        builder = builder.with_archetype(
+            RowId::new(),
+            time.clone(),
+            &Scalars::new([f64::from(phase as u8)]),
+        );
+        if let Some(color) = colors.color(phase) {
+            builder = builder.with_archetype(
+                RowId::new(),
+                time,
+                &SeriesLines::new().with_colors([color]),
+            );
+        }
+    }
+
+    Ok(vec![legend, builder.build()?])
+}
+
+#[cfg(test)]
+mod tests {
+    use rerun::{
+        EntityPath, Timeline,
+        components::{AnnotationContext, Color, Scalar},
+    };
+
+    use super::{MatchPhase, MatchPhaseConfig};
+    use crate::log::{EntryLog, Timestamp};
+
+    fn set(log: &mut EntryLog, name: &str, timestamp: u64, value: bool) {
+        log.add_entry(
+            EntityPath::from_single_string(name),
+            Timestamp(timestamp),
+            "boolean",
+            &[u8::from(value)],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn auto_then_teleop() {
+        let mut log = EntryLog::new();
+        set(&mut log, "DS:enabled", 0, false);
+        set(&mut log, "DS:autonomous", 0, true);
+        set(&mut log, "DS:enabled", 1_000, true);
+        set(&mut log, "DS:enabled", 16_000, false);
+        set(&mut log, "DS:autonomous", 17_000, false);
+        set(&mut log, "DS:enabled", 18_000, true);
+        set(&mut log, "DS:enabled", 153_000, false);
+
+        let phases = super::match_phases(&log, &MatchPhaseConfig::default()).unwrap();
+
+        assert_eq!(
+            phases,
+            vec![
+                (Timestamp(0), MatchPhase::Disabled),
+                (Timestamp(1_000), MatchPhase::Autonomous),
+                (Timestamp(16_000), MatchPhase::Disabled),
+                (Timestamp(18_000), MatchPhase::Teleop),
+                (Timestamp(153_000), MatchPhase::Disabled),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_entries_are_skipped() {
+        let log = EntryLog::new();
+
+        assert_eq!(
+            super::match_phases(&log, &MatchPhaseConfig::default()),
+            None
+        );
+        assert!(
+            super::match_phase_chunks(
+                &log,
+                &MatchPhaseConfig::default(),
+                rerun::Timeline::new_duration("robotime")
+            )
+            .unwrap()
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn phase_band() {
+        let mut log = EntryLog::new();
+        set(&mut log, "DS:enabled", 0, false);
+        set(&mut log, "DS:autonomous", 0, true);
+        set(&mut log, "DS:enabled", 1_000, true);
+        set(&mut log, "DS:enabled", 16_000, false);
+        set(&mut log, "DS:autonomous", 17_000, false);
+        set(&mut log, "DS:enabled", 18_000, true);
+
+        let config = MatchPhaseConfig {
+            band_entity: Some("phase_band".to_owned()),
+            ..MatchPhaseConfig::default()
+        };
+        let chunks =
+            super::match_phase_chunks(&log, &config, Timeline::new_duration("robotime")).unwrap();
+
+        let band = EntityPath::from_single_string("phase_band");
+        let (legend, series): (Vec<_>, Vec<_>) = chunks
+            .iter()
+            .filter(|c| c.entity_path() == &band)
+            .partition(|c| c.is_static());
+
+        let [legend] = &legend[..] else {
+            panic!("expected one static chunk, got {}", legend.len());
+        };
+        let contexts = legend
+            .iter_component::<AnnotationContext>()
+            .flat_map(|batch| batch.to_vec())
+            .collect::<Vec<_>>();
+        let [context] = &contexts[..] else {
+            panic!("expected one annotation context, got {}", contexts.len());
+        };
+        assert_eq!(context.0.len(), MatchPhase::ALL.len());
+
+        let [series] = &series[..] else {
+            panic!("expected one band chunk, got {}", series.len());
+        };
+        assert_eq!(
+            series
+                .iter_component::<Scalar>()
+                .flat_map(|batch| batch.to_vec())
+                .collect::<Vec<_>>(),
+            [0.0, 1.0, 0.0, 2.0].map(Scalar::from)
+        );
+        assert_eq!(
+            series
+                .iter_component::<Color>()
+                .flat_map(|batch| batch.to_vec())
+                .collect::<Vec<_>>(),
+            [
+                Color::from_rgb(0x80, 0x80, 0x80),
+                Color::from_rgb(0xe6, 0xb8, 0x00),
+                Color::from_rgb(0x80, 0x80, 0x80),
+                Color::from_rgb(0x2e, 0x8b, 0x57),
+            ]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Trajectories logged as arrays of timed states, such as Choreo's and PathPlanner's
+//! `{double t; Pose2d pose; double velocity;}`, drawn as a path with a setpoint following it.
+
+use std::{collections::BTreeSet, path::Path};
+
+use rerun::{
+    EntityPath, LineStrips2D, Scalars, Timeline,
+    external::{
+        anyhow,
+        arrow::{
+            self,
+            array::AsArray as _,
+            datatypes::{DataType, Float64Type, Int64Type},
+        },
+        re_log,
+    },
+    log::{Chunk, RowId},
+};
+
+use crate::{
+    conv::footprint_box,
+    log::{ARRAY_LENGTH, EntryLog, Timestamp, array_element, array_length},
+};
+
+/// One state of a trajectory, `t` seconds after it starts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrajectoryState {
+    pub t: f64,
+    pub x: f64,
+    pub y: f64,
+    pub heading: f64,
+    pub velocity: Option<f64>,
+}
+
+fn child(parent: &EntityPath, path: &str) -> EntityPath {
+    parent.join(&EntityPath::from_file_path(Path::new(path)))
+}
+
+fn f64_at(log: &EntryLog, key: &EntityPath, timestamp: Timestamp) -> Option<f64> {
+    let value =
+        arrow::compute::cast(log.get_entry(key)?.get(&timestamp)?, &DataType::Float64).ok()?;
+    value.as_primitive::<Float64Type>().iter().next().flatten()
+}
+
+/// Finds the array entities whose elements have a time and a pose.
+#[must_use]
+pub fn trajectories(log: &EntryLog) -> BTreeSet<EntityPath> {
+    log.entity_paths()
+        .filter(|path| {
+            path.last()
+                .is_some_and(|p| p.unescaped_str() == ARRAY_LENGTH)
+        })
+        .filter_map(EntityPath::parent)
+        .filter(|parent| {
+            ["t", "pose/translation/x"].iter().all(|field| {
+                log.get_entry(&child(&array_element(parent, 0), field))
+                    .is_some()
+            })
+        })
+        .collect()
+}
+
+/// Reads the trajectory published at `timestamp`, or `None` if any state is missing a field.
+#[must_use]
+pub fn trajectory_states(
+    log: &EntryLog,
+    trajectory: &EntityPath,
+    timestamp: Timestamp,
+) -> Option<Vec<TrajectoryState>> {
+    let length = log
+        .get_entry(&array_length(trajectory))?
+        .get(&timestamp)?
+        .as_primitive_opt::<Int64Type>()?
+        .iter()
+        .next()
+        .flatten()?;
+
+    (0..length)
+        .map(|i| {
+            let state = array_element(trajectory, usize::try_from(i).ok()?);
+            let get = |field: &str| f64_at(log, &child(&state, field), timestamp);
+
+            Some(TrajectoryState {
+                t: get("t")?,
+                x: get("pose/translation/x")?,
+                y: get("pose/translation/y")?,
+                heading: get("pose/rotation/value")?,
+                velocity: get("velocity"),
+            })
+        })
+        .collect()
+}
+
+/// Draws each published trajectory as a path at `<trajectory>/path`, and plays it back from the
+/// time it was published, as the commanded velocity at `<trajectory>/velocity` and a footprint
+/// at the setpoint at `<trajectory>/setpoint`.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn trajectory_chunks(log: &EntryLog, timeline: Timeline) -> Result<Vec<Chunk>, anyhow::Error> {
+    let config = log.config();
+    let mut chunks = Vec::new();
+
+    for trajectory in trajectories(log) {
+        let Some(lengths) = log.get_entry(&array_length(&trajectory)) else {
+            continue;
+        };
+
+        let mut path = Chunk::builder(child(&trajectory, "path"));
+        let mut velocity = Chunk::builder(child(&trajectory, "velocity"));
+        let mut setpoint = Chunk::builder(child(&trajectory, "setpoint"));
+
+        for &published in lengths.keys() {
+            let Some(states) = trajectory_states(log, &trajectory, published) else {
+                continue;
+            };
+
+            let Ok(time) = published.time_point(timeline) else {
+                re_log::warn_once!("skipping paths of {trajectory} that don't fit on the timeline");
+                continue;
+            };
+            path = path.with_archetype(
+                RowId::new(),
+                time,
+                &LineStrips2D::new([states
+                    .iter()
+                    .map(|s| config.coordinates.to_2d([s.x as f32, s.y as f32]))
+                    .collect::<Vec<_>>()]),
+            );
+
+            for state in &states {
+                // microseconds
+                let Some(time) = published.0.checked_add((state.t.max(0.0) * 1e6) as u64) else {
+                    re_log::warn_once!(
+                        "skipping a state of {trajectory} at {}s, past the end of the timeline",
+                        state.t
+                    );
+                    continue;
+                };
+                let Ok(time) = Timestamp(time).time_point(timeline) else {
+                    re_log::warn_once!(
+                        "skipping states of {trajectory} that don't fit on the timeline"
+                    );
+                    continue;
+                };
+
+                if let Some(v) = state.velocity {
+                    velocity =
+                        velocity.with_archetype(RowId::new(), time.clone(), &Scalars::new([v]));
+                }
+                setpoint = setpoint.with_archetype(
+                    RowId::new(),
+                    time,
+                    &footprint_box((state.x, state.y, state.heading), &config.footprint),
+                );
+            }
+        }
+
+        for builder in [path, velocity, setpoint] {
+            let chunk = builder.build()?;
+            if !chunk.is_empty() {
+                chunks.push(chunk);
+            }
+        }
+    }
+
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rerun::{EntityPath, Timeline};
+
+    use super::TrajectoryState;
+    use crate::{
+        log::{EntryLog, Timestamp},
+        values::parse::wpistruct::WpiLibStructSchema,
+    };
+
+    #[test]
+    fn three_state_trajectory() {
+        let mut log = EntryLog::new();
+        for (name, schema) in [
+            ("Translation2d", &b"double x;double y"[..]),
+            ("Rotation2d", b"double value"),
+            ("Pose2d", b"Translation2d translation;Rotation2d rotation"),
+            (
+                "struct:TrajectoryState",
+                b"double t;Pose2d pose;double velocity",
+            ),
+        ] {
+            log.add_struct(name, WpiLibStructSchema::parse(schema).unwrap());
+        }
+
+        let states = [
+            [0.0, 0.0, 0.0, 0.0, 0.0],
+            [0.5, 1.0, 0.5, 0.25, 2.0],
+            [1.0, 2.0, 1.0, 0.5, 0.0],
+        ];
+        let data = states
+            .iter()
+            .flatten()
+            .flat_map(|v: &f64| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let trajectory = EntityPath::from_file_path(Path::new("auto/trajectory"));
+        log.add_entry(
+            trajectory.clone(),
+            Timestamp(2_000_000),
+            "struct:TrajectoryState[]",
+            &data,
+        )
+        .unwrap();
+
+        assert_eq!(
+            super::trajectories(&log).into_iter().collect::<Vec<_>>(),
+            [trajectory.clone()]
+        );
+        assert_eq!(
+            super::trajectory_states(&log, &trajectory, Timestamp(2_000_000)).unwrap(),
+            states.map(|[t, x, y, heading, velocity]| TrajectoryState {
+                t,
+                x,
+                y,
+                heading,
+                velocity: Some(velocity),
+            })
+        );
+
+        let chunks = super::trajectory_chunks(&log, Timeline::new_duration("robotime")).unwrap();
+        let rows = |name: &str| {
+            chunks
+                .iter()
+                .find(|c| {
+                    c.entity_path() == &trajectory.join(&EntityPath::from_single_string(name))
+                })
+                .map(rerun::log::Chunk::num_rows)
+        };
+        assert_eq!(rows("path"), Some(1));
+        assert_eq!(rows("velocity"), Some(3));
+        assert_eq!(rows("setpoint"), Some(3));
+
+        // nothing was published then
+        assert_eq!(
+            super::trajectory_states(&log, &trajectory, Timestamp(0)),
+            None
+        );
+    }
+
+    #[test]
+    fn state_past_end_of_timeline() {
+        let mut log = EntryLog::new();
+        for (name, schema) in [
+            ("Translation2d", &b"double x;double y"[..]),
+            ("Rotation2d", b"double value"),
+            ("Pose2d", b"Translation2d translation;Rotation2d rotation"),
+            ("struct:TrajectoryState", b"double t;Pose2d pose"),
+        ] {
+            log.add_struct(name, WpiLibStructSchema::parse(schema).unwrap());
+        }
+
+        let data = [[0.0, 0.0, 0.0, 0.0], [1e300, 1.0, 1.0, 0.0]]
+            .iter()
+            .flatten()
+            .flat_map(|v: &f64| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let trajectory = EntityPath::from_file_path(Path::new("auto/trajectory"));
+        log.add_entry(
+            trajectory.clone(),
+            Timestamp(2_000_000),
+            "struct:TrajectoryState[]",
+            &data,
+        )
+        .unwrap();
+
+        let chunks = super::trajectory_chunks(&log, Timeline::new_duration("robotime")).unwrap();
+        let setpoints = chunks
+            .iter()
+            .find(|c| {
+                c.entity_path() == &trajectory.join(&EntityPath::from_single_string("setpoint"))
+            })
+            .map(rerun::log::Chunk::num_rows);
+        assert_eq!(setpoints, Some(1));
+    }
+}
@@ -0,0 +1,386 @@
+//! A default layout for a loaded log, with views picked by what the log contains.
+
+use std::collections::BTreeSet;
+
+use rerun::{
+    ApplicationId, ComponentName, EntityPath, Loggable as _, StoreId, StoreKind, TimePoint,
+    blueprint::{
+        archetypes::{
+            ContainerBlueprint, ScalarAxis, ViewBlueprint, ViewContents, ViewportBlueprint,
+        },
+        components::ContainerKind,
+    },
+    components::{LineStrip2D, PoseTranslation3D, Range1D, Scalar, Translation3D},
+    datatypes::Uuid,
+    external::{
+        anyhow,
+        re_log_types::{BlueprintActivationCommand, SetStoreInfo, StoreInfo, StoreSource},
+    },
+    log::{Chunk, LogMsg, RowId},
+};
+
+use crate::{conv::scalar_range, log::EntryLog};
+
+/// A view of every entity that has any of `components`.
+struct View {
+    class: &'static str,
+    name: String,
+    components: Vec<ComponentName>,
+    entities: BTreeSet<EntityPath>,
+    /// The range of a plot's axis.
+    range: Option<(f64, f64)>,
+}
+
+/// Blueprint ids only need to be unique within their blueprint.
+fn uuid(n: u8) -> Uuid {
+    Uuid {
+        bytes: [
+            0xf1, 0x25, 0x7e, 0x40, 0, 0, 0x40, 0, 0x80, 0, 0, 0, 0, 0, 0, n,
+        ],
+    }
+}
+
+fn uuid_path(kind: &str, uuid: Uuid) -> EntityPath {
+    let hex = uuid
+        .bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+
+    EntityPath::from_single_string(kind).join(&EntityPath::from_single_string(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )))
+}
+
+/// The blueprint sent with a log, if its config asks for one; see [`blueprint_messages`].
+///
+/// Without one, scalars keep the range the viewer fits to them, whatever bounds their metadata
+/// gives.
+pub fn default_blueprint(
+    application_id: &ApplicationId,
+    chunks: &[Chunk],
+    log: &EntryLog,
+) -> Result<Vec<LogMsg>, anyhow::Error> {
+    if !log.config().blueprint {
+        return Ok(Vec::new());
+    }
+
+    blueprint_messages(application_id, chunks, log)
+}
+
+/// Builds a blueprint that lays out a 2D view of pose trails, a 3D view of poses and a plot of
+/// scalars, leaving out whichever the chunks have nothing for. Scalars whose metadata in `log`
+/// bounds them get a plot of their own with that range.
+///
+/// Returns no messages if there's nothing to lay out, leaving the viewer's default layout.
+pub fn blueprint_messages(
+    application_id: &ApplicationId,
+    chunks: &[Chunk],
+    log: &EntryLog,
+) -> Result<Vec<LogMsg>, anyhow::Error> {
+    let mut views = vec![
+        View {
+            class: "2D",
+            name: "Field".to_owned(),
+            components: vec![LineStrip2D::name()],
+            entities: BTreeSet::new(),
+            range: None,
+        },
+        View {
+            class: "3D",
+            name: "Robot".to_owned(),
+            components: vec![Translation3D::name(), PoseTranslation3D::name()],
+            entities: BTreeSet::new(),
+            range: None,
+        },
+        View {
+            class: "TimeSeries",
+            name: "Plots".to_owned(),
+            components: vec![Scalar::name()],
+            entities: BTreeSet::new(),
+            range: None,
+        },
+    ];
+
+    for chunk in chunks {
+        for view in &mut views {
+            if chunk
+                .component_names()
+                .any(|name| view.components.contains(&name))
+            {
+                view.entities.insert(chunk.entity_path().clone());
+            }
+        }
+    }
+
+    // an axis range applies to everything in the plot
+    let bounded = views[2]
+        .entities
+        .iter()
+        .filter_map(|entity| Some((entity.clone(), scalar_range(log, entity)?)))
+        .collect::<Vec<_>>();
+    for (entity, range) in bounded {
+        views[2].entities.remove(&entity);
+        views.push(View {
+            class: "TimeSeries",
+            name: entity.to_string(),
+            components: vec![Scalar::name()],
+            entities: BTreeSet::from([entity]),
+            range: Some(range),
+        });
+    }
+
+    let mut blueprint = Vec::new();
+    let mut contents = Vec::new();
+    for (n, view) in (1..=u8::MAX).zip(views.iter().filter(|v| !v.entities.is_empty())) {
+        let path = uuid_path("view", uuid(n));
+        let queries = view
+            .entities
+            .iter()
+            .map(|entity| format!("+ {entity}/**"))
+            .collect::<Vec<_>>();
+
+        blueprint.push(
+            Chunk::builder(path.clone())
+                .with_archetype(
+                    RowId::new(),
+                    TimePoint::default(),
+                    &ViewBlueprint::new(view.class)
+                        .with_display_name(view.name.as_str())
+                        .with_space_origin("/"),
+                )
+                .build()?,
+        );
+        blueprint.push(
+            Chunk::builder(path.join(&EntityPath::from_single_string("ViewContents")))
+                .with_archetype(
+                    RowId::new(),
+                    TimePoint::default(),
+                    &ViewContents::new(queries),
+                )
+                .build()?,
+        );
+        if let Some((min, max)) = view.range {
+            blueprint.push(
+                Chunk::builder(path.join(&EntityPath::from_single_string("ScalarAxis")))
+                    .with_archetype(
+                        RowId::new(),
+                        TimePoint::default(),
+                        &ScalarAxis::update_fields().with_range(Range1D::new(min, max)),
+                    )
+                    .build()?,
+            );
+        }
+        contents.push(path.to_string());
+    }
+
+    if contents.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let root = uuid(0);
+    blueprint.push(
+        Chunk::builder(uuid_path("container", root))
+            .with_archetype(
+                RowId::new(),
+                TimePoint::default(),
+                &ContainerBlueprint::new(ContainerKind::Grid).with_contents(contents),
+            )
+            .build()?,
+    );
+    blueprint.push(
+        Chunk::builder(EntityPath::from_single_string("viewport"))
+            .with_archetype(
+                RowId::new(),
+                TimePoint::default(),
+                &ViewportBlueprint::new()
+                    .with_root_container(root)
+                    .with_auto_views(false),
+            )
+            .build()?,
+    );
+
+    let store_id = StoreId::random(StoreKind::Blueprint);
+    let mut messages = vec![LogMsg::SetStoreInfo(SetStoreInfo {
+        row_id: *RowId::new(),
+        info: StoreInfo {
+            application_id: application_id.clone(),
+            store_id: store_id.clone(),
+            cloned_from: None,
+            store_source: StoreSource::Other("WpiLog".into()),
+            store_version: None,
+        },
+    })];
+    for chunk in blueprint {
+        messages.push(LogMsg::ArrowMsg(store_id.clone(), chunk.to_arrow_msg()?));
+    }
+    messages.push(LogMsg::BlueprintActivationCommand(
+        BlueprintActivationCommand {
+            blueprint_id: store_id,
+            make_active: true,
+            make_default: true,
+        },
+    ));
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rerun::{
+        ApplicationId, EntityPath, Loggable as _, StoreId, StoreKind, Timeline,
+        blueprint::components::ViewClass,
+        components::Range1D,
+        log::{Chunk, LogMsg},
+    };
+
+    use crate::{
+        config::{ComponentOverride, Config, EntityPattern},
+        log::{EntryLog, Timestamp},
+    };
+
+    fn pose_chunks() -> Vec<Chunk> {
+        let mut log = EntryLog::with_config(Config {
+            components: vec![ComponentOverride {
+                pattern: EntityPattern("drive/pose".to_owned()),
+                components: vec!["Pose2d".to_owned()],
+            }],
+            ..Config::default()
+        });
+        for (path, value) in [
+            ("drive/pose/translation/x", 1.0f64),
+            ("drive/pose/translation/y", 2.0),
+            ("drive/pose/rotation/value", 0.0),
+        ] {
+            log.add_entry(
+                EntityPath::from_file_path(Path::new(path)),
+                Timestamp(1_000),
+                "double",
+                &value.to_le_bytes(),
+            )
+            .unwrap();
+        }
+
+        crate::conv::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        )
+    }
+
+    #[test]
+    fn blueprint_for_poses() {
+        let application_id = ApplicationId::random();
+        let messages =
+            super::blueprint_messages(&application_id, &pose_chunks(), &EntryLog::new()).unwrap();
+
+        // store info, the 3D view and its contents, the root container, the viewport, activation
+        assert_eq!(messages.len(), 6);
+        let Some(LogMsg::SetStoreInfo(info)) = messages.first() else {
+            panic!("blueprint doesn't start with its store info");
+        };
+        assert_eq!(info.info.application_id, application_id);
+        assert!(matches!(
+            messages.last(),
+            Some(LogMsg::BlueprintActivationCommand(command))
+                if command.blueprint_id == info.info.store_id
+        ));
+
+        assert!(
+            super::blueprint_messages(&application_id, &[], &EntryLog::new())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn bounded_scalar_plot() {
+        let mut log = EntryLog::with_config(Config {
+            blueprint: true,
+            ..Config::default()
+        });
+        let speed = EntityPath::from_single_string("speed");
+        let current = EntityPath::from_single_string("current");
+        log.set_metadata(speed.clone(), r#"{"min":0,"max":5}"#);
+        for entity in [&speed, &current] {
+            log.add_entry(
+                entity.clone(),
+                Timestamp(1_000),
+                "double",
+                &1.0f64.to_le_bytes(),
+            )
+            .unwrap();
+        }
+        let chunks = crate::conv::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        let messages = super::default_blueprint(&ApplicationId::random(), &chunks, &log).unwrap();
+        let blueprint = messages
+            .iter()
+            .filter_map(|message| match message {
+                LogMsg::ArrowMsg(_, msg) => Some(Chunk::from_arrow_msg(msg).unwrap()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // the bounded scalar is plotted on its own, with its range
+        let axes = blueprint
+            .iter()
+            .filter(|c| {
+                c.entity_path()
+                    .last()
+                    .is_some_and(|part| part.unescaped_str() == "ScalarAxis")
+            })
+            .collect::<Vec<_>>();
+        let [axis] = &axes[..] else {
+            panic!("expected one axis range, got {}", axes.len());
+        };
+        assert!(axis.component_names().any(|name| name == Range1D::name()));
+        assert_eq!(
+            blueprint
+                .iter()
+                .filter(|c| c.component_names().any(|name| name == ViewClass::name()))
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn bounds_need_the_blueprint() {
+        let mut log = EntryLog::new();
+        let speed = EntityPath::from_single_string("speed");
+        log.set_metadata(speed.clone(), r#"{"min":0,"max":5}"#);
+        log.add_entry(speed, Timestamp(1_000), "double", &1.0f64.to_le_bytes())
+            .unwrap();
+        let chunks = crate::conv::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        // nothing but the blueprint can carry the range, and it's opt-in
+        assert!(
+            chunks
+                .iter()
+                .all(|c| c.component_names().all(|name| name != Range1D::name()))
+        );
+        assert!(
+            super::default_blueprint(&ApplicationId::random(), &chunks, &log)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
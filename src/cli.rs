@@ -0,0 +1,327 @@
+//! Command line tools that run in place of the viewer.
+
+use std::{fmt::Write as _, path::Path};
+
+use rerun::external::{
+    anyhow::{self, Context as _, anyhow, bail},
+    re_log,
+};
+
+use crate::{
+    config::{Config, UnknownWallClock, WallClockFilter},
+    export,
+    log::EntryLog,
+    nt::{
+        client::NtConfig,
+        replay::{self, Publisher, SystemClock},
+    },
+    values::parse::wpistruct::{UnresolvedWpiLibStructType, WpiLibStructType},
+    wpilog,
+};
+
+/// Runs the tool named by the first argument, or returns `None` if it isn't one, in which case
+/// the arguments are meant for the viewer.
+#[must_use]
+pub fn run(args: &[String]) -> Option<Result<(), anyhow::Error>> {
+    let tool = args.get(1)?.as_str();
+    let rest = &args[2..];
+
+    Some(match tool {
+        "--print-schema" => print_schema(rest),
+        "--entities" => print_entities(rest),
+        "--to-arrow" => to_arrow(rest),
+        "--to-ndjson" => to_ndjson(rest),
+        "--replay" => replay_log(rest),
+        _ => return None,
+    })
+}
+
+/// The options every tool takes, to pick which records are loaded by wall-clock time.
+const WALL_CLOCK_USAGE: &str = "[--since <time>] [--until <time>] [--drop-unknown-time]";
+
+/// Takes the wall-clock options out of a tool's arguments, applying them over `filter`, and
+/// returns the rest.
+fn wall_clock_options<'a>(
+    args: &'a [String],
+    filter: &mut WallClockFilter,
+) -> Result<Vec<&'a str>, anyhow::Error> {
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            option @ ("--since" | "--until") => {
+                let time = args
+                    .next()
+                    .with_context(|| format!("{option} needs an RFC 3339 time"))?
+                    .parse()?;
+                if option == "--since" {
+                    filter.since = Some(time);
+                } else {
+                    filter.until = Some(time);
+                }
+            }
+            "--drop-unknown-time" => filter.unknown = UnknownWallClock::Drop,
+            arg => rest.push(arg),
+        }
+    }
+
+    Ok(rest)
+}
+
+/// Decodes the log at `path` with `config`.
+fn load(path: &Path, config: Config) -> Result<EntryLog, anyhow::Error> {
+    let contents =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+
+    let mut log = EntryLog::with_config(config);
+    wpilog::load::load_parts(&[contents], &mut log);
+
+    Ok(log)
+}
+
+fn print_schema(args: &[String]) -> Result<(), anyhow::Error> {
+    let mut config = Config::from_env()?;
+    let [path] = wall_clock_options(args, &mut config.wall_clock)?[..] else {
+        bail!("usage: firstrun --print-schema <file.wpilog> {WALL_CLOCK_USAGE}");
+    };
+
+    print!("{}", schema_report(&load(Path::new(path), config)?));
+
+    Ok(())
+}
+
+fn print_entities(args: &[String]) -> Result<(), anyhow::Error> {
+    let mut config = Config::from_env()?;
+    let [path] = wall_clock_options(args, &mut config.wall_clock)?[..] else {
+        bail!("usage: firstrun --entities <file.wpilog> {WALL_CLOCK_USAGE}");
+    };
+
+    print!("{}", entity_tree(&load(Path::new(path), config)?));
+
+    Ok(())
+}
+
+fn to_arrow(args: &[String]) -> Result<(), anyhow::Error> {
+    let mut config = Config::from_env()?;
+    let [path, out] = wall_clock_options(args, &mut config.wall_clock)?[..] else {
+        bail!("usage: firstrun --to-arrow <file.wpilog> <out.arrow> {WALL_CLOCK_USAGE}");
+    };
+
+    let log = load(Path::new(path), config)?;
+    let file = std::fs::File::create(out).with_context(|| format!("failed to create {out}"))?;
+    export::write_arrow(&log, std::io::BufWriter::new(file))
+}
+
+fn to_ndjson(args: &[String]) -> Result<(), anyhow::Error> {
+    let mut config = Config::from_env()?;
+    let [path, out] = wall_clock_options(args, &mut config.wall_clock)?[..] else {
+        bail!("usage: firstrun --to-ndjson <file.wpilog> <out.ndjson | -> {WALL_CLOCK_USAGE}");
+    };
+
+    let log = load(Path::new(path), config)?;
+    if out == "-" {
+        return export::write_ndjson(&log, std::io::stdout().lock());
+    }
+    let file = std::fs::File::create(out).with_context(|| format!("failed to create {out}"))?;
+    export::write_ndjson(&log, std::io::BufWriter::new(file))
+}
+
+/// Re-publishes the NetworkTables entries of a log to the NT4 server at `host`, at real time or
+/// `--speed` times it.
+fn replay_log(args: &[String]) -> Result<(), anyhow::Error> {
+    let mut config = Config::from_env()?;
+    let (path, host, speed) = match wall_clock_options(args, &mut config.wall_clock)?[..] {
+        [path, host] => (path, host, 1.0),
+        [path, host, "--speed", speed] => {
+            (path, host, speed.parse().context("--speed needs a number")?)
+        }
+        _ => {
+            bail!("usage: firstrun --replay <file.wpilog> <host> [--speed <x>] {WALL_CLOCK_USAGE}")
+        }
+    };
+
+    let nt = NtConfig {
+        address: Some(host.to_owned()),
+        ..config.nt
+    };
+    let url = nt.url().context("no host to replay to")?;
+
+    let contents = std::fs::read(path).with_context(|| format!("failed to read {path}"))?;
+    let (_, file) = wpilog::parse::WpiLogFile::parse(&contents, |_| {})
+        .map_err(|e| anyhow!("failed to parse {path}: {e}"))?;
+    let records = wpilog::load::wall_clock_records(file.records, &config.wall_clock);
+    let messages = replay::nt_messages(&records, &config.filter);
+
+    let mut publisher = Publisher::connect(&url)?;
+    re_log::warn!("replaying {} values to {url}", messages.len());
+    replay::replay(&messages, speed, &mut SystemClock, |message| {
+        publisher.publish(message)
+    })
+}
+
+fn size(size: Option<usize>) -> String {
+    size.map_or_else(
+        || "variable size".to_owned(),
+        |size| format!("{size} bytes"),
+    )
+}
+
+/// Lists every struct schema the log registered, with its fields and whether it resolved.
+#[must_use]
+pub fn schema_report(log: &EntryLog) -> String {
+    let structs = log.structs();
+    let mut names = structs.keys().collect::<Vec<_>>();
+    names.sort();
+
+    let mut report = String::new();
+    for name in names {
+        let schema = &structs[name];
+
+        match schema.resolve(structs) {
+            Ok(resolved) => {
+                let _ = writeln!(report, "{name} ({})", size(resolved.size()));
+
+                for (field, data) in &resolved.fields {
+                    let ty = match &data.ty {
+                        WpiLibStructType::Primitive(p) => format!("{p:?}"),
+                        WpiLibStructType::Custom(_) => match &schema.fields[field].ty {
+                            UnresolvedWpiLibStructType::Custom(name) => name.clone(),
+                            UnresolvedWpiLibStructType::Primitive(p) => format!("{p:?}"),
+                        },
+                    };
+                    let count = data.count.map(|c| format!("[{c}]")).unwrap_or_default();
+                    let _ = writeln!(report, "  {field}: {ty}{count} ({})", size(data.size()));
+                }
+            }
+            Err(missing) => {
+                let _ = writeln!(report, "{name} (unresolved: missing {missing})");
+
+                for (field, data) in &schema.fields {
+                    let ty = match &data.ty {
+                        UnresolvedWpiLibStructType::Primitive(p) => format!("{p:?}"),
+                        UnresolvedWpiLibStructType::Custom(name) => name.clone(),
+                    };
+                    let count = data.count.map(|c| format!("[{c}]")).unwrap_or_default();
+                    let _ = writeln!(report, "  {field}: {ty}{count}");
+                }
+            }
+        }
+    }
+
+    report
+}
+
+/// Lists every entity the log decoded to as a tree, one part of its path per level, with the
+/// Arrow type and sample count of each entity that has values.
+#[must_use]
+pub fn entity_tree(log: &EntryLog) -> String {
+    let mut paths = log
+        .entity_paths()
+        .map(|path| {
+            let parts = path
+                .iter()
+                .map(|part| part.unescaped_str().to_owned())
+                .collect::<Vec<_>>();
+            (parts, path)
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut tree = String::new();
+    let mut previous: &[String] = &[];
+    for (parts, path) in &paths {
+        // the parents shared with the previous entity are already listed
+        let shared = parts
+            .iter()
+            .zip(previous)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        for (depth, part) in parts.iter().enumerate().skip(shared) {
+            let indent = "  ".repeat(depth);
+            if depth + 1 < parts.len() {
+                let _ = writeln!(tree, "{indent}{part}");
+                continue;
+            }
+
+            let entry = log.get_entry(path).map(|entry| {
+                let ty = entry
+                    .values()
+                    .next()
+                    .map_or_else(String::new, |value| value.data_type().to_string());
+                (ty, entry.len())
+            });
+            match entry {
+                Some((ty, 1)) => {
+                    let _ = writeln!(tree, "{indent}{part}: {ty} (1 sample)");
+                }
+                Some((ty, samples)) => {
+                    let _ = writeln!(tree, "{indent}{part}: {ty} ({samples} samples)");
+                }
+                None => {
+                    let _ = writeln!(tree, "{indent}{part}");
+                }
+            }
+        }
+        previous = parts;
+    }
+
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rerun::EntityPath;
+
+    use crate::log::{EntryLog, Timestamp};
+
+    #[test]
+    fn test_log_schemas() {
+        let mut log = EntryLog::new();
+        crate::wpilog::load::load_parts(
+            &[include_bytes!("../test_data/FRC_TBD_d225b5377c70a88d.wpilog").to_vec()],
+            &mut log,
+        );
+
+        let report = super::schema_report(&log);
+
+        assert!(report.contains("struct:Translation3d (24 bytes)\n"));
+        assert!(report.contains("  x: Double (8 bytes)\n"));
+    }
+
+    #[test]
+    fn struct_entity_tree() {
+        let mut log = EntryLog::new();
+        log.add_entry(
+            EntityPath::from_file_path(Path::new(".schema/struct:Translation2d")),
+            Timestamp(0),
+            "structschema",
+            b"double x;double y",
+        )
+        .unwrap();
+        for timestamp in [1_000, 2_000] {
+            log.add_entry(
+                EntityPath::from_file_path(Path::new("drive/target")),
+                Timestamp(timestamp),
+                "struct:Translation2d",
+                &[1.0f64.to_le_bytes(), 2.0f64.to_le_bytes()].concat(),
+            )
+            .unwrap();
+        }
+        log.add_entry(
+            EntityPath::from_file_path(Path::new("drive/speed")),
+            Timestamp(1_000),
+            "double",
+            &1.5f64.to_le_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            super::entity_tree(&log),
+            "drive\n  speed: Float64 (1 sample)\n  target\n    x: Float64 (2 samples)\n    y: Float64 (2 samples)\n"
+        );
+    }
+}
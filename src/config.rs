@@ -0,0 +1,754 @@
+use std::{num::NonZeroUsize, path::Path};
+
+use rerun::{
+    EntityPath, Timeline, ViewCoordinates,
+    external::anyhow::{self, Context as _, anyhow},
+};
+use serde::Deserialize;
+
+use crate::{
+    analysis::{commands::CommandsConfig, phase::MatchPhaseConfig},
+    nt::client::NtConfig,
+};
+
+/// The environment variable pointing at a JSON config file.
+pub const CONFIG_ENV_VAR: &str = "FIRSTRUN_CONFIG";
+
+/// Loader configuration, read from the JSON file named by [`CONFIG_ENV_VAR`].
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Components to synthesize for entities that don't carry in-band `.type`/`.components`
+    /// metadata.
+    pub components: Vec<ComponentOverride>,
+    /// How struct values are turned into entities.
+    pub struct_mode: StructMode,
+    /// The driver station entries the match phase is derived from.
+    pub match_phase: MatchPhaseConfig,
+    /// The command scheduler entries the running commands are derived from.
+    pub commands: CommandsConfig,
+    /// Entries whose values are times, logged on a timeline of their own.
+    pub time_columns: Vec<TimeColumnOverride>,
+    /// Pose entities that also get a trail of the positions they've passed through.
+    pub trails: Vec<TrailOverride>,
+    /// Which parts of array values are turned into entities.
+    pub arrays: Vec<ArrayOverride>,
+    /// Which entries are decoded at all.
+    pub filter: EntryFilter,
+    /// Whether each registered struct schema is also logged as a text document under
+    /// `<schema name>/.schema`.
+    pub log_schemas: bool,
+    /// What happens when an entity gets two values with the same timestamp.
+    pub duplicate_timestamps: DuplicateTimestamps,
+    /// The robot's outline, drawn for pose entities rendered as a `Footprint`.
+    pub footprint: Footprint,
+    /// How entities rendered as `ChassisSpeeds` are drawn.
+    pub velocity: VelocityConfig,
+    /// How entities rendered as an `Imu` are oriented.
+    pub imu: ImuConfig,
+    /// How field coordinates are mapped onto the viewer's.
+    pub coordinates: Coordinates,
+    /// Whether a default layout of views, picked by what the log contains, is sent with it.
+    ///
+    /// A plot's range is part of the layout, so the `min`/`max` bounds scalar metadata gives are
+    /// only applied with it, as sending one replaces the viewer's own layout.
+    pub blueprint: bool,
+    /// How many samples of each entity the live client keeps in memory, as its sessions never
+    /// end. Loaded files are always kept whole.
+    pub retention: Retention,
+    /// The field poses are drawn on.
+    pub field: FieldConfig,
+    /// What happens to values logged at a timestamp of 0.
+    pub early_timestamps: EarlyTimestamps,
+    /// How `int64` scalars too large to be exactly represented as an `f64` are logged.
+    pub large_integers: LargeIntegers,
+    /// String entries logged to the viewer's log panel, one entry per value.
+    pub text_logs: Vec<TextLogOverride>,
+    /// Whether the same log always converts to the same chunks, in the same order and with the
+    /// same ids, for comparing output across runs.
+    pub deterministic: bool,
+    /// The resolution of the robot's timeline.
+    pub time_precision: TimePrecision,
+    /// Which data records are loaded, by the wall-clock time they were logged at.
+    pub wall_clock: WallClockFilter,
+    /// How many threads the parts of a log are parsed on. Defaults to one per logical core, or
+    /// just one for small logs.
+    pub threads: Option<NonZeroUsize>,
+    /// Entries whose `boolean` and `boolean[]` values are written as 4-byte `int32`s instead of
+    /// a byte each, as some third-party loggers do.
+    pub int32_booleans: Vec<EntityPattern>,
+    /// Which numeric entries that aren't part of an entity are plotted at their own path.
+    pub plain_scalars: PlainScalars,
+    /// How many parts deep an entity path nested values are flattened into, after which the rest
+    /// of the value is logged as JSON text. Defaults to [`DEFAULT_MAX_DEPTH`].
+    pub max_depth: Option<usize>,
+    /// The width in seconds of the buckets records are counted in for
+    /// `$meta/records_per_second`, which isn't logged if unset.
+    pub record_rate: Option<f64>,
+    /// The NetworkTables server logged live while the viewer is open.
+    pub nt: NtConfig,
+}
+
+/// Deep enough for any real struct or JSON value, while bounding what a malformed one can create.
+pub const DEFAULT_MAX_DEPTH: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlainScalars {
+    /// Every one of them, so logs without `.type`/`.components` metadata still plot.
+    #[default]
+    All,
+    /// Only top-level entries, which couldn't have metadata.
+    TopLevel,
+}
+
+/// Logs smaller than this, in bytes, parse faster than threads for them start.
+const SMALL_LOG: usize = 8 * 1024 * 1024;
+
+/// Skips data records logged outside a range of wall-clock times, as derived from a log part's
+/// first `systemTime` sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct WallClockFilter {
+    pub since: Option<WallTime>,
+    pub until: Option<WallTime>,
+    /// What happens to records logged before `systemTime` was, whose wall-clock time isn't known.
+    pub unknown: UnknownWallClock,
+}
+
+impl WallClockFilter {
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        self.since.is_some() || self.until.is_some()
+    }
+
+    /// Whether a record logged at `time` (or an unknown time) is loaded.
+    #[must_use]
+    pub fn contains(&self, time: Option<WallTime>) -> bool {
+        let Some(time) = time else {
+            return !self.is_active() || self.unknown == UnknownWallClock::Keep;
+        };
+
+        self.since.is_none_or(|since| time >= since) && self.until.is_none_or(|until| time <= until)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownWallClock {
+    #[default]
+    Keep,
+    Drop,
+}
+
+/// A wall-clock time, in microseconds since the Unix epoch like `systemTime`.
+///
+/// Written as an RFC 3339 timestamp, e.g. `2025-03-15T18:45:00Z`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "String")]
+pub struct WallTime(pub i64);
+
+impl TryFrom<String> for WallTime {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl std::str::FromStr for WallTime {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_rfc3339(s).ok_or_else(|| anyhow!("{s:?} isn't an RFC 3339 timestamp"))
+    }
+}
+
+fn parse_rfc3339(s: &str) -> Option<WallTime> {
+    let number = |s: &str| -> Option<i64> {
+        s.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| s.parse().ok())
+            .flatten()
+    };
+
+    let (date, time) = s.split_once(['T', 't', ' '])?;
+    if date.len() != "yyyy-mm-dd".len() {
+        return None;
+    }
+    let [year, month, day] = <[&str; 3]>::try_from(date.split('-').collect::<Vec<_>>()).ok()?;
+    let (year, month, day) = (number(year)?, number(month)?, number(day)?);
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    // the offset from UTC is either `Z` or `+hh:mm`/`-hh:mm`
+    let (time, offset) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let at = time.rfind(['+', '-'])?;
+        let (hours, minutes) = time[at + 1..].split_once(':')?;
+        let (hours, minutes) = (number(hours)?, number(minutes)?);
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        let offset = hours * 3600 + minutes * 60;
+        (
+            &time[..at],
+            if &time[at..=at] == "-" {
+                -offset
+            } else {
+                offset
+            },
+        )
+    };
+
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    if time.len() != "hh:mm:ss".len() {
+        return None;
+    }
+    let [hour, minute, second] = <[&str; 3]>::try_from(time.split(':').collect::<Vec<_>>()).ok()?;
+    let (hour, minute, second) = (number(hour)?, number(minute)?, number(second)?);
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // only microseconds are kept, like `systemTime`
+    let micros = if fraction.is_empty() {
+        0
+    } else {
+        number(fraction)?;
+        format!("{fraction:0<6}")[..6].parse().ok()?
+    };
+
+    let seconds = days_from_civil(year, month, day)
+        .checked_mul(86_400)?
+        .checked_add(hour * 3600 + minute * 60 + second)?
+        .checked_sub(offset)?;
+    Some(WallTime(
+        seconds.checked_mul(1_000_000)?.checked_add(micros)?,
+    ))
+}
+
+/// How many days a month of a proleptic Gregorian year has.
+const fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since the Unix epoch of a proleptic Gregorian date.
+const fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The DataLog's timestamps are microseconds, which the viewer's duration timelines only take as
+/// nanoseconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimePrecision {
+    /// A duration timeline, with each timestamp scaled to nanoseconds.
+    #[default]
+    Nanoseconds,
+    /// A sequence timeline of the timestamps' own microseconds, shown as plain numbers.
+    Microseconds,
+}
+
+impl TimePrecision {
+    /// The timeline named `name` with this precision.
+    #[must_use]
+    pub fn timeline(self, name: &str) -> Timeline {
+        match self {
+            Self::Nanoseconds => Timeline::new_duration(name),
+            Self::Microseconds => Timeline::new_sequence(name),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LargeIntegers {
+    /// Plotted as the nearest `f64`, losing the low bits.
+    #[default]
+    Cast,
+    /// Logged as a text log entry of the exact value instead of being plotted.
+    Text,
+}
+
+/// Values logged with a timestamp of 0 are usually the defaults an entry starts with, and would
+/// otherwise land before everything else.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EarlyTimestamps {
+    /// The values keep their timestamps.
+    #[default]
+    Keep,
+    /// The values are moved to the earliest real timestamp.
+    Clamp,
+    /// The values are dropped.
+    Drop,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct FieldConfig {
+    /// In meters, along the X axis, which red alliance poses are mirrored across.
+    pub length: f64,
+    /// Whether poses logged relative to the red alliance's origin are mirrored to the blue
+    /// alliance's, so logs from either alliance line up with the same field.
+    pub blue_origin: bool,
+}
+
+impl Default for FieldConfig {
+    fn default() -> Self {
+        Self {
+            // the 2025 field
+            length: 16.54,
+            blue_origin: false,
+        }
+    }
+}
+
+/// Caps each entity's samples, evicting the oldest first. Unlimited by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    /// The most samples kept per entity.
+    pub max_samples: Option<usize>,
+    /// In seconds, how far before an entity's newest sample its samples are kept.
+    pub max_age: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Coordinates {
+    /// WPILib's field convention: X forward, Y left, Z up, with counterclockwise-positive angles.
+    ///
+    /// 3D views are told the scene is right-handed and Z up, and 2D geometry has its Y axis
+    /// flipped, since 2D views draw Y pointing down.
+    #[default]
+    WpilibField,
+    /// Values are logged as they are, leaving the viewer's defaults.
+    Raw,
+}
+
+impl Coordinates {
+    /// The coordinate system logged at the recording root, if any.
+    #[must_use]
+    pub fn view_coordinates(self) -> Option<ViewCoordinates> {
+        match self {
+            Self::WpilibField => Some(ViewCoordinates::RIGHT_HAND_Z_UP()),
+            Self::Raw => None,
+        }
+    }
+
+    /// Maps a field position onto a 2D view.
+    #[must_use]
+    pub fn to_2d(self, [x, y]: [f32; 2]) -> [f32; 2] {
+        match self {
+            Self::WpilibField => [x, -y],
+            Self::Raw => [x, y],
+        }
+    }
+}
+
+/// The size of the robot's rectangular footprint, in meters.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Footprint {
+    /// Along the robot's forward axis.
+    pub length: f32,
+    pub width: f32,
+}
+
+impl Default for Footprint {
+    fn default() -> Self {
+        Self {
+            length: 0.9,
+            width: 0.9,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct VelocityConfig {
+    /// The pose entity the robot's velocity is drawn from, or the origin if unset.
+    pub pose: Option<String>,
+    /// Seconds of travel each arrow is as long as, so 1 m/s is `scale` meters.
+    pub scale: f32,
+}
+
+impl Default for VelocityConfig {
+    fn default() -> Self {
+        Self {
+            pose: None,
+            scale: 1.0,
+        }
+    }
+}
+
+/// An IMU's orientation is read from a quaternion's `w`/`x`/`y`/`z` fields, or `yaw` and
+/// optionally `pitch` and `roll`, which are 0 if missing.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ImuConfig {
+    /// Where the IMU is mounted, the origin of its orientation.
+    pub mount: [f32; 3],
+    /// Whether yaw, pitch and roll are in degrees rather than radians.
+    pub degrees: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateTimestamps {
+    /// The later value replaces the earlier one.
+    #[default]
+    LastWins,
+    /// The later value is moved to the next free microsecond.
+    Nudge,
+}
+
+/// Selects entries by name. An empty include list includes everything that isn't excluded.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct EntryFilter {
+    pub include: Vec<EntityPattern>,
+    pub exclude: Vec<EntityPattern>,
+}
+
+impl EntryFilter {
+    #[must_use]
+    pub fn allows(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches_str(name)))
+            && !self.exclude.iter().any(|p| p.matches_str(name))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StructMode {
+    /// Each struct field becomes its own child entity.
+    #[default]
+    Flatten,
+    /// The struct is kept as a single entity holding an Arrow struct value.
+    Nested,
+}
+
+/// Renders every entity matching `pattern` as the given rerun components.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ComponentOverride {
+    pub pattern: EntityPattern,
+    pub components: Vec<String>,
+}
+
+/// Interprets the values of every entity matching `pattern` as a time on `timeline`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TimeColumnOverride {
+    pub pattern: EntityPattern,
+    pub timeline: String,
+    #[serde(default)]
+    pub unit: TimeUnit,
+}
+
+/// Draws the path of every pose entity matching `pattern`, capped to its most recent points and
+/// optionally to the points within a window of time.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TrailOverride {
+    pub pattern: EntityPattern,
+    /// Defaults to [`DEFAULT_TRAIL_POINTS`].
+    #[serde(default)]
+    pub max_points: Option<usize>,
+    /// In seconds.
+    #[serde(default)]
+    pub max_age: Option<f64>,
+    /// Breaks the trail where the pose went longer than this many seconds without an update, like
+    /// while the robot was off, rather than drawing a line across the gap.
+    #[serde(default)]
+    pub max_gap: Option<f64>,
+}
+
+/// Every update logs the whole trail, so an uncapped one grows quadratically with the log.
+pub const DEFAULT_TRAIL_POINTS: usize = 500;
+
+impl TrailOverride {
+    #[must_use]
+    pub fn max_points(&self) -> usize {
+        self.max_points.unwrap_or(DEFAULT_TRAIL_POINTS)
+    }
+}
+
+/// Logs the values of every string entry matching `pattern` as text log entries, with the level
+/// of the first severity prefix they start with.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TextLogOverride {
+    pub pattern: EntityPattern,
+    #[serde(default = "default_severities")]
+    pub severities: Vec<Severity>,
+}
+
+impl TextLogOverride {
+    /// Splits a line into its level and the message after its severity prefix, or returns `None`
+    /// if it has none.
+    #[must_use]
+    pub fn parse<'l>(&self, line: &'l str) -> Option<(&str, &'l str)> {
+        self.severities.iter().find_map(|severity| {
+            let message = line.strip_prefix(severity.prefix.as_str())?;
+            Some((severity.level.as_str(), message.trim_start()))
+        })
+    }
+}
+
+/// Lines starting with `prefix` are logged at `level`, one of rerun's `TextLogLevel`s.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Severity {
+    pub prefix: String,
+    pub level: String,
+}
+
+fn default_severities() -> Vec<Severity> {
+    [
+        ("[ERROR]", "ERROR"),
+        ("[WARNING]", "WARN"),
+        ("[WARN]", "WARN"),
+        ("[INFO]", "INFO"),
+        ("[DEBUG]", "DEBUG"),
+        ("[TRACE]", "TRACE"),
+    ]
+    .into_iter()
+    .map(|(prefix, level)| Severity {
+        prefix: prefix.to_owned(),
+        level: level.to_owned(),
+    })
+    .collect()
+}
+
+/// Controls how the array values of every entity matching `pattern` are materialized.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ArrayOverride {
+    pub pattern: EntityPattern,
+    #[serde(flatten)]
+    pub settings: ArraySettings,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ArraySettings {
+    /// Whether the array's `$length` is logged.
+    pub length: bool,
+    /// Whether each element gets its own entity, named by its index as `$0`, `$1`, ...
+    pub indices: bool,
+    /// The most elements that get their own entity, the rest are dropped.
+    pub max_indices: Option<usize>,
+}
+
+impl Default for ArraySettings {
+    fn default() -> Self {
+        Self {
+            length: true,
+            indices: true,
+            max_indices: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeUnit {
+    #[default]
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl TimeUnit {
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn to_nanos(self, value: f64) -> i64 {
+        let scale = match self {
+            Self::Seconds => 1e9,
+            Self::Milliseconds => 1e6,
+            Self::Microseconds => 1e3,
+            Self::Nanoseconds => 1.0,
+        };
+
+        (value * scale).round() as i64
+    }
+}
+
+impl Config {
+    /// Reads the config from [`CONFIG_ENV_VAR`], falling back to the default when it isn't set.
+    pub fn from_env() -> Result<Self, anyhow::Error> {
+        match std::env::var_os(CONFIG_ENV_VAR) {
+            Some(path) => Self::from_file(Path::new(&path)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
+        let contents = std::fs::read(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+
+        serde_json::from_slice(&contents)
+            .with_context(|| format!("failed to parse config file {}", path.display()))
+    }
+
+    /// The number of threads to parse a log of `bytes` on.
+    #[must_use]
+    pub fn threads(&self, bytes: usize) -> NonZeroUsize {
+        self.threads.unwrap_or_else(|| {
+            if bytes < SMALL_LOG {
+                NonZeroUsize::MIN
+            } else {
+                std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+            }
+        })
+    }
+
+    #[must_use]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth.unwrap_or(DEFAULT_MAX_DEPTH)
+    }
+
+    /// Gets the time column an entity's values should be logged as, if any pattern matches it.
+    #[must_use]
+    pub fn time_column(&self, entity: &EntityPath) -> Option<&TimeColumnOverride> {
+        self.time_columns.iter().find(|o| o.pattern.matches(entity))
+    }
+
+    /// Gets the trail settings for a pose entity, if any pattern matches it.
+    #[must_use]
+    pub fn trail(&self, entity: &EntityPath) -> Option<&TrailOverride> {
+        self.trails.iter().find(|o| o.pattern.matches(entity))
+    }
+
+    /// Gets the text log settings for a string entry, if any pattern matches it.
+    #[must_use]
+    pub fn text_log(&self, entity: &EntityPath) -> Option<&TextLogOverride> {
+        self.text_logs.iter().find(|o| o.pattern.matches(entity))
+    }
+
+    /// Gets how an entity's array values should be materialized.
+    #[must_use]
+    pub fn array_settings(&self, entity: &EntityPath) -> ArraySettings {
+        self.arrays
+            .iter()
+            .find(|o| o.pattern.matches(entity))
+            .map_or_else(ArraySettings::default, |o| o.settings)
+    }
+
+    /// Gets the overridden components for an entity, if any pattern matches it.
+    #[must_use]
+    pub fn component_overrides(&self, entity: &EntityPath) -> Option<&[String]> {
+        self.components
+            .iter()
+            .find(|o| o.pattern.matches(entity))
+            .map(|o| o.components.as_slice())
+    }
+}
+
+/// A glob over `/`-separated paths.
+///
+/// `*` matches anything within a single path segment, and `**` matches any number of segments.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct EntityPattern(pub String);
+
+impl EntityPattern {
+    #[must_use]
+    pub fn matches(&self, entity: &EntityPath) -> bool {
+        self.matches_str(&entity.to_string())
+    }
+
+    #[must_use]
+    pub fn matches_str(&self, path: &str) -> bool {
+        let pattern = self.0.trim_matches('/').split('/').collect::<Vec<_>>();
+        let path = path.trim_matches('/').split('/').collect::<Vec<_>>();
+
+        glob_segments(&pattern, &path)
+    }
+}
+
+fn glob_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, None) => true,
+        (Some((&"**", rest)), _) => {
+            glob_segments(rest, path) || (!path.is_empty() && glob_segments(pattern, &path[1..]))
+        }
+        (Some((segment, rest)), Some((part, path_rest))) => {
+            glob_segment(segment.as_bytes(), part.as_bytes()) && glob_segments(rest, path_rest)
+        }
+        _ => false,
+    }
+}
+
+fn glob_segment(pattern: &[u8], part: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => part.is_empty(),
+        Some((b'*', rest)) => (0..=part.len()).any(|i| glob_segment(rest, &part[i..])),
+        Some((c, rest)) => part.first() == Some(c) && glob_segment(rest, &part[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EntityPattern, WallTime};
+
+    #[test]
+    fn glob_patterns() {
+        let pattern = |p: &str| EntityPattern(p.to_owned());
+
+        assert!(pattern("drive/pose").matches_str("/drive/pose"));
+        assert!(!pattern("drive/pose").matches_str("drive/pose/x"));
+        assert!(pattern("drive/*").matches_str("drive/pose"));
+        assert!(pattern("drive/**").matches_str("drive/pose/translation/x"));
+        assert!(pattern("**/debug/**").matches_str("arm/debug/setpoint"));
+        assert!(pattern("*/mod*").matches_str("swerve/module0"));
+        assert!(!pattern("drive/**").matches_str("arm/pose"));
+    }
+
+    #[test]
+    fn rfc3339_wall_times() {
+        let time = |s: &str| s.parse::<WallTime>().ok().map(|t| t.0);
+
+        assert_eq!(time("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(time("2025-03-15T18:45:00Z"), Some(1_742_064_300_000_000));
+        assert_eq!(time("2025-03-15T18:45:00.25Z"), Some(1_742_064_300_250_000));
+        assert_eq!(
+            time("2025-03-15T13:45:00-05:00"),
+            time("2025-03-15T18:45:00Z")
+        );
+        assert_eq!(time("2025-03-15 18:45:00z"), time("2025-03-15T18:45:00Z"));
+
+        assert_eq!(time("2025-03-15"), None);
+        assert_eq!(time("2025-13-01T00:00:00Z"), None);
+        assert_eq!(time("2025-03-15T18:45:00"), None);
+    }
+
+    #[test]
+    fn rfc3339_out_of_range() {
+        let time = |s: &str| s.parse::<WallTime>().ok().map(|t| t.0);
+
+        assert_eq!(time("2025-02-29T00:00:00Z"), None);
+        assert_eq!(time("2025-04-31T00:00:00Z"), None);
+        assert!(time("2024-02-29T00:00:00Z").is_some());
+        assert!(time("2000-02-29T00:00:00Z").is_some());
+        assert_eq!(time("1900-02-29T00:00:00Z"), None);
+
+        assert_eq!(time("2025-03-15T18:45:00+24:00"), None);
+        assert_eq!(time("2025-03-15T18:45:00+05:60"), None);
+        // used to overflow while the offset was scaled to seconds
+        assert_eq!(time("2025-03-15T18:45:00+9999999999999999:00"), None);
+        assert_eq!(time("2025-03-15T18:45:00-00:9999999999999999"), None);
+    }
+}
@@ -1,73 +1,793 @@
-use std::{fmt::Debug, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Debug,
+    path::Path,
+    sync::Arc,
+};
 
 use rerun::{
-    ApplicationId, ComponentBatch, EntityPath, Loggable, StoreId, TimePoint, Timeline,
+    ApplicationId, Arrows3D, BarChart, Boxes3D, Clear, ComponentBatch, EntityPath, LineStrips2D,
+    Loggable, Scalars, SeriesLines, StoreId, TextDocument, TextLog, TimeColumn, TimePoint,
+    Timeline,
+    components::MediaType,
     external::{
         anyhow::{self, bail},
         arrow::{
             self,
-            array::{ArrayData, AsArray, FixedSizeListArray, StructArray},
-            datatypes::{DataType, Field, Float64Type, Utf8Type},
+            array::{Array, ArrayRef, AsArray, StructArray},
+            datatypes::{DataType, Field, Float64Type, Int64Type, Utf8Type},
         },
         nohash_hasher::IntMap,
         re_chunk::ChunkBuilder,
         re_log,
     },
-    log::{Chunk, RowId},
+    log::{Chunk, ChunkId, RowId},
+    time::TimeInt,
 };
 
-use crate::log::{EntryLog, Timestamp};
+use serde::Deserialize;
+
+use crate::{
+    config::{
+        Coordinates, Footprint, LargeIntegers, PlainScalars, TimeColumnOverride, TrailOverride,
+    },
+    log::{EntryLog, Timestamp, array_element, array_length},
+};
 
 trait DebuggableComponent: ComponentBatch + Debug {}
 impl<T: ComponentBatch + Debug> DebuggableComponent for T {}
 
+fn latest_value(
+    log: &EntryLog,
+    timestamp: Timestamp,
+    key: &EntityPath,
+) -> Result<ArrayRef, anyhow::Error> {
+    log.get_latest_from(key, timestamp)
+        .map(|(_, t)| t.clone())
+        .ok_or_else(|| anyhow::anyhow!("couldn't find latest value for {key} at {timestamp:?}"))
+}
+
+fn latest_f64(
+    log: &EntryLog,
+    timestamp: Timestamp,
+    key: &EntityPath,
+) -> Result<f64, anyhow::Error> {
+    let array = arrow::compute::cast(&latest_value(log, timestamp, key)?, &DataType::Float64)?;
+
+    array
+        .as_primitive::<Float64Type>()
+        .iter()
+        .next()
+        .flatten()
+        .ok_or_else(|| anyhow::anyhow!("{key} has no value at {timestamp:?}"))
+}
+
 fn retrieve_component(
     log: &EntryLog,
     timestamp: Timestamp,
     parent: &EntityPath,
     component: &str,
-) -> Result<Box<dyn DebuggableComponent>, anyhow::Error> {
+    mirror: Option<f64>,
+) -> Result<Vec<Box<dyn DebuggableComponent>>, anyhow::Error> {
     let key = parent.join(&EntityPath::from_file_path(Path::new(component)));
 
     if component == "Scalar" {
-        let array = arrow::compute::cast(
-            &log.get_latest_from(&key, timestamp)
-                .map(|(_, t)| t.clone())
-                .ok_or_else(|| {
-                    anyhow::anyhow!("couldn't find latest value for {key} at {timestamp:?}")
-                })?,
-            &DataType::Float64,
-        )?;
+        let array = arrow::compute::cast(&latest_value(log, timestamp, &key)?, &DataType::Float64)?;
 
-        Ok(Box::new(rerun::components::Scalar::from_arrow(
+        Ok(vec![Box::new(rerun::components::Scalar::from_arrow(
             array.as_primitive::<Float64Type>(),
-        )?))
+        )?)])
     } else if component == "Point3d" {
         let get = |val: &str| {
-            let key = key.join(&EntityPath::from_single_string(val));
-            Ok::<_, anyhow::Error>(
-                log.get_latest_from(&key, timestamp)
-                    .map(|(_, t)| t.clone())
-                    .ok_or_else(|| {
-                        anyhow::anyhow!("couldn't find latest value for {key} at {timestamp:?}")
-                    })?
-                    .to_data(),
-            )
-        };
-        let mut fields = ArrayData::builder(DataType::Float32)
-            .len(3)
-            .add_child_data(get("x")?)
-            .add_child_data(get("y")?)
-            .add_child_data(get("z")?)
-            .build()?;
-        let array = FixedSizeListArray::from(fields);
-
-        Ok(Box::new(rerun::components::Position3D::from_arrow(&array)?))
+            latest_f64(
+                log,
+                timestamp,
+                &key.join(&EntityPath::from_single_string(val)),
+            )
+        };
+
+        Ok(vec![Box::new(rerun::components::Position3D::new(
+            get("x")? as f32,
+            get("y")? as f32,
+            get("z")? as f32,
+        ))])
+    } else if component == "Pose2d" {
+        let (x, y, theta) = pose2d(log, timestamp, parent, mirror)?;
+
+        Ok(vec![
+            Box::new(rerun::components::Translation3D::new(
+                x as f32, y as f32, 0.0,
+            )),
+            Box::new(rerun::components::RotationAxisAngle::new(
+                [0.0, 0.0, 1.0],
+                rerun::Angle::from_radians(theta as f32),
+            )),
+        ])
+    } else if component == "Rotation2d" {
+        let radians = latest_f64(
+            log,
+            timestamp,
+            &parent.join(&EntityPath::from_single_string("value")),
+        )?;
+        let radians = if mirror.is_some() {
+            std::f64::consts::PI - radians
+        } else {
+            radians
+        };
+
+        // a heading reads better on a plot in degrees
+        Ok(vec![Box::new(rerun::components::Scalar::from(
+            radians.to_degrees(),
+        ))])
+    } else if component == "Imu" {
+        let [x, y, z, w] = imu_orientation(log, timestamp, parent)?;
+        let [mount_x, mount_y, mount_z] = log.config().imu.mount;
+
+        Ok(vec![
+            Box::new(rerun::components::Translation3D::new(
+                mount_x, mount_y, mount_z,
+            )),
+            Box::new(rerun::components::RotationQuat::from(
+                rerun::Quaternion::from_xyzw([x as f32, y as f32, z as f32, w as f32]),
+            )),
+        ])
     } else {
         bail!("unknown component");
     }
 }
 
+/// Reads the latest orientation of an IMU entity as an `[x, y, z, w]` quaternion.
+fn imu_orientation(
+    log: &EntryLog,
+    timestamp: Timestamp,
+    entity: &EntityPath,
+) -> Result<[f64; 4], anyhow::Error> {
+    let get = |field: &str| {
+        latest_f64(
+            log,
+            timestamp,
+            &entity.join(&EntityPath::from_single_string(field)),
+        )
+    };
+
+    if let Ok(w) = get("w") {
+        return Ok([get("x")?, get("y")?, get("z")?, w]);
+    }
+
+    let angle = |field: &str| {
+        get(field).map(|angle| {
+            if log.config().imu.degrees {
+                angle.to_radians()
+            } else {
+                angle
+            }
+        })
+    };
+    let yaw = angle("yaw")?;
+    // a gyro that only measures heading is level
+    let (pitch, roll) = (angle("pitch").unwrap_or(0.0), angle("roll").unwrap_or(0.0));
+
+    Ok(euler_quaternion(roll, pitch, yaw))
+}
+
+/// Converts a WPILib `Rotation3d(roll, pitch, yaw)` to an `[x, y, z, w]` quaternion.
+///
+/// Like WPILib, the rotations are extrinsic: about X by `roll`, then about Y by `pitch`, then about
+/// Z by `yaw`, all fixed axes, counterclockwise positive.
+fn euler_quaternion(roll: f64, pitch: f64, yaw: f64) -> [f64; 4] {
+    let (sr, cr) = (roll / 2.0).sin_cos();
+    let (sp, cp) = (pitch / 2.0).sin_cos();
+    let (sy, cy) = (yaw / 2.0).sin_cos();
+
+    [
+        sr * cp * cy - cr * sp * sy,
+        cr * sp * cy + sr * cp * sy,
+        cr * cp * sy - sr * sp * cy,
+        cr * cp * cy + sr * sp * sy,
+    ]
+}
+
+/// The largest magnitude an `f64` holds every integer up to.
+const MAX_EXACT_F64_INT: u64 = 1 << f64::MANTISSA_DIGITS;
+
+/// Reads the latest value of an `int64` entry if it's too large to be represented as an `f64`.
+fn inexact_int64(log: &EntryLog, timestamp: Timestamp, key: &EntityPath) -> Option<i64> {
+    let value = latest_value(log, timestamp, key).ok()?;
+
+    value
+        .as_primitive_opt::<Int64Type>()?
+        .iter()
+        .next()
+        .flatten()
+        .filter(|value| value.unsigned_abs() > MAX_EXACT_F64_INT)
+}
+
+/// A sample of a scalar series, or the exact value of an `int64` too large to plot exactly when
+/// those are logged as text instead.
+enum ScalarSample {
+    Plotted(f64),
+    Text(i64),
+}
+
+/// Reads the latest value of `key` as a sample of `entity`'s series, warning the first time an
+/// entity has an `int64` too large to plot exactly.
+fn scalar_sample(
+    log: &EntryLog,
+    timestamp: Timestamp,
+    key: &EntityPath,
+    entity: &EntityPath,
+    imprecise: &mut HashSet<EntityPath>,
+) -> Result<ScalarSample, anyhow::Error> {
+    if let Some(exact) = inexact_int64(log, timestamp, key) {
+        if imprecise.insert(entity.clone()) {
+            re_log::warn!(
+                "{key} has int64 values too large to plot exactly, like {exact} at {:.3}s",
+                timestamp.as_secs_f64()
+            );
+        }
+
+        if log.config().large_integers == LargeIntegers::Text {
+            return Ok(ScalarSample::Text(exact));
+        }
+    }
+
+    latest_f64(log, timestamp, key).map(ScalarSample::Plotted)
+}
+
+/// Logs the exact value of an `int64` too large to plot as a row of `entity`'s text log.
+fn log_exact_int(
+    entities: &mut IntMap<EntityPath, ChunkBuilder>,
+    entity: &EntityPath,
+    time: TimePoint,
+    exact: i64,
+) {
+    let builder = || Chunk::builder(entity.clone());
+    let chunk = entities.entry(entity.clone()).or_insert_with(builder);
+    replace_with::replace_with(chunk, builder, |c| {
+        c.with_archetype(RowId::new(), time, &TextLog::new(exact.to_string()))
+    });
+}
+
+/// Reads the elements of a numeric array published at `timestamp`.
+fn numeric_array(
+    log: &EntryLog,
+    entity: &EntityPath,
+    timestamp: Timestamp,
+) -> Result<Vec<f64>, anyhow::Error> {
+    let at = |key: &EntityPath| {
+        log.get_entry(key)
+            .and_then(|entry| entry.get(&timestamp))
+            .ok_or_else(|| anyhow::anyhow!("{key} wasn't published at {timestamp:?}"))
+    };
+
+    let length = at(&array_length(entity))?
+        .as_primitive_opt::<Int64Type>()
+        .and_then(|length| length.iter().next().flatten())
+        .ok_or_else(|| anyhow::anyhow!("{entity} has no array length"))?;
+
+    (0..usize::try_from(length)?)
+        .map(|i| {
+            let key = array_element(entity, i);
+            let value = at(&key)?;
+            if !value.data_type().is_numeric() {
+                bail!("{key} isn't numeric, it's {}", value.data_type());
+            }
+
+            arrow::compute::cast(value, &DataType::Float64)?
+                .as_primitive::<Float64Type>()
+                .iter()
+                .next()
+                .flatten()
+                .ok_or_else(|| anyhow::anyhow!("{key} has no value"))
+        })
+        .collect()
+}
+
+/// The field length red alliance poses are mirrored across to put them on the blue alliance's
+/// origin, or `None` if poses are drawn as they were logged.
+fn blue_origin_mirror(log: &EntryLog) -> Option<f64> {
+    let field = log.config().field;
+    if !field.blue_origin {
+        return None;
+    }
+
+    match crate::analysis::fms::red_alliance(log) {
+        Some(red) => red.then_some(field.length),
+        None => {
+            re_log::warn!("the log doesn't say which alliance it's from, so poses aren't flipped");
+            None
+        }
+    }
+}
+
+/// Mirrors a pose across a field of the given length, as seen from the other alliance's origin.
+fn mirror_pose((x, y, theta): (f64, f64, f64), length: f64) -> (f64, f64, f64) {
+    (length - x, y, std::f64::consts::PI - theta)
+}
+
+/// Reads the latest `(x, y, heading)` of a pose entity, mirrored across a field of length
+/// `mirror` if given.
+fn pose2d(
+    log: &EntryLog,
+    timestamp: Timestamp,
+    parent: &EntityPath,
+    mirror: Option<f64>,
+) -> Result<(f64, f64, f64), anyhow::Error> {
+    // a WPILib `Pose2d` flattens to `translation/{x,y}` and `rotation/value` (radians)
+    let get = |path: &str| {
+        latest_f64(
+            log,
+            timestamp,
+            &parent.join(&EntityPath::from_file_path(Path::new(path))),
+        )
+    };
+
+    let pose = (
+        get("translation/x")?,
+        get("translation/y")?,
+        get("rotation/value")?,
+    );
+
+    Ok(mirror.map_or(pose, |length| mirror_pose(pose, length)))
+}
+
+/// A flat box the size of the robot, centered on its pose and turned to its heading.
+///
+/// It's placed in the parent's space, so the entity shouldn't also be rendered as a `Pose2d`,
+/// which would transform it a second time.
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn footprint_box((x, y, theta): (f64, f64, f64), footprint: &Footprint) -> Boxes3D {
+    Boxes3D::from_centers_and_half_sizes(
+        [(x as f32, y as f32, 0.0)],
+        [(footprint.length / 2.0, footprint.width / 2.0, 0.0)],
+    )
+    .with_rotation_axis_angles([rerun::components::PoseRotationAxisAngle::new(
+        [0.0, 0.0, 1.0],
+        rerun::Angle::from_radians(theta as f32),
+    )])
+}
+
+/// Arrows for a `ChassisSpeeds` entity's robot-relative `vx`/`vy`/`omega`: one for the robot's
+/// velocity, from its pose, and one along Z as long as its turn rate, counterclockwise up.
+#[allow(clippy::cast_possible_truncation)]
+fn chassis_speeds_arrows(
+    log: &EntryLog,
+    timestamp: Timestamp,
+    entity: &EntityPath,
+    mirror: Option<f64>,
+) -> Result<Arrows3D, anyhow::Error> {
+    let get = |field: &str| {
+        latest_f64(
+            log,
+            timestamp,
+            &entity.join(&EntityPath::from_single_string(field)),
+        )
+    };
+    let (vx, mut vy, mut omega) = (get("vx")?, get("vy")?, get("omega")?);
+
+    let config = &log.config().velocity;
+    let pose = config.pose.as_ref().and_then(|pose| {
+        pose2d(
+            log,
+            timestamp,
+            &EntityPath::from_file_path(Path::new(pose)),
+            mirror,
+        )
+        .ok()
+    });
+    let (x, y, theta) = pose.unwrap_or_default();
+
+    // mirroring the field turns the robot's left into its right
+    if mirror.is_some() {
+        vy = -vy;
+        omega = -omega;
+    }
+
+    let (sin, cos) = theta.sin_cos();
+    let scale = f64::from(config.scale);
+    let velocity = [
+        ((vx * cos - vy * sin) * scale) as f32,
+        ((vx * sin + vy * cos) * scale) as f32,
+        0.0,
+    ];
+    let origin = [x as f32, y as f32, 0.0];
+
+    Ok(
+        Arrows3D::from_vectors([velocity, [0.0, 0.0, (omega * scale) as f32]])
+            .with_origins([origin, origin]),
+    )
+}
+
+/// The motion of a `Twist2d` or `Twist3d` entity: an arrow from the origin along its
+/// `dx`/`dy`/`dz`, and its rotation in degrees, counterclockwise positive.
+///
+/// A `Twist3d` rotation is the angle of its `rx`/`ry`/`rz` rotation vector, so it's never negative.
+#[allow(clippy::cast_possible_truncation)]
+fn twist_arrow(
+    log: &EntryLog,
+    timestamp: Timestamp,
+    entity: &EntityPath,
+    mirror: Option<f64>,
+) -> Result<(Arrows3D, f64), anyhow::Error> {
+    let get = |field: &str| {
+        latest_f64(
+            log,
+            timestamp,
+            &entity.join(&EntityPath::from_single_string(field)),
+        )
+    };
+
+    let (dx, mut dy) = (get("dx")?, get("dy")?);
+    // a `Twist2d` has no `dz`, and turns only about Z
+    let (dz, angle) = match get("dtheta") {
+        Ok(dtheta) => (0.0, if mirror.is_some() { -dtheta } else { dtheta }),
+        Err(_) => (
+            get("dz")?,
+            f64::hypot(get("rx")?, get("ry")?).hypot(get("rz")?),
+        ),
+    };
+
+    // mirroring the field turns the robot's left into its right
+    if mirror.is_some() {
+        dy = -dy;
+    }
+
+    Ok((
+        Arrows3D::from_vectors([[dx as f32, dy as f32, dz as f32]]),
+        angle.to_degrees(),
+    ))
+}
+
+/// Builds a single chunk holding every sample of a scalar entity as one column.
+fn scalar_column_chunk(
+    entity: EntityPath,
+    timeline: Timeline,
+    mut samples: Vec<(Timestamp, f64)>,
+) -> Result<Chunk, anyhow::Error> {
+    samples.sort_by_key(|(timestamp, _)| *timestamp);
+    // metadata changing alongside the value would otherwise duplicate the sample
+    samples.dedup_by_key(|(timestamp, _)| *timestamp);
+
+    let (timestamps, values): (Vec<_>, Vec<_>) = samples.into_iter().unzip();
+
+    scalar_columns_chunk(
+        entity,
+        vec![TimeColumn::new(
+            Some(true),
+            timeline,
+            robot_times(timeline, &timestamps)?.into(),
+        )],
+        values,
+    )
+}
+
+/// Display hints a scalar entry's metadata may carry, e.g.
+/// `{"name":"Left speed","unit":"m/s","color":"#ff8000","min":0,"max":5}`.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+struct ScalarHints {
+    name: Option<String>,
+    unit: Option<String>,
+    /// `#rrggbb`
+    color: Option<String>,
+    /// The plot's range, which only the blueprint can set, so it's ignored unless
+    /// [`Config::blueprint`](crate::config::Config::blueprint) is.
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl ScalarHints {
+    /// Reads the hints of a scalar entity from the metadata of its value, or of the entity itself.
+    fn from_log(log: &EntryLog, entity: &EntityPath) -> Option<Self> {
+        let value = entity.join(&EntityPath::from_single_string("Scalar"));
+
+        [&value, entity]
+            .into_iter()
+            .filter_map(|key| log.metadata(key))
+            // absent and malformed hints are both ignored
+            .find_map(|metadata| serde_json::from_str::<Self>(metadata).ok())
+            .filter(|hints| *hints != Self::default())
+    }
+
+    /// Builds a static chunk naming and coloring the series, so it's logged once rather than with
+    /// every sample.
+    ///
+    /// Bounds are a property of the plot rather than the series, so they're left to the blueprint;
+    /// see [`scalar_range`].
+    fn to_chunk(&self, entity: EntityPath) -> Result<Chunk, anyhow::Error> {
+        let mut builder = Chunk::builder(entity.clone());
+
+        let mut lines = SeriesLines::new();
+        if self.name.is_some() || self.unit.is_some() {
+            let name = self.name.clone().unwrap_or_else(|| {
+                entity.last().map_or_else(
+                    || entity.to_string(),
+                    |part| part.unescaped_str().to_owned(),
+                )
+            });
+            let name = match &self.unit {
+                Some(unit) => format!("{name} ({unit})"),
+                None => name,
+            };
+            lines = lines.with_names([name]);
+        }
+        if let Some(color) = &self.color {
+            match hex_color(color) {
+                Some(color) => lines = lines.with_colors([color]),
+                None => re_log::warn!("{entity} has color {color:?}, which isn't #rrggbb"),
+            }
+        }
+        if self.name.is_some() || self.unit.is_some() || self.color.is_some() {
+            builder = builder.with_archetype(RowId::new(), TimePoint::default(), &lines);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// The plot axis range a scalar entity's metadata asks for, if it gives both ends.
+#[must_use]
+pub fn scalar_range(log: &EntryLog, entity: &EntityPath) -> Option<(f64, f64)> {
+    let hints = ScalarHints::from_log(log, entity)?;
+    Some((hints.min?, hints.max?))
+}
+
+/// Parses a `#rrggbb` color.
+pub(crate) fn hex_color(color: &str) -> Option<rerun::Color> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    let [_, r, g, b] = rgb.to_be_bytes();
+
+    Some(rerun::Color::from_rgb(r, g, b))
+}
+
+fn robot_times(timeline: Timeline, timestamps: &[Timestamp]) -> Result<Vec<i64>, anyhow::Error> {
+    Ok(timestamps
+        .iter()
+        .map(|timestamp| timestamp.time_int(timeline.typ()).map(TimeInt::as_i64))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+fn scalar_columns_chunk(
+    entity: EntityPath,
+    time_columns: Vec<TimeColumn>,
+    values: Vec<f64>,
+) -> Result<Chunk, anyhow::Error> {
+    let components = Scalars::new(values)
+        .columns_of_unit_batches()?
+        .map(|column| (column.descriptor, column.list_array))
+        .collect();
+
+    Ok(Chunk::from_auto_row_ids(
+        ChunkId::new(),
+        entity,
+        time_columns
+            .into_iter()
+            .map(|column| (*column.timeline().name(), column))
+            .collect(),
+        components,
+    )?)
+}
+
+/// Builds a chunk for an entity whose values are times, placing each sample on the derived
+/// timeline as well as the robot's.
+fn time_column_chunk(
+    entity: EntityPath,
+    timeline: Timeline,
+    derived: &TimeColumnOverride,
+    mut samples: Vec<(Timestamp, f64)>,
+) -> Result<Chunk, anyhow::Error> {
+    samples.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let (timestamps, values): (Vec<_>, Vec<_>) = samples.into_iter().unzip();
+    let derived_times = values
+        .iter()
+        .map(|value| derived.unit.to_nanos(*value))
+        .collect::<Vec<_>>();
+
+    scalar_columns_chunk(
+        entity,
+        vec![
+            TimeColumn::new(
+                Some(true),
+                timeline,
+                robot_times(timeline, &timestamps)?.into(),
+            ),
+            TimeColumn::new(
+                None,
+                Timeline::new_duration(derived.timeline.as_str()),
+                derived_times.into(),
+            ),
+        ],
+        values,
+    )
+}
+
+/// Collects every position a pose entity has been at up to `until`, oldest first, along with when
+/// it moved there.
+#[allow(clippy::cast_possible_truncation)]
+fn pose_positions(
+    log: &EntryLog,
+    entity: &EntityPath,
+    until: Timestamp,
+    mirror: Option<f64>,
+) -> Result<Vec<(Timestamp, [f32; 2])>, anyhow::Error> {
+    let x = entity.join(&EntityPath::from_file_path(Path::new("translation/x")));
+    let y = entity.join(&EntityPath::from_file_path(Path::new("translation/y")));
+
+    // a move along a single axis only updates one of the coordinates
+    [&x, &y]
+        .into_iter()
+        .filter_map(|key| log.get_entry(key))
+        .flat_map(|entry| entry.range(..=until).map(|(t, _)| *t))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|t| {
+            let x = latest_f64(log, t, &x)?;
+            let x = mirror.map_or(x, |length| length - x);
+            let point = log
+                .config()
+                .coordinates
+                .to_2d([x as f32, latest_f64(log, t, &y)? as f32]);
+            Ok((t, point))
+        })
+        .collect()
+}
+
+/// Picks the positions the trail shows at `timestamp` out of every position the pose has been at,
+/// split into separate strips wherever it went longer than the trail's `max_gap` without an
+/// update.
+///
+/// The positions are only looked up once per entity, so each row only costs the length of its
+/// trail.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn pose_trail(
+    positions: &[(Timestamp, [f32; 2])],
+    timestamp: Timestamp,
+    trail: &TrailOverride,
+) -> Vec<Vec<[f32; 2]>> {
+    // microseconds
+    let since = trail.max_age.map_or(Timestamp(0), |age| {
+        Timestamp(timestamp.0.saturating_sub((age * 1e6) as u64))
+    });
+    let max_gap = trail.max_gap.map(|gap| (gap.max(0.0) * 1e6) as u64);
+
+    let end = positions.partition_point(|(t, _)| *t <= timestamp);
+    let start = positions
+        .partition_point(|(t, _)| *t < since)
+        .max(end.saturating_sub(trail.max_points()));
+
+    let mut strips = Vec::<Vec<[f32; 2]>>::new();
+    let mut previous = None::<Timestamp>;
+    for &(t, point) in &positions[start..end] {
+        let gap = previous
+            .zip(max_gap)
+            .is_some_and(|(previous, max_gap)| t.0 - previous.0 > max_gap);
+        match strips.last_mut() {
+            Some(strip) if !gap => strip.push(point),
+            _ => strips.push(vec![point]),
+        }
+        previous = Some(t);
+    }
+
+    strips
+}
+
+/// The meta-entry naming what kind of value an entity is.
+///
+/// This is the `.type` entry WPILib's `Sendable`s publish, so theirs is read as is, and renaming
+/// it would stop them from being recognized. NetworkTables treats names starting with `.` as
+/// hidden rather than forbidding them, so only a string under one of these names is taken as
+/// metadata; a field named `type`, or a number named `.type`, is logged like any other.
+pub const TYPE_META_ENTRY: &str = ".type";
+/// The meta-entry listing the components an entity should be rendered as.
+pub const COMPONENTS_META_ENTRY: &str = ".components";
+
+fn meta_entry(parent: &EntityPath, name: &str) -> EntityPath {
+    parent.join(&EntityPath::from_single_string(name))
+}
+
+/// Whether `value` at `key` is one of an entity's meta-entries, rather than a field of it.
+fn is_meta_entry(key: &EntityPath, value: &dyn Array) -> bool {
+    value.data_type() == &DataType::Utf8
+        && key.last().is_some_and(|part| {
+            matches!(
+                part.unescaped_str(),
+                TYPE_META_ENTRY | COMPONENTS_META_ENTRY
+            )
+        })
+}
+
+/// Finds the entity a changed key belongs to, along with the components it should be rendered as.
+///
+/// In-band `.type`/`.components` metadata takes precedence over the configured overrides.
+fn entity_components(log: &EntryLog, key: &EntityPath) -> Option<(EntityPath, Vec<String>)> {
+    // only the root has no parent, and it has no meta-entries of its own
+    let parent = key.parent()?;
+
+    let ty = log
+        .get_latest_entry(&meta_entry(&parent, TYPE_META_ENTRY))
+        .map(|(_, t)| &**t)
+        .and_then(|a| a.as_bytes_opt::<Utf8Type>());
+
+    let components = log
+        .get_latest_entry(&meta_entry(&parent, COMPONENTS_META_ENTRY))
+        .map(|(_, t)| t.clone());
+    let components = components
+        .as_ref()
+        .and_then(|a| a.as_bytes_opt::<Utf8Type>());
+
+    match (ty, components) {
+        (Some(ty), Some(components)) if ty.iter().next().flatten() == Some("Entity") => {
+            return Some((
+                parent,
+                components.iter().flatten().map(str::to_owned).collect(),
+            ));
+        }
+        _ => {}
+    }
+
+    // the changed key may be nested arbitrarily deep inside the overridden entity
+    let mut entity = Some(key.clone());
+    while let Some(path) = entity {
+        if let Some(components) = log.config().component_overrides(&path) {
+            return Some((path, components.to_vec()));
+        }
+        entity = path.parent();
+    }
+
+    // lone headings and twists are recognized by their struct type, as they're rarely given
+    // metadata
+    match log.struct_type(&parent)? {
+        ty @ ("struct:Rotation2d" | "struct:Twist2d" | "struct:Twist3d") => {
+            let component = ty.trim_start_matches("struct:").to_owned();
+            Some((parent, vec![component]))
+        }
+        _ => None,
+    }
+}
+
+/// Builds the static chunk orienting 3D views for `coordinates`, if they need orienting.
+///
+/// It's the same for the whole recording, so it's logged once rather than with every batch of
+/// changes.
+#[must_use]
+pub fn view_coordinates_chunk(coordinates: Coordinates) -> Option<Chunk> {
+    let view = coordinates.view_coordinates()?;
+
+    match Chunk::builder(EntityPath::root())
+        .with_archetype(RowId::new(), TimePoint::default(), &view)
+        .build()
+    {
+        Ok(chunk) => Some(chunk),
+        Err(e) => {
+            re_log::error!("error building view coordinates chunk: {e}");
+            None
+        }
+    }
+}
+
+/// The time point of `timestamp` on `timeline`, or `None` if it doesn't fit there, warning the
+/// first time a sample of `entity` doesn't.
+fn time_point(
+    timestamp: Timestamp,
+    timeline: Timeline,
+    entity: &EntityPath,
+    untimed: &mut HashSet<EntityPath>,
+) -> Option<TimePoint> {
+    match timestamp.time_point(timeline) {
+        Ok(time) => Some(time),
+        Err(e) => {
+            if untimed.insert(entity.clone()) {
+                re_log::warn!(
+                    "skipping samples of {entity} that don't fit on the timeline, like at {:.3}s: {e}",
+                    timestamp.as_secs_f64()
+                );
+            }
+            None
+        }
+    }
+}
+
 pub fn log_changes_to_chunks(
     store_id: &StoreId,
     application_id: &ApplicationId,
@@ -75,57 +795,1786 @@ pub fn log_changes_to_chunks(
     log: &mut EntryLog,
 ) -> Vec<Chunk> {
     let mut entities = IntMap::<EntityPath, ChunkBuilder>::default();
+    // scalars are batched into a single column per entity rather than a row per sample
+    let mut scalars = IntMap::<EntityPath, Vec<(Timestamp, f64)>>::default();
+    let mut time_columns = IntMap::<EntityPath, Vec<(Timestamp, f64)>>::default();
+    let mut trails = IntMap::<EntityPath, Vec<Timestamp>>::default();
+    // every element of an array changes at once, but the whole array is one bar chart
+    let mut bar_charts = IntMap::<EntityPath, Vec<Timestamp>>::default();
+    let mirror = blue_origin_mirror(log);
+    // warned about once each
+    let mut imprecise = HashSet::<EntityPath>::new();
+    let mut untimed = HashSet::<EntityPath>::new();
+    // the keys whose values became components, for the log's coverage
+    let mut rendered = HashSet::<EntityPath>::new();
+    let mut entity_rows = HashSet::<(EntityPath, Timestamp)>::new();
+
+    let mut changed = log.get_changed();
+    if log.config().deterministic {
+        // rows are added in this order
+        changed.sort_unstable_by(|(a, a_time, _), (b, b_time, _)| (a_time, a).cmp(&(b_time, b)));
+    }
 
-    let changed = log.get_changed();
-
-    for (key, timestamp, _val) in log.get_changed() {
-        let builder = || Chunk::builder(key.clone());
-
-        let parent = key.parent().unwrap_or_else(|| key.clone());
-
-        let ty = log
-            .get_latest_entry(&parent.join(&EntityPath::from_single_string(".type")))
-            .map(|(_, t)| &**t)
-            .and_then(|a| a.as_bytes_opt::<Utf8Type>());
-
-        let components = log
-            .get_latest_entry(&parent.join(&EntityPath::from_single_string(".components")))
-            .map(|(_, t)| t.clone());
-        let components = components
-            .as_ref()
-            .and_then(|a| a.as_bytes_opt::<Utf8Type>());
-
-        match (ty, components) {
-            (Some(ty), Some(components)) if ty.iter().next().unwrap().unwrap() == "Entity" => {
-                let chunk = entities.entry(parent.clone()).or_insert_with(builder);
-
-                re_log::info!("Skipping entity entry: {}; {:#?}", key, components);
-                for component in components.iter().flatten() {
-                    let component = match retrieve_component(log, timestamp, &parent, component) {
-                        Ok(c) => c,
-                        Err(e) => {
-                            re_log::error!("error retrieving component: {e}");
-                            continue;
-                        }
-                    };
-                    dbg!(&component);
+    for (key, timestamp, value) in changed {
+        let Some(time) = time_point(timestamp, timeline, &key, &mut untimed) else {
+            continue;
+        };
+
+        if let Some(format) = log.frame_format(&key) {
+            let frame = value
+                .as_binary_opt::<i32>()
+                .and_then(|frames| frames.iter().next().flatten());
+            match frame.map(|frame| format.to_image(frame)) {
+                Some(Ok(image)) => {
+                    rendered.insert(key.clone());
+                    let builder = || Chunk::builder(key.clone());
+                    let chunk = entities.entry(key.clone()).or_insert_with(builder);
                     replace_with::replace_with(chunk, builder, |c| {
-                        c.with_component_batch(
-                            RowId::new(),
-                            TimePoint::default().with(timeline, timestamp),
-                            &*component,
-                        )
+                        c.with_archetype(RowId::new(), time.clone(), &image)
                     });
                 }
+                Some(Err(e)) => re_log::warn!("skipping frame of {key}: {e}"),
+                None => re_log::warn!("skipping frame of {key}: not raw data"),
+            }
+            continue;
+        }
+
+        if log.config().time_column(&key).is_some() {
+            match latest_f64(log, timestamp, &key) {
+                Ok(value) => {
+                    rendered.insert(key.clone());
+                    time_columns
+                        .entry(key.clone())
+                        .or_default()
+                        .push((timestamp, value));
+                }
+                Err(e) => re_log::error!("error retrieving time column: {e}"),
+            }
+        }
+
+        // lines without a severity prefix are left as the strings they are
+        if let Some(text_log) = log.config().text_log(&key)
+            && let Some(line) = value
+                .as_string_opt::<i32>()
+                .and_then(|lines| lines.iter().next().flatten())
+            && let Some((level, message)) = text_log.parse(line)
+        {
+            let entry = TextLog::new(message).with_level(level);
+
+            rendered.insert(key.clone());
+            let builder = || Chunk::builder(key.clone());
+            let chunk = entities.entry(key.clone()).or_insert_with(builder);
+            replace_with::replace_with(chunk, builder, |c| {
+                c.with_archetype(RowId::new(), time.clone(), &entry)
+            });
+            continue;
+        }
+
+        if is_meta_entry(&key, &*value) {
+            // describes the entity rather than being a sample of it
+            continue;
+        }
+
+        let Some((entity, components)) = entity_components(log, &key) else {
+            // a number outside of any entity is plotted as is, and one at the top level always is,
+            // as it has nowhere to put metadata; time columns already plot their own values
+            let plain = match log.config().plain_scalars {
+                PlainScalars::All => true,
+                PlainScalars::TopLevel => key.len() == 1,
+            };
+            if plain && value.data_type().is_numeric() && log.config().time_column(&key).is_none() {
+                match scalar_sample(log, timestamp, &key, &key, &mut imprecise) {
+                    Ok(ScalarSample::Plotted(value)) => {
+                        rendered.insert(key.clone());
+                        scalars
+                            .entry(key.clone())
+                            .or_default()
+                            .push((timestamp, value));
+                    }
+                    Ok(ScalarSample::Text(exact)) => {
+                        rendered.insert(key.clone());
+                        log_exact_int(&mut entities, &key, time.clone(), exact);
+                    }
+                    Err(e) => re_log::error!("error retrieving plain scalar: {e}"),
+                }
+            }
+
+            // not an entity
+            continue;
+        };
+        if !components.is_empty() {
+            rendered.insert(key.clone());
+        }
+        // every field of a struct changes at once, but each is read back for the entity's one row
+        if !entity_rows.insert((entity.clone(), timestamp)) {
+            continue;
+        }
+
+        for component in &components {
+            if matches!(component.as_str(), "Pose2d" | "Pose3d")
+                && log.config().trail(&entity).is_some()
+            {
+                trails.entry(entity.clone()).or_default().push(timestamp);
+            }
+
+            if component == "Scalar" {
+                let key = entity.join(&EntityPath::from_single_string(component.as_str()));
+
+                match scalar_sample(log, timestamp, &key, &entity, &mut imprecise) {
+                    Ok(ScalarSample::Plotted(value)) => scalars
+                        .entry(entity.clone())
+                        .or_default()
+                        .push((timestamp, value)),
+                    Ok(ScalarSample::Text(exact)) => {
+                        log_exact_int(&mut entities, &entity, time.clone(), exact);
+                    }
+                    Err(e) => re_log::error!("error retrieving component: {e}"),
+                }
+                continue;
+            }
+
+            if component == "BarChart" {
+                bar_charts
+                    .entry(entity.clone())
+                    .or_default()
+                    .push(timestamp);
+                continue;
+            }
+
+            if component == "ChassisSpeeds" {
+                match chassis_speeds_arrows(log, timestamp, &entity, mirror) {
+                    Ok(arrows) => {
+                        let builder = || Chunk::builder(entity.clone());
+                        let chunk = entities.entry(entity.clone()).or_insert_with(builder);
+                        replace_with::replace_with(chunk, builder, |c| {
+                            c.with_archetype(RowId::new(), time.clone(), &arrows)
+                        });
+                    }
+                    Err(e) => re_log::error!("error retrieving chassis speeds: {e}"),
+                }
+                continue;
             }
-            _ => {
-                // not an entity
+
+            if component == "Twist2d" || component == "Twist3d" {
+                match twist_arrow(log, timestamp, &entity, mirror) {
+                    Ok((arrow, angle)) => {
+                        let builder = || Chunk::builder(entity.clone());
+                        let chunk = entities.entry(entity.clone()).or_insert_with(builder);
+                        replace_with::replace_with(chunk, builder, |c| {
+                            c.with_archetype(RowId::new(), time.clone(), &arrow)
+                                .with_component_batch(
+                                    RowId::new(),
+                                    time.clone(),
+                                    &rerun::components::Scalar::from(angle),
+                                )
+                        });
+                    }
+                    Err(e) => re_log::error!("error retrieving twist: {e}"),
+                }
+                continue;
+            }
+
+            if component == "Footprint" {
+                match pose2d(log, timestamp, &entity, mirror) {
+                    Ok(pose) => {
+                        let footprint = footprint_box(pose, &log.config().footprint);
+                        let builder = || Chunk::builder(entity.clone());
+                        let chunk = entities.entry(entity.clone()).or_insert_with(builder);
+                        replace_with::replace_with(chunk, builder, |c| {
+                            c.with_archetype(RowId::new(), time.clone(), &footprint)
+                        });
+                    }
+                    Err(e) => re_log::error!("error retrieving footprint pose: {e}"),
+                }
+                continue;
+            }
+
+            let batches = match retrieve_component(log, timestamp, &entity, component, mirror) {
+                Ok(c) => c,
+                Err(e) => {
+                    re_log::error!("error retrieving component: {e}");
+                    continue;
+                }
+            };
+
+            let builder = || Chunk::builder(entity.clone());
+            let chunk = entities.entry(entity.clone()).or_insert_with(builder);
+            for batch in batches {
+                replace_with::replace_with(chunk, builder, |c| {
+                    c.with_component_batch(RowId::new(), time.clone(), &*batch)
+                });
             }
         }
     }
 
-    entities
+    let mut chunks: Vec<Chunk> = entities
         .into_values()
         .map(|builder| builder.build().unwrap())
+        .collect();
+
+    for (entity, samples) in scalars {
+        // the style is static, so it's only logged with an entity's first batch of samples
+        if let Some(hints) = ScalarHints::from_log(log, &entity)
+            && log.mark_styled(entity.clone())
+        {
+            match hints.to_chunk(entity.clone()) {
+                // bounds alone leave nothing to log with the series
+                Ok(chunk) if chunk.is_empty() => {}
+                Ok(chunk) => chunks.push(chunk),
+                Err(e) => re_log::error!("error building scalar hints chunk: {e}"),
+            }
+        }
+
+        match scalar_column_chunk(entity, timeline, samples) {
+            Ok(chunk) => chunks.push(chunk),
+            Err(e) => re_log::error!("error building scalar chunk: {e}"),
+        }
+    }
+
+    for (entity, samples) in time_columns {
+        let Some(derived) = log.config().time_column(&entity) else {
+            continue;
+        };
+        match time_column_chunk(entity, timeline, derived, samples) {
+            Ok(chunk) => chunks.push(chunk),
+            Err(e) => re_log::error!("error building time column chunk: {e}"),
+        }
+    }
+
+    for (entity, mut updates) in trails {
+        let Some(trail) = log.config().trail(&entity) else {
+            continue;
+        };
+        updates.sort_unstable();
+        updates.dedup();
+
+        let Some(&last) = updates.last() else {
+            continue;
+        };
+        let positions = match pose_positions(log, &entity, last, mirror) {
+            Ok(positions) => positions,
+            Err(e) => {
+                re_log::error!("error retrieving pose trail: {e}");
+                continue;
+            }
+        };
+
+        let mut builder = Chunk::builder(entity.join(&EntityPath::from_single_string("trail")));
+        for timestamp in updates {
+            let Some(time) = time_point(timestamp, timeline, &entity, &mut untimed) else {
+                continue;
+            };
+            builder = builder.with_archetype(
+                RowId::new(),
+                time,
+                &LineStrips2D::new(pose_trail(&positions, timestamp, trail)),
+            );
+        }
+
+        match builder.build() {
+            Ok(chunk) => chunks.push(chunk),
+            Err(e) => re_log::error!("error building trail chunk: {e}"),
+        }
+    }
+
+    for (entity, mut updates) in bar_charts {
+        updates.sort_unstable();
+        updates.dedup();
+
+        let mut builder = Chunk::builder(entity.clone());
+        for timestamp in updates {
+            let Some(time) = time_point(timestamp, timeline, &entity, &mut untimed) else {
+                continue;
+            };
+            match numeric_array(log, &entity, timestamp) {
+                Ok(values) => {
+                    builder = builder.with_archetype(
+                        RowId::new(),
+                        time,
+                        &BarChart::new(values.as_slice()),
+                    );
+                }
+                Err(e) => re_log::error!("error retrieving bar chart: {e}"),
+            }
+        }
+
+        match builder.build() {
+            Ok(chunk) => chunks.push(chunk),
+            Err(e) => re_log::error!("error building bar chart chunk: {e}"),
+        }
+    }
+
+    for (key, timestamp, text) in log.take_documents() {
+        let Some(time) = time_point(timestamp, timeline, &key, &mut untimed) else {
+            continue;
+        };
+        match Chunk::builder(key)
+            .with_archetype(
+                RowId::new(),
+                time,
+                &TextDocument::new(text).with_media_type(MediaType::markdown()),
+            )
+            .build()
+        {
+            Ok(chunk) => chunks.push(chunk),
+            Err(e) => re_log::error!("error building document chunk: {e}"),
+        }
+    }
+
+    // entries that were finished get cleared so the viewer doesn't show stale values
+    for (key, timestamp) in log.take_cleared() {
+        let Some(time) = time_point(timestamp, timeline, &key, &mut untimed) else {
+            continue;
+        };
+        match Chunk::builder(key)
+            .with_archetype(RowId::new(), time, &Clear::recursive())
+            .build()
+        {
+            Ok(chunk) => chunks.push(chunk),
+            Err(e) => re_log::error!("error building clear chunk: {e}"),
+        }
+    }
+
+    log.mark_rendered(rendered);
+
+    chunks
+}
+
+/// Sorts chunks by entity and gives them ids counting up from the last ones `log` gave out,
+/// replacing the random ones, if its config asks for deterministic output.
+///
+/// Everything sent to a recording goes through here, so no two chunks share an id.
+#[must_use]
+pub fn renumbered(mut chunks: Vec<Chunk>, log: &mut EntryLog) -> Vec<Chunk> {
+    if !log.config().deterministic {
+        return chunks;
+    }
+
+    let key = |chunk: &Chunk| {
+        let mut components = chunk.component_names().collect::<Vec<_>>();
+        components.sort_unstable();
+        (chunk.entity_path().clone(), components)
+    };
+    chunks.sort_by_cached_key(key);
+
+    chunks
+        .into_iter()
+        .map(|chunk| {
+            let id = log.claim_chunk_id();
+            let first_row = log.claim_row_ids(chunk.num_rows() as u128);
+            chunk.clone_as(ChunkId::from_u128(id), RowId::from_u128(first_row))
+        })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rerun::{
+        ApplicationId, EntityPath, Loggable as _, StoreId, StoreKind, Timeline,
+        components::{
+            ClearIsRecursive, Color, ImageBuffer, Name, Position3D, Range1D, RotationAxisAngle,
+            RotationQuat, Scalar, TensorData, Text, TextLogLevel, Translation3D, Vector3D,
+        },
+        external::arrow::array::{Float64Array, StringArray},
+        log::{Chunk, ChunkId, RowId},
+    };
+
+    use crate::{
+        config::{
+            ComponentOverride, Config, Coordinates, DEFAULT_TRAIL_POINTS, EntityPattern,
+            FieldConfig, Footprint, ImuConfig, LargeIntegers, PlainScalars, TimeColumnOverride,
+            TimeUnit, TrailOverride, VelocityConfig,
+        },
+        log::{Coverage, EntryLog, Timestamp},
+        values::image::FrameFormat,
+    };
+
+    #[test]
+    fn finished_entry_is_cleared() {
+        let mut log = EntryLog::new();
+        let key = EntityPath::from_single_string("test");
+
+        log.add_entry(key.clone(), Timestamp(1_000), "int64", &3i64.to_le_bytes())
+            .unwrap();
+        log.clear_entry(key.clone(), Timestamp(2_000));
+
+        let timeline = Timeline::new_duration("robotime");
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            timeline,
+            &mut log,
+        );
+
+        let clear = chunks
+            .iter()
+            .find(|c| {
+                c.entity_path() == &key
+                    && c.component_names().any(|n| n == ClearIsRecursive::name())
+            })
+            .expect("no clear chunk for finished entry");
+
+        let times = clear.timelines().get(timeline.name()).unwrap().times_raw();
+        // microseconds -> nanoseconds
+        assert_eq!(times, &[2_000_000]);
+    }
+
+    #[test]
+    fn component_override_without_metadata() {
+        let mut log = EntryLog::with_config(Config {
+            components: vec![ComponentOverride {
+                pattern: EntityPattern("drive/pose".to_owned()),
+                components: vec!["Pose2d".to_owned()],
+            }],
+            ..Config::default()
+        });
+
+        for (path, value) in [
+            ("drive/pose/translation/x", 1.0f64),
+            ("drive/pose/translation/y", 2.0),
+            ("drive/pose/rotation/value", std::f64::consts::FRAC_PI_2),
+        ] {
+            log.add_entry(
+                EntityPath::from_file_path(Path::new(path)),
+                Timestamp(1_000),
+                "double",
+                &value.to_le_bytes(),
+            )
+            .unwrap();
+        }
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        let pose = chunks
+            .iter()
+            .find(|c| c.entity_path() == &EntityPath::from_file_path(Path::new("drive/pose")))
+            .expect("no chunk for overridden entity");
+
+        let components = pose.component_names().collect::<Vec<_>>();
+        assert!(components.contains(&Translation3D::name()));
+        assert!(components.contains(&RotationAxisAngle::name()));
+    }
+
+    #[test]
+    fn scalars_are_batched() {
+        let mut log = EntryLog::new();
+        let entity = EntityPath::from_single_string("speed");
+
+        for (key, value) in [
+            (super::TYPE_META_ENTRY, "Entity"),
+            (super::COMPONENTS_META_ENTRY, "Scalar"),
+        ] {
+            log.add_entry(
+                entity.join(&EntityPath::from_single_string(key)),
+                Timestamp(0),
+                "string",
+                value.as_bytes(),
+            )
+            .unwrap();
+        }
+        log.get_changed();
+
+        for (timestamp, value) in [(1_000, 1.0f64), (2_000, 2.0), (3_000, 3.0)] {
+            log.add_entry(
+                entity.join(&EntityPath::from_single_string("Scalar")),
+                Timestamp(timestamp),
+                "double",
+                &value.to_le_bytes(),
+            )
+            .unwrap();
+        }
+
+        let timeline = Timeline::new_duration("robotime");
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            timeline,
+            &mut log,
+        );
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].entity_path(), &entity);
+        assert_eq!(chunks[0].num_rows(), 3);
+        assert_eq!(
+            chunks[0]
+                .timelines()
+                .get(timeline.name())
+                .unwrap()
+                .times_raw(),
+            &[1_000_000, 2_000_000, 3_000_000]
+        );
+    }
+
+    #[test]
+    fn top_level_scalar() {
+        let mut log = EntryLog::new();
+        let key = EntityPath::from_single_string("voltage");
+
+        for (timestamp, value) in [(1_000, 12.5f64), (2_000, 12.25)] {
+            log.add_entry(
+                key.clone(),
+                Timestamp(timestamp),
+                "double",
+                &value.to_le_bytes(),
+            )
+            .unwrap();
+        }
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        let scalars = chunks
+            .iter()
+            .find(|c| c.entity_path() == &key)
+            .expect("no chunk for top-level scalar");
+        assert_eq!(
+            scalars
+                .iter_component::<Scalar>()
+                .flat_map(|batch| batch.to_vec())
+                .collect::<Vec<_>>(),
+            [Scalar::from(12.5), Scalar::from(12.25)]
+        );
+    }
+
+    #[test]
+    fn samples_past_the_timeline_are_skipped() {
+        let mut log = EntryLog::new();
+        let key = EntityPath::from_single_string("voltage");
+
+        // too late to count in nanoseconds
+        for (timestamp, value) in [(1_000, 12.5f64), (u64::MAX / 1_000, 12.25)] {
+            log.add_entry(
+                key.clone(),
+                Timestamp(timestamp),
+                "double",
+                &value.to_le_bytes(),
+            )
+            .unwrap();
+        }
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        let scalars = chunks
+            .iter()
+            .find(|c| c.entity_path() == &key)
+            .expect("no chunk for top-level scalar");
+        assert_eq!(
+            scalars
+                .iter_component::<Scalar>()
+                .flat_map(|batch| batch.to_vec())
+                .collect::<Vec<_>>(),
+            [Scalar::from(12.5)]
+        );
+    }
+
+    #[test]
+    fn rotation2d_heading() {
+        let mut log = EntryLog::new();
+        log.add_entry(
+            EntityPath::from_file_path(Path::new(".schema/struct:Rotation2d")),
+            Timestamp(0),
+            "structschema",
+            b"double value",
+        )
+        .unwrap();
+
+        let gyro = EntityPath::from_single_string("gyro");
+        log.add_entry(
+            gyro.clone(),
+            Timestamp(1_000),
+            "struct:Rotation2d",
+            &std::f64::consts::FRAC_PI_2.to_le_bytes(),
+        )
+        .unwrap();
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        let heading = chunks
+            .iter()
+            .find(|c| c.entity_path() == &gyro)
+            .expect("no chunk for the heading")
+            .iter_component::<Scalar>()
+            .flat_map(|batch| batch.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(heading.len(), 1);
+        assert!((heading[0].0.0 - 90.0).abs() < 1e-9);
+
+        // the radians aren't plotted alongside it
+        assert!(
+            !chunks
+                .iter()
+                .any(|c| c.entity_path() == &gyro.join(&EntityPath::from_single_string("value")))
+        );
+    }
+
+    #[test]
+    fn twist2d_motion() {
+        let mut log = EntryLog::new();
+        log.add_entry(
+            EntityPath::from_file_path(Path::new(".schema/struct:Twist2d")),
+            Timestamp(0),
+            "structschema",
+            b"double dx;double dy;double dtheta",
+        )
+        .unwrap();
+
+        // a single loop's worth of motion is tiny, but still drawn
+        let twist = EntityPath::from_file_path(Path::new("odometry/twist"));
+        log.add_entry(
+            twist.clone(),
+            Timestamp(1_000),
+            "struct:Twist2d",
+            &[0.02f64, -0.01, 0.001f64.to_radians()]
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+        let chunk = chunks
+            .iter()
+            .find(|c| c.entity_path() == &twist)
+            .expect("no chunk for the twist");
+
+        let vectors = chunk
+            .iter_component::<Vector3D>()
+            .flat_map(|batch| batch.to_vec())
+            .map(|v| v.0.0)
+            .collect::<Vec<_>>();
+        assert_eq!(vectors, [[0.02, -0.01, 0.0]]);
+
+        let angle = chunk
+            .iter_component::<Scalar>()
+            .flat_map(|batch| batch.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(angle.len(), 1);
+        assert!((angle[0].0.0 - 0.001).abs() < 1e-12);
+    }
+
+    #[test]
+    fn plain_scalars() {
+        let speed = EntityPath::from_file_path(Path::new("drive/speed"));
+        let scalars_for = |plain_scalars| {
+            let mut log = EntryLog::with_config(Config {
+                plain_scalars,
+                ..Config::default()
+            });
+            for (timestamp, value) in [(1_000, 1.5f64), (2_000, 2.5)] {
+                log.add_entry(
+                    speed.clone(),
+                    Timestamp(timestamp),
+                    "double",
+                    &value.to_le_bytes(),
+                )
+                .unwrap();
+            }
+
+            super::log_changes_to_chunks(
+                &StoreId::random(StoreKind::Recording),
+                &ApplicationId::random(),
+                Timeline::new_duration("robotime"),
+                &mut log,
+            )
+            .iter()
+            .filter(|c| c.entity_path() == &speed)
+            .flat_map(|c| {
+                c.iter_component::<Scalar>()
+                    .flat_map(|batch| batch.to_vec())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            scalars_for(PlainScalars::All),
+            [Scalar::from(1.5), Scalar::from(2.5)]
+        );
+        assert!(scalars_for(PlainScalars::TopLevel).is_empty());
+    }
+
+    #[test]
+    fn time_column_override() {
+        let mut log = EntryLog::with_config(Config {
+            time_columns: vec![TimeColumnOverride {
+                pattern: EntityPattern("vision/captureTime".to_owned()),
+                timeline: "capture".to_owned(),
+                unit: TimeUnit::Seconds,
+            }],
+            ..Config::default()
+        });
+        let key = EntityPath::from_file_path(Path::new("vision/captureTime"));
+
+        log.add_entry(
+            key.clone(),
+            Timestamp(10_000),
+            "double",
+            &1.5f64.to_le_bytes(),
+        )
+        .unwrap();
+
+        let robotime = Timeline::new_duration("robotime");
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            robotime,
+            &mut log,
+        );
+
+        let chunk = chunks
+            .iter()
+            .find(|c| c.entity_path() == &key)
+            .expect("no chunk for time column entity");
+
+        assert_eq!(
+            chunk
+                .timelines()
+                .get(Timeline::new_duration("capture").name())
+                .expect("no derived timeline")
+                .times_raw(),
+            &[1_500_000_000]
+        );
+        assert_eq!(
+            chunk.timelines().get(robotime.name()).unwrap().times_raw(),
+            &[10_000_000]
+        );
+    }
+
+    #[test]
+    fn trail_grows_up_to_cap() {
+        let pattern = || EntityPattern("drive/pose".to_owned());
+        let trail = TrailOverride {
+            pattern: pattern(),
+            max_points: Some(3),
+            max_age: None,
+            max_gap: None,
+        };
+        let mut log = EntryLog::with_config(Config {
+            components: vec![ComponentOverride {
+                pattern: pattern(),
+                components: vec!["Pose2d".to_owned()],
+            }],
+            trails: vec![trail.clone()],
+            ..Config::default()
+        });
+        let entity = EntityPath::from_file_path(Path::new("drive/pose"));
+
+        let mut lengths = Vec::new();
+        for step in 0..5u32 {
+            let timestamp = Timestamp(u64::from(step) * 20_000);
+            for (path, value) in [
+                ("drive/pose/translation/x", f64::from(step)),
+                ("drive/pose/translation/y", 0.0),
+                ("drive/pose/rotation/value", 0.0),
+            ] {
+                log.add_entry(
+                    EntityPath::from_file_path(Path::new(path)),
+                    timestamp,
+                    "double",
+                    &value.to_le_bytes(),
+                )
+                .unwrap();
+            }
+
+            let positions = super::pose_positions(&log, &entity, timestamp, None).unwrap();
+            lengths.push(
+                super::pose_trail(&positions, timestamp, &trail)
+                    .concat()
+                    .len(),
+            );
+        }
+
+        assert_eq!(lengths, [1, 2, 3, 3, 3]);
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+        let trail_chunk = chunks
+            .iter()
+            .find(|c| c.entity_path() == &entity.join(&EntityPath::from_single_string("trail")))
+            .expect("no trail chunk");
+        assert_eq!(trail_chunk.num_rows(), 5);
+    }
+
+    #[test]
+    fn field_named_like_meta_entry() {
+        let mut log = EntryLog::new();
+        let entity = EntityPath::from_single_string("arm");
+
+        // an ordinary struct that happens to have `type` and `components` fields
+        for (key, value) in [("type", "Entity"), ("components", "Scalar")] {
+            log.add_entry(
+                entity.join(&EntityPath::from_single_string(key)),
+                Timestamp(1_000),
+                "string",
+                value.as_bytes(),
+            )
+            .unwrap();
+        }
+        log.add_entry(
+            entity.join(&EntityPath::from_single_string("Scalar")),
+            Timestamp(1_000),
+            "double",
+            &1.0f64.to_le_bytes(),
+        )
+        .unwrap();
+
+        let string = StringArray::from(vec!["Entity"]);
+        assert!(!super::is_meta_entry(
+            &entity.join(&EntityPath::from_single_string("type")),
+            &string
+        ));
+        assert!(super::is_meta_entry(
+            &super::meta_entry(&entity, super::TYPE_META_ENTRY),
+            &string
+        ));
+        // only a string can describe the entity
+        assert!(!super::is_meta_entry(
+            &super::meta_entry(&entity, super::TYPE_META_ENTRY),
+            &Float64Array::from(vec![1.0])
+        ));
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        assert!(chunks.iter().all(|c| c.entity_path() != &entity));
+    }
+
+    #[test]
+    fn meta_entries_arent_samples() {
+        let mut log = EntryLog::new();
+        let entity = EntityPath::from_single_string("speed");
+
+        log.add_entry(
+            entity.join(&EntityPath::from_single_string("Scalar")),
+            Timestamp(1_000),
+            "double",
+            &1.0f64.to_le_bytes(),
+        )
+        .unwrap();
+        // the metadata is published after the first value, under its own entries
+        for (key, value) in [
+            (super::TYPE_META_ENTRY, "Entity"),
+            (super::COMPONENTS_META_ENTRY, "Scalar"),
+        ] {
+            log.add_entry(
+                super::meta_entry(&entity, key),
+                Timestamp(2_000),
+                "string",
+                value.as_bytes(),
+            )
+            .unwrap();
+        }
+
+        let timeline = Timeline::new_duration("robotime");
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            timeline,
+            &mut log,
+        );
+
+        // a change to `.type` or `.components` isn't a new sample of the entity
+        let speed = chunks
+            .iter()
+            .find(|c| c.entity_path() == &entity && !c.is_static())
+            .expect("no scalar chunk");
+        assert_eq!(
+            speed.timelines().get(timeline.name()).unwrap().times_raw(),
+            &[1_000_000]
+        );
+    }
+
+    #[test]
+    fn raw_frames_become_images() {
+        let mut log = EntryLog::new();
+        let camera = EntityPath::from_single_string("camera");
+        log.set_frame_format(
+            camera.clone(),
+            FrameFormat::from_metadata(r#"{"format":"YUYV","width":2,"height":2}"#).unwrap(),
+        );
+
+        log.add_entry(
+            camera.clone(),
+            Timestamp(1_000),
+            "raw",
+            &[16, 128, 235, 128, 81, 90, 145, 240],
+        )
+        .unwrap();
+        // too short for the declared size, so it's skipped
+        log.add_entry(camera.clone(), Timestamp(2_000), "raw", &[16, 128])
+            .unwrap();
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        let chunk = chunks
+            .iter()
+            .find(|c| c.entity_path() == &camera)
+            .expect("no chunk for camera frames");
+        assert!(chunk.component_names().any(|n| n == ImageBuffer::name()));
+        assert_eq!(chunk.num_rows(), 1);
+    }
+
+    #[test]
+    fn scalar_metadata_hints() {
+        let mut log = EntryLog::new();
+        let entity = EntityPath::from_single_string("speed");
+
+        for (key, value) in [
+            (super::TYPE_META_ENTRY, "Entity"),
+            (super::COMPONENTS_META_ENTRY, "Scalar"),
+        ] {
+            log.add_entry(
+                entity.join(&EntityPath::from_single_string(key)),
+                Timestamp(0),
+                "string",
+                value.as_bytes(),
+            )
+            .unwrap();
+        }
+        let value = entity.join(&EntityPath::from_single_string("Scalar"));
+        log.set_metadata(value.clone(), r#"{"unit":"m/s","min":0,"max":5}"#);
+        log.add_entry(value, Timestamp(1_000), "double", &1.0f64.to_le_bytes())
+            .unwrap();
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        let hints = chunks
+            .iter()
+            .find(|c| c.entity_path() == &entity && c.is_static())
+            .expect("no hints chunk");
+        let components = hints.component_names().collect::<Vec<_>>();
+        assert!(components.contains(&Name::name()));
+        // the bounds go to the blueprint's plot instead
+        assert!(!components.contains(&Range1D::name()));
+        assert_eq!(super::scalar_range(&log, &entity), Some((0.0, 5.0)));
+    }
+
+    #[test]
+    fn static_series_style() {
+        let mut log = EntryLog::new();
+        let speed = EntityPath::from_file_path(Path::new("drive/left"));
+        log.set_metadata(
+            speed.clone(),
+            r##"{"name":"Left speed","color":"#ff8000"}"##,
+        );
+        for timestamp in [1_000, 2_000, 3_000] {
+            log.add_entry(
+                speed.clone(),
+                Timestamp(timestamp),
+                "double",
+                &1.0f64.to_le_bytes(),
+            )
+            .unwrap();
+        }
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+        let (style, values): (Vec<_>, Vec<_>) = chunks
+            .iter()
+            .filter(|c| c.entity_path() == &speed)
+            .partition(|c| c.is_static());
+
+        // named once, rather than on every sample
+        let [style] = &style[..] else {
+            panic!("expected one static chunk, got {}", style.len());
+        };
+        assert_eq!(style.num_rows(), 1);
+        let names = style
+            .iter_component::<Name>()
+            .flat_map(|batch| batch.to_vec())
+            .map(|name| name.as_str().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Left speed"]);
+        assert_eq!(
+            style
+                .iter_component::<Color>()
+                .flat_map(|batch| batch.to_vec())
+                .collect::<Vec<_>>(),
+            [Color::from_rgb(255, 128, 0)]
+        );
+
+        assert!(
+            values
+                .iter()
+                .all(|c| !c.component_names().any(|n| n == Name::name()))
+        );
+        assert_eq!(values.iter().map(|c| c.num_rows()).sum::<usize>(), 3);
+
+        // nor again with later samples
+        log.add_entry(
+            speed.clone(),
+            Timestamp(4_000),
+            "double",
+            &1.0f64.to_le_bytes(),
+        )
+        .unwrap();
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+        assert!(
+            chunks
+                .iter()
+                .filter(|c| c.entity_path() == &speed)
+                .all(|c| !c.is_static())
+        );
+    }
+
+    #[test]
+    fn malformed_scalar_hints_are_ignored() {
+        let mut log = EntryLog::new();
+        let entity = EntityPath::from_single_string("speed");
+        log.set_metadata(entity.clone(), r#"{"min":"low"}"#);
+
+        assert_eq!(super::ScalarHints::from_log(&log, &entity), None);
+    }
+
+    #[test]
+    fn schema_text_document() {
+        let mut log = EntryLog::with_config(Config {
+            log_schemas: true,
+            ..Config::default()
+        });
+        log.add_entry(
+            EntityPath::from_file_path(Path::new(".schema/struct:Translation2d")),
+            Timestamp(0),
+            "structschema",
+            b"double x;double y",
+        )
+        .unwrap();
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        let document = EntityPath::from_single_string("struct:Translation2d")
+            .join(&EntityPath::from_single_string(".schema"));
+        let chunk = chunks
+            .iter()
+            .find(|c| c.entity_path() == &document)
+            .expect("no schema document");
+        assert!(chunk.component_names().any(|n| n == Text::name()));
+    }
+
+    #[test]
+    fn footprint_follows_pose() {
+        use std::f64::consts::{FRAC_PI_2, PI};
+
+        use rerun::{Angle, Boxes3D, components::PoseRotationAxisAngle};
+
+        let footprint = Footprint {
+            length: 0.8,
+            width: 0.6,
+        };
+
+        for (x, y, theta) in [(0.0, 0.0, 0.0), (1.0, 2.0, FRAC_PI_2), (-3.5, 4.25, PI)] {
+            #[allow(clippy::cast_possible_truncation)]
+            let expected = Boxes3D::from_centers_and_half_sizes(
+                [(x as f32, y as f32, 0.0)],
+                [(0.4, 0.3, 0.0)],
+            )
+            .with_rotation_axis_angles([PoseRotationAxisAngle::new(
+                [0.0, 0.0, 1.0],
+                Angle::from_radians(theta as f32),
+            )]);
+
+            assert_eq!(super::footprint_box((x, y, theta), &footprint), expected);
+        }
+    }
+
+    #[test]
+    fn chassis_speeds_arrow() {
+        let mut log = EntryLog::with_config(Config {
+            components: vec![ComponentOverride {
+                pattern: EntityPattern("drive/speeds".to_owned()),
+                components: vec!["ChassisSpeeds".to_owned()],
+            }],
+            velocity: VelocityConfig {
+                pose: Some("drive/pose".to_owned()),
+                scale: 0.5,
+            },
+            ..Config::default()
+        });
+
+        for (path, value) in [
+            ("drive/pose/translation/x", 2.0f64),
+            ("drive/pose/translation/y", 3.0),
+            ("drive/pose/rotation/value", std::f64::consts::FRAC_PI_2),
+            ("drive/speeds/vx", 1.0),
+            ("drive/speeds/vy", 0.0),
+            ("drive/speeds/omega", 0.5),
+        ] {
+            log.add_entry(
+                EntityPath::from_file_path(Path::new(path)),
+                Timestamp(1_000),
+                "double",
+                &value.to_le_bytes(),
+            )
+            .unwrap();
+        }
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+        let arrows = chunks
+            .iter()
+            .find(|c| c.entity_path() == &EntityPath::from_file_path(Path::new("drive/speeds")))
+            .expect("no chunk for chassis speeds");
+
+        let close = |a: [f32; 3], b: [f32; 3]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-6);
+
+        // driving forward while facing +Y is driving along +Y
+        let vectors = arrows
+            .iter_component::<Vector3D>()
+            .flat_map(|batch| batch.to_vec())
+            .map(|v| v.0.0)
+            .collect::<Vec<_>>();
+        assert_eq!(vectors.len(), 2);
+        assert!(close(vectors[0], [0.0, 0.5, 0.0]), "{vectors:?}");
+        assert!(close(vectors[1], [0.0, 0.0, 0.25]), "{vectors:?}");
+
+        let origins = arrows
+            .iter_component::<Position3D>()
+            .flat_map(|batch| batch.to_vec())
+            .map(|p| p.0.0)
+            .collect::<Vec<_>>();
+        assert_eq!(origins, [[2.0, 3.0, 0.0]; 2]);
+    }
+
+    #[test]
+    fn imu_euler_angles() {
+        let close = |a: [f64; 4], b: [f64; 4]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9);
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        let quarter = std::f64::consts::FRAC_PI_2;
+
+        assert!(close(
+            super::euler_quaternion(0.0, 0.0, quarter),
+            [0.0, 0.0, half, half]
+        ));
+        assert!(close(
+            super::euler_quaternion(quarter, 0.0, 0.0),
+            [half, 0.0, 0.0, half]
+        ));
+        // rolled about X, then turned about the fixed Z
+        assert!(close(
+            super::euler_quaternion(quarter, 0.0, quarter),
+            [0.5, 0.5, 0.5, 0.5]
+        ));
+    }
+
+    #[test]
+    fn imu_yaw_only() {
+        let mut log = EntryLog::with_config(Config {
+            components: vec![ComponentOverride {
+                pattern: EntityPattern("gyro".to_owned()),
+                components: vec!["Imu".to_owned()],
+            }],
+            imu: ImuConfig {
+                mount: [0.1, 0.0, 0.5],
+                degrees: true,
+            },
+            ..Config::default()
+        });
+        log.add_entry(
+            EntityPath::from_file_path(Path::new("gyro/yaw")),
+            Timestamp(1_000),
+            "double",
+            &90.0f64.to_le_bytes(),
+        )
+        .unwrap();
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+        let gyro = chunks
+            .iter()
+            .find(|c| c.entity_path() == &EntityPath::from_single_string("gyro"))
+            .expect("no chunk for the imu");
+
+        let rotations = gyro
+            .iter_component::<RotationQuat>()
+            .flat_map(|batch| batch.to_vec())
+            .map(|q| q.0.0)
+            .collect::<Vec<_>>();
+        assert_eq!(rotations.len(), 1);
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        assert!(
+            rotations[0]
+                .iter()
+                .zip([0.0, 0.0, half, half])
+                .all(|(a, b)| (a - b).abs() < 1e-6),
+            "{rotations:?}"
+        );
+
+        let mounts = gyro
+            .iter_component::<Translation3D>()
+            .flat_map(|batch| batch.to_vec())
+            .map(|t| t.0.0)
+            .collect::<Vec<_>>();
+        assert_eq!(mounts, [[0.1, 0.0, 0.5]]);
+    }
+
+    #[test]
+    fn coverage() {
+        let mut log = EntryLog::new();
+        for (name, ty, data) in [
+            ("speed", "double", 1.5f64.to_le_bytes().to_vec()),
+            ("name", "string", b"robot".to_vec()),
+            ("blob", "raw", vec![1, 2, 3]),
+            ("mystery", "custom:thing", vec![0]),
+        ] {
+            // the unknown type is expected to fail
+            let _ = log.add_entry(
+                EntityPath::from_single_string(name),
+                Timestamp(1_000),
+                ty,
+                &data,
+            );
+        }
+
+        super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+
+        let coverage = log.coverage();
+        assert_eq!(
+            coverage,
+            Coverage {
+                entries: 4,
+                decoded: 3,
+                rendered: 1,
+                unknown_type: 1,
+            }
+        );
+        assert_eq!(
+            coverage.to_string(),
+            "3/4 entries decoded, 1 rendered as components, 1 unknown-type dropped"
+        );
+    }
+
+    #[test]
+    fn trail_capped_by_default() {
+        let trail = TrailOverride {
+            pattern: EntityPattern("drive/pose".to_owned()),
+            max_points: None,
+            max_age: None,
+            max_gap: None,
+        };
+        let positions = (0..1_000u16)
+            .map(|i| (Timestamp(u64::from(i) * 20_000), [f32::from(i), 0.0]))
+            .collect::<Vec<_>>();
+
+        let strips = super::pose_trail(&positions, Timestamp(999 * 20_000), &trail);
+        let points = strips.concat();
+        assert_eq!(points.len(), DEFAULT_TRAIL_POINTS);
+        assert_eq!(points.last(), Some(&[999.0, 0.0]));
+    }
+
+    #[test]
+    fn trail_breaks_at_gaps() {
+        let trail = TrailOverride {
+            pattern: EntityPattern("drive/pose".to_owned()),
+            max_points: None,
+            max_age: None,
+            max_gap: Some(1.0),
+        };
+        let mut log = EntryLog::with_config(Config {
+            coordinates: Coordinates::Raw,
+            ..Config::default()
+        });
+        let entity = EntityPath::from_file_path(Path::new("drive/pose"));
+
+        // the robot is turned off for a while between the third and fourth updates
+        for (timestamp, x) in [
+            (0, 0.0),
+            (20_000, 1.0),
+            (40_000, 2.0),
+            (10_000_000, 5.0),
+            (10_020_000, 6.0),
+        ] {
+            for (path, value) in [
+                ("drive/pose/translation/x", x),
+                ("drive/pose/translation/y", 0.0f64),
+            ] {
+                log.add_entry(
+                    EntityPath::from_file_path(Path::new(path)),
+                    Timestamp(timestamp),
+                    "double",
+                    &value.to_le_bytes(),
+                )
+                .unwrap();
+            }
+        }
+
+        let timestamp = Timestamp(10_020_000);
+        let positions = super::pose_positions(&log, &entity, timestamp, None).unwrap();
+        let strips = super::pose_trail(&positions, timestamp, &trail);
+        assert_eq!(
+            strips,
+            [
+                vec![[0.0, 0.0], [1.0, 0.0], [2.0, 0.0]],
+                vec![[5.0, 0.0], [6.0, 0.0]],
+            ]
+        );
+    }
+
+    #[test]
+    fn wpilib_field_coordinates() {
+        let trail = TrailOverride {
+            pattern: EntityPattern("drive/pose".to_owned()),
+            max_points: None,
+            max_age: None,
+            max_gap: None,
+        };
+        let entity = EntityPath::from_file_path(Path::new("drive/pose"));
+
+        let trail_of = |coordinates| {
+            let mut log = EntryLog::with_config(Config {
+                coordinates,
+                ..Config::default()
+            });
+            for (path, value) in [
+                ("drive/pose/translation/x", 1.0f64),
+                ("drive/pose/translation/y", 2.0),
+            ] {
+                log.add_entry(
+                    EntityPath::from_file_path(Path::new(path)),
+                    Timestamp(1_000),
+                    "double",
+                    &value.to_le_bytes(),
+                )
+                .unwrap();
+            }
+            let positions = super::pose_positions(&log, &entity, Timestamp(1_000), None).unwrap();
+            super::pose_trail(&positions, Timestamp(1_000), &trail)
+        };
+
+        // a robot 2m to the left of the origin is drawn above it, not below
+        assert_eq!(trail_of(Coordinates::WpilibField), [[[1.0, -2.0]]]);
+        assert_eq!(trail_of(Coordinates::Raw), [[[1.0, 2.0]]]);
+        assert!(Coordinates::Raw.view_coordinates().is_none());
+    }
+
+    #[test]
+    fn tagged_array_is_a_bar_chart() {
+        let mut log = EntryLog::with_config(Config {
+            components: vec![ComponentOverride {
+                pattern: EntityPattern("vision/spectrum".to_owned()),
+                components: vec!["BarChart".to_owned()],
+            }],
+            ..Config::default()
+        });
+        let spectrum = EntityPath::from_file_path(Path::new("vision/spectrum"));
+
+        for (timestamp, scale) in [(1_000, 1.0f64), (2_000, 2.0)] {
+            let bins = (0..8u32)
+                .flat_map(|bin| (f64::from(bin) * scale).to_le_bytes())
+                .collect::<Vec<_>>();
+            log.add_entry(spectrum.clone(), Timestamp(timestamp), "double[8]", &bins)
+                .unwrap();
+        }
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+        let chart = chunks
+            .iter()
+            .find(|c| c.entity_path() == &spectrum)
+            .expect("no bar chart for the tagged array");
+
+        // one chart per update, rather than one per changed element
+        assert_eq!(chart.num_rows(), 2);
+        let shapes = chart
+            .iter_component::<TensorData>()
+            .flat_map(|batch| batch.to_vec())
+            .map(|tensor| tensor.0.shape().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(shapes, [vec![8], vec![8]]);
+
+        assert_eq!(
+            super::numeric_array(&log, &spectrum, Timestamp(2_000)).unwrap(),
+            [0.0, 2.0, 4.0, 6.0, 8.0, 10.0, 12.0, 14.0]
+        );
+
+        // strings can't be charted, even if they'd parse as numbers
+        let labels = EntityPath::from_single_string("labels");
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend(1u32.to_le_bytes());
+        data.extend(b"1");
+        log.add_entry(labels.clone(), Timestamp(3_000), "string[]", &data)
+            .unwrap();
+        assert!(super::numeric_array(&log, &labels, Timestamp(3_000)).is_err());
+    }
+
+    #[test]
+    fn red_alliance_pose_is_flipped() {
+        let log_pose = |alliance: Option<bool>| {
+            let mut log = EntryLog::with_config(Config {
+                field: FieldConfig {
+                    blue_origin: true,
+                    ..FieldConfig::default()
+                },
+                ..Config::default()
+            });
+            for (path, value) in [
+                ("drive/pose/translation/x", 2.0f64),
+                ("drive/pose/translation/y", 3.0),
+                ("drive/pose/rotation/value", 0.5),
+            ] {
+                log.add_entry(
+                    EntityPath::from_file_path(Path::new(path)),
+                    Timestamp(1_000),
+                    "double",
+                    &value.to_le_bytes(),
+                )
+                .unwrap();
+            }
+            if let Some(red) = alliance {
+                log.add_entry(
+                    EntityPath::from_file_path(Path::new("FMSInfo/IsRedAlliance")),
+                    Timestamp(0),
+                    "boolean",
+                    &[u8::from(red)],
+                )
+                .unwrap();
+            }
+
+            super::pose2d(
+                &log,
+                Timestamp(1_000),
+                &EntityPath::from_file_path(Path::new("drive/pose")),
+                super::blue_origin_mirror(&log),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            log_pose(Some(true)),
+            (16.54 - 2.0, 3.0, std::f64::consts::PI - 0.5)
+        );
+        // already on the blue origin
+        assert_eq!(log_pose(Some(false)), (2.0, 3.0, 0.5));
+        // unknown, so left alone
+        assert_eq!(log_pose(None), (2.0, 3.0, 0.5));
+    }
+
+    #[test]
+    fn large_int64_scalars() {
+        let ticks = EntityPath::from_single_string("ticks");
+        let chunks_for = |large_integers| {
+            let mut log = EntryLog::with_config(Config {
+                large_integers,
+                ..Config::default()
+            });
+            for (key, value) in [
+                (super::TYPE_META_ENTRY, "Entity"),
+                (super::COMPONENTS_META_ENTRY, "Scalar"),
+            ] {
+                log.add_entry(
+                    ticks.join(&EntityPath::from_single_string(key)),
+                    Timestamp(0),
+                    "string",
+                    value.as_bytes(),
+                )
+                .unwrap();
+            }
+            log.add_entry(
+                ticks.join(&EntityPath::from_single_string("Scalar")),
+                Timestamp(1_000),
+                "int64",
+                &(1i64 << 60).to_le_bytes(),
+            )
+            .unwrap();
+
+            super::log_changes_to_chunks(
+                &StoreId::random(StoreKind::Recording),
+                &ApplicationId::random(),
+                Timeline::new_duration("robotime"),
+                &mut log,
+            )
+        };
+
+        // plotted, if inexactly, by default
+        let chunks = chunks_for(LargeIntegers::Cast);
+        assert!(
+            chunks
+                .iter()
+                .any(|c| c.entity_path() == &ticks
+                    && c.component_names().any(|n| n == Scalar::name()))
+        );
+
+        let chunks = chunks_for(LargeIntegers::Text);
+        assert!(
+            chunks
+                .iter()
+                .all(|c| !c.component_names().any(|n| n == Scalar::name()))
+        );
+        let text = chunks
+            .iter()
+            .find(|c| c.entity_path() == &ticks)
+            .expect("no text for the large value")
+            .iter_component::<Text>()
+            .flat_map(|batch| batch.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(text, [Text::from("1152921504606846976")]);
+    }
+
+    #[test]
+    fn large_plain_int64_scalars() {
+        let ticks = EntityPath::from_single_string("ticks");
+        let chunks_for = |large_integers| {
+            let mut log = EntryLog::with_config(Config {
+                large_integers,
+                ..Config::default()
+            });
+            log.add_entry(
+                ticks.clone(),
+                Timestamp(1_000),
+                "int64",
+                &(1i64 << 60).to_le_bytes(),
+            )
+            .unwrap();
+
+            super::log_changes_to_chunks(
+                &StoreId::random(StoreKind::Recording),
+                &ApplicationId::random(),
+                Timeline::new_duration("robotime"),
+                &mut log,
+            )
+        };
+
+        // a top-level number has no metadata, but is plotted all the same
+        let chunks = chunks_for(LargeIntegers::Cast);
+        assert!(
+            chunks
+                .iter()
+                .any(|c| c.entity_path() == &ticks
+                    && c.component_names().any(|n| n == Scalar::name()))
+        );
+
+        let chunks = chunks_for(LargeIntegers::Text);
+        assert!(
+            chunks
+                .iter()
+                .all(|c| !c.component_names().any(|n| n == Scalar::name()))
+        );
+        let text = chunks
+            .iter()
+            .find(|c| c.entity_path() == &ticks)
+            .expect("no text for the large value")
+            .iter_component::<Text>()
+            .flat_map(|batch| batch.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(text, [Text::from("1152921504606846976")]);
+    }
+
+    #[test]
+    fn deterministic_output() {
+        let convert = || {
+            let mut log = EntryLog::with_config(Config {
+                components: vec![ComponentOverride {
+                    pattern: EntityPattern("drive/pose".to_owned()),
+                    components: vec!["Pose2d".to_owned()],
+                }],
+                deterministic: true,
+                ..Config::default()
+            });
+            for timestamp in [1_000, 2_000, 3_000] {
+                for (path, value) in [
+                    ("drive/pose/translation/x", 1.0f64),
+                    ("drive/pose/translation/y", 2.0),
+                    ("drive/pose/rotation/value", 0.5),
+                    ("arm/angle", 3.0),
+                ] {
+                    log.add_entry(
+                        EntityPath::from_file_path(Path::new(path)),
+                        Timestamp(timestamp),
+                        "double",
+                        &value.to_le_bytes(),
+                    )
+                    .unwrap();
+                }
+            }
+            log.clear_entry(EntityPath::from_single_string("arm"), Timestamp(4_000));
+
+            let chunks = super::log_changes_to_chunks(
+                &StoreId::random(StoreKind::Recording),
+                &ApplicationId::random(),
+                Timeline::new_duration("robotime"),
+                &mut log,
+            );
+            super::renumbered(chunks, &mut log)
+                .iter()
+                .map(|chunk| (chunk.id(), chunk.to_arrow_msg().unwrap().batch.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let first = convert();
+        assert!(first.len() > 1);
+        assert_eq!(first, convert());
+    }
+
+    #[test]
+    fn renumbering_continues_across_conversions() {
+        let mut log = EntryLog::with_config(Config {
+            deterministic: true,
+            ..Config::default()
+        });
+        let mut convert = |timestamp| {
+            log.add_entry(
+                EntityPath::from_single_string("voltage"),
+                Timestamp(timestamp),
+                "double",
+                &12.5f64.to_le_bytes(),
+            )
+            .unwrap();
+            let chunks = super::log_changes_to_chunks(
+                &StoreId::random(StoreKind::Recording),
+                &ApplicationId::random(),
+                Timeline::new_duration("robotime"),
+                &mut log,
+            );
+            super::renumbered(chunks, &mut log)
+        };
+
+        let first = convert(1_000);
+        let second = convert(2_000);
+
+        let ids = |chunks: &[Chunk]| chunks.iter().map(Chunk::id).collect::<Vec<_>>();
+        assert_eq!(ids(&first), [ChunkId::from_u128(1)]);
+        assert_eq!(ids(&second), [ChunkId::from_u128(2)]);
+        assert_eq!(
+            second[0].row_ids().collect::<Vec<_>>(),
+            [RowId::from_u128(2)]
+        );
+    }
+
+    #[test]
+    fn severity_prefixed_text_logs() {
+        let mut log = EntryLog::with_config(Config {
+            text_logs: vec![serde_json::from_str(r#"{"pattern": "Messages"}"#).unwrap()],
+            ..Config::default()
+        });
+        let messages = EntityPath::from_single_string("Messages");
+        log.add_entry(messages.clone(), Timestamp(1_000), "string", b"[WARN] foo")
+            .unwrap();
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+        let chunk = chunks
+            .iter()
+            .find(|c| c.entity_path() == &messages)
+            .expect("no text log for the messages");
+
+        assert_eq!(
+            chunk
+                .iter_component::<Text>()
+                .flat_map(|batch| batch.to_vec())
+                .collect::<Vec<_>>(),
+            [Text::from("foo")]
+        );
+        assert_eq!(
+            chunk
+                .iter_component::<TextLogLevel>()
+                .flat_map(|batch| batch.to_vec())
+                .collect::<Vec<_>>(),
+            [TextLogLevel::from(TextLogLevel::WARN)]
+        );
+    }
+
+    #[test]
+    fn unprefixed_lines_arent_text_logs() {
+        let mut log = EntryLog::with_config(Config {
+            text_logs: vec![serde_json::from_str(r#"{"pattern": "Messages"}"#).unwrap()],
+            ..Config::default()
+        });
+        let messages = EntityPath::from_single_string("Messages");
+        log.add_entry(messages.clone(), Timestamp(1_000), "string", b"plain")
+            .unwrap();
+
+        let chunks = super::log_changes_to_chunks(
+            &StoreId::random(StoreKind::Recording),
+            &ApplicationId::random(),
+            Timeline::new_duration("robotime"),
+            &mut log,
+        );
+        assert!(
+            chunks
+                .iter()
+                .all(|c| c.iter_component::<TextLogLevel>().next().is_none()
+                    && c.iter_component::<Text>().next().is_none()),
+            "a line without a severity became a text log"
+        );
+        // still there as the string it was logged as
+        assert!(log.get_entry(&messages).is_some());
+    }
+}
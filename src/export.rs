@@ -0,0 +1,393 @@
+//! Exporting a parsed log to an Arrow IPC (Feather) file or line-delimited JSON for analysis
+//! outside the viewer.
+
+use std::{io::Write, sync::Arc};
+
+use rerun::external::{
+    anyhow,
+    arrow::{
+        array::{
+            Array, ArrayRef, DictionaryArray, Int32Array, RecordBatch, StringArray, UnionArray,
+            new_empty_array,
+        },
+        buffer::ScalarBuffer,
+        datatypes::{DataType, Field, Int32Type, Schema, UnionFields, UnionMode},
+        ipc::writer::FileWriter,
+    },
+    re_log,
+};
+
+use serde_json::json;
+
+use crate::{log::EntryLog, values::json::json_value};
+
+/// Writes every entity of `log` as one long table with `entity`, `timestamp` and `value` columns,
+/// a record batch per entity so only one is held in memory at a time.
+///
+/// `entity` is dictionary encoded, `timestamp` is in microseconds, and `value` is a dense union
+/// with a variant per distinct value type, so structs and lists keep their shape. Entities whose
+/// samples don't all have the same type can't be put in one column, so they're skipped.
+pub fn write_arrow(log: &EntryLog, writer: impl Write) -> Result<(), anyhow::Error> {
+    let mut paths = log.entity_paths().collect::<Vec<_>>();
+    paths.sort();
+
+    // the schema comes first, so every entity's type is needed before any of them is written
+    let mut entities = Vec::new();
+    let mut variants: Vec<DataType> = Vec::new();
+    for path in paths {
+        let Some(entry) = log.get_entry(path) else {
+            continue;
+        };
+        let mut types = entry.values().map(|value| value.data_type());
+        let Some(ty) = types.next().cloned() else {
+            continue;
+        };
+        if types.any(|other| *other != ty) {
+            re_log::warn!(
+                "skipping {path} in the export: its samples don't all have the same type"
+            );
+            continue;
+        }
+
+        let variant = match variants.iter().position(|variant| *variant == ty) {
+            Some(variant) => variant,
+            None => {
+                variants.push(ty);
+                variants.len() - 1
+            }
+        };
+        entities.push((path, variant));
+    }
+
+    let union_fields = UnionFields::new(
+        (0..).take(variants.len()),
+        variants
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| Field::new(i.to_string(), ty.clone(), true)),
+    );
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            "entity",
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+            false,
+        ),
+        Field::new("timestamp", DataType::UInt64, false),
+        Field::new(
+            "value",
+            DataType::Union(union_fields.clone(), UnionMode::Dense),
+            false,
+        ),
+    ]));
+    // shared by every batch, as a file can only have one dictionary
+    let names: ArrayRef = Arc::new(StringArray::from_iter_values(
+        entities.iter().map(|(path, _)| path.to_string()),
+    ));
+
+    let mut writer = FileWriter::try_new(writer, &schema)?;
+    for (entity, &(path, variant)) in entities.iter().enumerate() {
+        let batch = match log.to_record_batch(path) {
+            Ok(Some(batch)) => batch,
+            Ok(None) => continue,
+            Err(e) => {
+                re_log::warn!("skipping {path} in the export: {e}");
+                continue;
+            }
+        };
+        let rows = batch.num_rows();
+
+        // every other variant is empty in this entity's batch
+        let children = variants
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                if i == variant {
+                    batch.column(1).clone()
+                } else {
+                    new_empty_array(ty)
+                }
+            })
+            .collect();
+        let values = UnionArray::try_new(
+            union_fields.clone(),
+            ScalarBuffer::from(vec![i8::try_from(variant)?; rows]),
+            Some(ScalarBuffer::from(
+                (0..i32::try_from(rows)?).collect::<Vec<_>>(),
+            )),
+            children,
+        )?;
+        let entity = DictionaryArray::<Int32Type>::try_new(
+            Int32Array::from(vec![i32::try_from(entity)?; rows]),
+            names.clone(),
+        )?;
+
+        writer.write(&RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(entity), batch.column(0).clone(), Arc::new(values)],
+        )?)?;
+    }
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Writes every sample of `log` as one JSON object per line, with the timestamp `t` in
+/// microseconds, the `entity`, its value `type` and the `value` itself.
+///
+/// Structs become nested objects, arrays become JSON arrays and raw bytes are base64 encoded.
+pub fn write_ndjson(log: &EntryLog, mut writer: impl Write) -> Result<(), anyhow::Error> {
+    let mut paths = log.entity_paths().collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        let Some(entry) = log.get_entry(path) else {
+            continue;
+        };
+        let entity = path.to_string();
+
+        for (timestamp, array) in entry {
+            let ty = log
+                .struct_type(path)
+                .map_or_else(|| type_name(array.data_type()), str::to_owned);
+            for row in 0..array.len() {
+                let line = json!({
+                    "t": timestamp.0,
+                    "entity": entity,
+                    "type": ty,
+                    "value": json_value(array.as_ref(), row),
+                });
+                serde_json::to_writer(&mut writer, &line)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// The DataLog name of a value type, e.g. `double` or `int64[]`.
+fn type_name(ty: &DataType) -> String {
+    match ty {
+        DataType::Boolean => "boolean".to_owned(),
+        DataType::Float32 => "float".to_owned(),
+        DataType::Float64 => "double".to_owned(),
+        DataType::Int64 => "int64".to_owned(),
+        DataType::Utf8 | DataType::LargeUtf8 => "string".to_owned(),
+        DataType::Binary | DataType::LargeBinary => "raw".to_owned(),
+        DataType::Struct(_) => "struct".to_owned(),
+        DataType::List(field) | DataType::LargeList(field) | DataType::FixedSizeList(field, _) => {
+            format!("{}[]", type_name(field.data_type()))
+        }
+        ty => ty.to_string().to_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::Path, sync::Arc};
+
+    use hashbrown::HashMap;
+    use rerun::{
+        EntityPath,
+        external::arrow::{
+            array::{AsArray as _, Float64Array, RecordBatch},
+            compute::cast,
+            datatypes::{DataType, Float64Type, UInt64Type},
+            ipc::reader::FileReader,
+        },
+    };
+    use serde_json::json;
+
+    use crate::{
+        config::{Config, StructMode},
+        log::{EntryLog, Timestamp},
+        values::EntryValue,
+    };
+
+    #[test]
+    fn round_trip() {
+        let mut log = EntryLog::with_config(Config {
+            struct_mode: StructMode::Nested,
+            ..Config::default()
+        });
+        for (timestamp, speed) in [(1_000, 1.5), (2_000, 2.5)] {
+            log.add_entry(
+                EntityPath::from_file_path(Path::new("drive/speed")),
+                Timestamp(timestamp),
+                "double",
+                &f64::to_le_bytes(speed),
+            )
+            .unwrap();
+        }
+        log.add_entryvalue(
+            EntityPath::from_single_string("pose"),
+            Timestamp(1_500),
+            EntryValue::Map(HashMap::from([(
+                "x".to_owned(),
+                EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([3.0]))),
+            )])),
+        )
+        .unwrap();
+
+        let mut file = Vec::new();
+        super::write_arrow(&log, &mut file).unwrap();
+
+        let batches = FileReader::try_new(Cursor::new(file), None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        // a batch per entity
+        let [speed, pose] = &batches[..] else {
+            panic!("expected two batches, got {}", batches.len());
+        };
+
+        let entities = |batch: &RecordBatch| {
+            cast(batch.column(0), &DataType::Utf8)
+                .unwrap()
+                .as_string::<i32>()
+                .iter()
+                .flatten()
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(entities(speed), ["/drive/speed", "/drive/speed"]);
+        assert_eq!(entities(pose), ["/pose"]);
+
+        let timestamps = speed.column(1).as_primitive::<UInt64Type>();
+        assert_eq!(timestamps.values()[..], [1_000, 2_000]);
+        let timestamps = pose.column(1).as_primitive::<UInt64Type>();
+        assert_eq!(timestamps.values()[..], [1_500]);
+
+        let values = speed.column(2).as_union();
+        assert_eq!(
+            values.child(0).as_primitive::<Float64Type>().values()[..],
+            [1.5, 2.5]
+        );
+        let pose = pose.column(2).as_union().child(1).as_struct();
+        assert_eq!(
+            pose.column_by_name("x")
+                .unwrap()
+                .as_primitive::<Float64Type>()
+                .values()[..],
+            [3.0]
+        );
+    }
+
+    #[test]
+    fn mixed_types_are_skipped() {
+        let mut log = EntryLog::new();
+        let current = EntityPath::from_single_string("current");
+        log.add_entry(
+            current.clone(),
+            Timestamp(1_000),
+            "double",
+            &1.5f64.to_le_bytes(),
+        )
+        .unwrap();
+        // republished with another type
+        log.add_entry(current, Timestamp(2_000), "int64", &2i64.to_le_bytes())
+            .unwrap();
+        log.add_entry(
+            EntityPath::from_single_string("voltage"),
+            Timestamp(1_000),
+            "double",
+            &12.0f64.to_le_bytes(),
+        )
+        .unwrap();
+
+        let mut file = Vec::new();
+        super::write_arrow(&log, &mut file).unwrap();
+        let batches = FileReader::try_new(Cursor::new(file), None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let [voltage] = &batches[..] else {
+            panic!("expected one batch, got {}", batches.len());
+        };
+        assert_eq!(
+            cast(voltage.column(0), &DataType::Utf8)
+                .unwrap()
+                .as_string::<i32>()
+                .value(0),
+            "/voltage"
+        );
+    }
+
+    #[test]
+    fn import_round_trip() {
+        let mut log = EntryLog::new();
+        crate::wpilog::load::load_parts(
+            &[include_bytes!("../test_data/entities.wpilog").to_vec()],
+            &mut log,
+        );
+
+        let mut file = Vec::new();
+        super::write_arrow(&log, &mut file).unwrap();
+        let batches = FileReader::try_new(Cursor::new(file), None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let imported = EntryLog::from_record_batches(Config::default(), batches).unwrap();
+
+        let mut paths = log.entity_paths().collect::<Vec<_>>();
+        paths.sort();
+        let mut imported_paths = imported.entity_paths().collect::<Vec<_>>();
+        imported_paths.sort();
+        assert!(!paths.is_empty());
+        assert_eq!(imported_paths, paths);
+
+        for path in paths {
+            assert_eq!(imported.get_entry(path), log.get_entry(path), "{path}");
+        }
+    }
+
+    #[test]
+    fn ndjson_lines_parse_back() {
+        let mut log = EntryLog::with_config(Config {
+            struct_mode: StructMode::Nested,
+            ..Config::default()
+        });
+        log.add_entry(
+            EntityPath::from_file_path(Path::new("drive/speed")),
+            Timestamp(1_000),
+            "double",
+            &f64::to_le_bytes(1.5),
+        )
+        .unwrap();
+        log.add_entry(
+            EntityPath::from_file_path(Path::new("blob")),
+            Timestamp(2_000),
+            "raw",
+            b"hello",
+        )
+        .unwrap();
+        log.add_entryvalue(
+            EntityPath::from_single_string("pose"),
+            Timestamp(1_500),
+            EntryValue::Map(HashMap::from([(
+                "x".to_owned(),
+                EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([3.0]))),
+            )])),
+        )
+        .unwrap();
+
+        let mut file = Vec::new();
+        super::write_ndjson(&log, &mut file).unwrap();
+
+        let lines = String::from_utf8(file)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                json!({"t": 2_000, "entity": "/blob", "type": "raw", "value": "aGVsbG8="}),
+                json!({"t": 1_000, "entity": "/drive/speed", "type": "double", "value": 1.5}),
+                json!({"t": 1_500, "entity": "/pose", "type": "struct", "value": {"x": 3.0}}),
+            ]
+        );
+    }
+}
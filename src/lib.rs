@@ -0,0 +1,15 @@
+//! Decoding of WPILib DataLog files and NetworkTables data into Rerun chunks.
+
+#![warn(clippy::nursery, clippy::pedantic)]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+pub mod analysis;
+pub mod blueprint;
+pub mod cli;
+pub mod config;
+pub mod conv;
+pub mod export;
+pub mod log;
+pub mod nt;
+pub mod values;
+pub mod wpilog;
@@ -1,43 +1,234 @@
-use std::{collections::BTreeMap, num::TryFromIntError, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::Arc,
+};
 
 use hashbrown::{HashMap, HashSet};
 use rerun::{
-    EntityPath,
+    EntityPath, TimePoint, Timeline,
     external::{
-        anyhow,
-        arrow::array::{ArrayRef, Int64Array},
+        anyhow::{self, Context as _},
+        arrow::{
+            self,
+            array::{
+                Array as _, ArrayRef, AsArray as _, BooleanArray, Float64Array, Int64Array,
+                RecordBatch, StringArray, StructArray, UInt64Array,
+            },
+            datatypes::{DataType, Field, Schema, UInt64Type},
+        },
         nohash_hasher::IntMap,
         re_log,
         re_log_types::NonMinI64,
     },
-    time::TimeInt,
+    time::{TimeInt, TimeType},
 };
 
+use crate::config::{Config, DuplicateTimestamps, EntityPattern, Retention, StructMode};
 use crate::values::{
-    EntryValue, EntryValueParseError,
-    parse::wpistruct::{UnresolvedWpiLibStructType, WpiLibStructSchema, WpiLibStructType},
+    EntryValue, EntryValueParseError, decode_payload,
+    decoders::Decoders,
+    image::FrameFormat,
+    json::json_value,
+    parse::wpistruct::{
+        UnresolvedWpiLibStructType, WpiLibStructSchema, WpiLibStructType, struct_type_name,
+    },
 };
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 /// The timestamp of an entry in the log.
 ///
 /// Measured in microseconds since the RIO was enabled.
 pub struct Timestamp(pub u64);
 
+impl Timestamp {
+    /// The timestamp in seconds.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn as_secs_f64(self) -> f64 {
+        self.0 as f64 / 1e6
+    }
+
+    /// The timestamp as a time of type `typ`: nanoseconds for a duration, and the timestamp's own
+    /// microseconds for a sequence.
+    ///
+    /// # Errors
+    /// If the timestamp doesn't fit in an `i64` of that unit.
+    pub fn time_int(self, typ: TimeType) -> Result<TimeInt, anyhow::Error> {
+        match typ {
+            TimeType::Sequence => Ok(TimeInt::from_sequence(
+                NonMinI64::new(self.0.try_into()?).unwrap_or_default(),
+            )),
+            _ => self.try_into(),
+        }
+    }
+
+    /// The time point of this timestamp on `timeline`, in the timeline's precision.
+    ///
+    /// # Errors
+    /// If the timestamp doesn't fit on `timeline`.
+    pub fn time_point(self, timeline: Timeline) -> Result<TimePoint, anyhow::Error> {
+        Ok(TimePoint::default().with(timeline, self.time_int(timeline.typ())?))
+    }
+}
+
+impl From<Timestamp> for std::time::Duration {
+    fn from(timestamp: Timestamp) -> Self {
+        Self::from_micros(timestamp.0)
+    }
+}
+
 impl TryInto<TimeInt> for Timestamp {
-    type Error = TryFromIntError;
+    type Error = anyhow::Error;
     fn try_into(self) -> Result<TimeInt, Self::Error> {
+        let nanos = self
+            .0
+            .checked_mul(1000)
+            .with_context(|| format!("timestamp {}us overflows in nanoseconds", self.0))?;
         Ok(TimeInt::from_nanos(
-            NonMinI64::new((self.0 * 1000).try_into()?).unwrap_or_default(),
+            NonMinI64::new(nanos.try_into()?).unwrap_or_default(),
         ))
     }
 }
 
+/// How many records the log has per second, counted in buckets of
+/// [`Config::record_rate`] seconds.
+#[must_use]
+pub fn record_rate_path() -> EntityPath {
+    EntityPath::from_single_string("$meta")
+        .join(&EntityPath::from_single_string("records_per_second"))
+}
+
+/// The child an array's length is logged to.
+///
+/// The `$` keeps the children an array is exploded into apart from struct fields and JSON keys
+/// named `length` or `0`. It's rare in entry names but allowed, so an entry actually named
+/// `$length` under an array's path would share this child.
+pub const ARRAY_LENGTH: &str = "$length";
+
+/// The child an array's length is logged to, see [`ARRAY_LENGTH`].
+#[must_use]
+pub fn array_length(array: &EntityPath) -> EntityPath {
+    array.join(&EntityPath::from_single_string(ARRAY_LENGTH))
+}
+
+/// The child the `i`th element of an array is logged to, namespaced like [`ARRAY_LENGTH`].
+#[must_use]
+pub fn array_element(array: &EntityPath, i: usize) -> EntityPath {
+    array.join(&EntityPath::from_single_string(format!("${i}")))
+}
+
+/// A struct type that entries used but whose schema was never registered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnresolvedStruct {
+    pub name: String,
+    /// How many values are still queued waiting for the schema.
+    pub samples: usize,
+    pub entities: BTreeSet<EntityPath>,
+}
+
+/// How much of a log was decoded and rendered, by entry. Struct schemas aren't counted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub entries: usize,
+    pub decoded: usize,
+    /// Decoded entries with at least one value that became a component.
+    pub rendered: usize,
+    /// Entries that never decoded because their type isn't known.
+    pub unknown_type: usize,
+}
+
+impl std::fmt::Display for Coverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} entries decoded, {} rendered as components, {} unknown-type dropped",
+            self.decoded, self.entries, self.rendered, self.unknown_type
+        )
+    }
+}
+
+/// Whether an entry's values have decoded, see [`Coverage`].
+#[derive(Clone, Copy, Debug, Default)]
+struct EntryStatus {
+    decoded: bool,
+    unknown_type: bool,
+}
+
+/// Called with each value as it's inserted into the log, see [`EntryLog::set_observer`].
+pub type Observer = dyn FnMut(&EntityPath, Timestamp, &ArrayRef) + Send;
+
+/// A payload decoded ahead of being added to a log.
+pub struct Decoded {
+    /// The payload with its `int32` booleans narrowed, if they were.
+    narrowed: Option<Vec<u8>>,
+    parsed: Result<EntryValue, EntryValueParseError>,
+}
+
+/// Decodes payloads the way an [`EntryLog`] does, from any thread.
+#[derive(Clone, Copy)]
+pub struct PayloadDecoder<'log> {
+    int32_booleans: &'log [EntityPattern],
+    struct_map: &'log HashMap<String, WpiLibStructSchema<UnresolvedWpiLibStructType>>,
+    decoders: &'log Decoders,
+}
+
+impl PayloadDecoder<'_> {
+    /// Whether payloads of type `ty` decode the same whenever they're decoded, rather than
+    /// depending on the struct schemas registered before them.
+    #[must_use]
+    pub fn is_independent(ty: &str) -> bool {
+        !ty.starts_with("struct:") && !matches!(ty, "structschema" | "jsonschema")
+    }
+
+    /// Decodes a payload of the entry logged to `key`.
+    #[must_use]
+    pub fn decode(&self, key: &EntityPath, ty: &str, value: &[u8]) -> Decoded {
+        let narrowed = if self.int32_booleans.iter().any(|p| p.matches(key)) {
+            EntryValue::narrow_int32_booleans(ty, value)
+        } else {
+            None
+        };
+        let parsed = decode_payload(
+            ty,
+            narrowed.as_deref().unwrap_or(value),
+            self.struct_map,
+            self.decoders,
+        );
+
+        Decoded { narrowed, parsed }
+    }
+}
+
 pub struct EntryLog {
+    config: Config,
     entries: IntMap<EntityPath, BTreeMap<Timestamp, ArrayRef>>,
     changed: HashSet<(EntityPath, Timestamp)>,
+    cleared: Vec<(EntityPath, Timestamp)>,
+    decode_errors: IntMap<EntityPath, usize>,
+    schema_warnings: Vec<String>,
+    value_warnings: Vec<String>,
+    statuses: IntMap<EntityPath, EntryStatus>,
+    rendered: HashSet<EntityPath>,
+    /// Entities whose series style was already logged.
+    styled: HashSet<EntityPath>,
+    frame_formats: IntMap<EntityPath, FrameFormat>,
+    struct_types: IntMap<EntityPath, String>,
+    metadata: IntMap<EntityPath, String>,
+    documents: Vec<(EntityPath, Timestamp, String)>,
     struct_map: HashMap<String, WpiLibStructSchema<UnresolvedWpiLibStructType>>,
+    decoders: Decoders,
+    observer: Option<Box<Observer>>,
     pub queued_structs: HashMap<String, Vec<(EntityPath, Timestamp, String, Vec<u8>)>>,
+    /// Records counted per bucket, by bucket index.
+    record_counts: BTreeMap<u64, usize>,
+    /// Unlimited unless set, so loaded files are kept whole.
+    retention: Retention,
+    /// The next chunk and row ids numbered output is given, counting up across conversions so
+    /// no two chunks of a recording share one.
+    next_chunk_id: u128,
+    next_row_id: u128,
 }
 
 impl Default for EntryLog {
@@ -49,20 +240,242 @@ impl Default for EntryLog {
 impl EntryLog {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    #[must_use]
+    pub fn with_config(config: Config) -> Self {
         Self {
+            config,
             entries: IntMap::default(),
             changed: HashSet::new(),
+            cleared: Vec::new(),
+            decode_errors: IntMap::default(),
+            schema_warnings: Vec::new(),
+            value_warnings: Vec::new(),
+            statuses: IntMap::default(),
+            rendered: HashSet::new(),
+            styled: HashSet::new(),
+            frame_formats: IntMap::default(),
+            struct_types: IntMap::default(),
+            metadata: IntMap::default(),
+            documents: Vec::new(),
             struct_map: HashMap::new(),
+            decoders: Decoders::default(),
+            observer: None,
             queued_structs: HashMap::new(),
+            record_counts: BTreeMap::new(),
+            retention: Retention::default(),
+            next_chunk_id: 1,
+            next_row_id: 1,
         }
     }
 
+    /// Calls `observer` with every value inserted from now on, after structs, maps and arrays are
+    /// flattened, so it sees the same values as [`Self::get_changed`].
+    pub fn set_observer(
+        &mut self,
+        observer: impl FnMut(&EntityPath, Timestamp, &ArrayRef) + Send + 'static,
+    ) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Caps each entity's samples from now on, for a live session that never ends.
+    pub const fn set_retention(&mut self, retention: Retention) {
+        self.retention = retention;
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Claims the next id for a chunk of numbered rather than random output.
+    pub const fn claim_chunk_id(&mut self) -> u128 {
+        let id = self.next_chunk_id;
+        self.next_chunk_id += 1;
+        id
+    }
+
+    /// Claims `rows` consecutive row ids for numbered rather than random output, returning the
+    /// first.
+    pub const fn claim_row_ids(&mut self, rows: u128) -> u128 {
+        let first = self.next_row_id;
+        self.next_row_id += rows;
+        first
+    }
+
+    /// Counts a value of `key` that failed to decode, returning how many have failed so far.
+    pub fn record_decode_error(&mut self, key: &EntityPath) -> usize {
+        let count = self.decode_errors.entry(key.clone()).or_default();
+        *count += 1;
+        *count
+    }
+
+    /// Gets how many values of each entity failed to decode.
+    pub fn decode_errors(&self) -> impl Iterator<Item = (&EntityPath, usize)> {
+        self.decode_errors.iter().map(|(key, count)| (key, *count))
+    }
+
+    /// Gets the problems found in struct schemas as they were registered, like enum values that
+    /// don't fit their field.
+    #[must_use]
+    pub fn schema_warnings(&self) -> &[String] {
+        &self.schema_warnings
+    }
+
+    /// Gets why values were skipped as they were added, like a leaf that isn't a single value.
+    #[must_use]
+    pub fn value_warnings(&self) -> &[String] {
+        &self.value_warnings
+    }
+
+    /// Marks values that were rendered as components, by the keys they were inserted at.
+    pub fn mark_rendered(&mut self, keys: impl IntoIterator<Item = EntityPath>) {
+        self.rendered.extend(keys);
+    }
+
+    /// Marks an entity's series style as logged, returning whether it hadn't been already.
+    pub fn mark_styled(&mut self, entity: EntityPath) -> bool {
+        self.styled.insert(entity)
+    }
+
+    /// Counts how many entries decoded and were rendered.
+    #[must_use]
+    pub fn coverage(&self) -> Coverage {
+        // a struct entry's values are rendered from the keys it was flattened into
+        let rendered = self
+            .rendered
+            .iter()
+            .filter_map(|key| {
+                std::iter::successors(Some(key.clone()), EntityPath::parent).find(|entry| {
+                    self.statuses
+                        .get(entry)
+                        .is_some_and(|status| status.decoded)
+                })
+            })
+            .collect::<HashSet<_>>();
+
+        Coverage {
+            entries: self.statuses.len(),
+            decoded: self
+                .statuses
+                .values()
+                .filter(|status| status.decoded)
+                .count(),
+            rendered: rendered.len(),
+            unknown_type: self
+                .statuses
+                .values()
+                .filter(|status| status.unknown_type && !status.decoded)
+                .count(),
+        }
+    }
+
+    /// Summarizes the values still queued for a struct schema, by struct name.
+    ///
+    /// Once the whole log is loaded, these values will never be decoded.
+    #[must_use]
+    pub fn unresolved_structs(&self) -> Vec<UnresolvedStruct> {
+        let mut unresolved = self
+            .queued_structs
+            .iter()
+            .map(|(name, queued)| UnresolvedStruct {
+                name: name.clone(),
+                samples: queued.len(),
+                entities: queued.iter().map(|(key, ..)| key.clone()).collect(),
+            })
+            .collect::<Vec<_>>();
+        unresolved.sort_by(|a, b| a.name.cmp(&b.name));
+
+        unresolved
+    }
+
+    /// Sets the metadata of an entry, replacing whatever it had before.
+    pub fn set_metadata(&mut self, key: EntityPath, metadata: impl Into<String>) {
+        self.metadata.insert(key, metadata.into());
+    }
+
+    #[must_use]
+    pub fn metadata(&self, key: &EntityPath) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+
+    /// Marks the `raw` values of `key` as camera frames of the given format.
+    pub fn set_frame_format(&mut self, key: EntityPath, format: FrameFormat) {
+        self.frame_formats.insert(key, format);
+    }
+
+    #[must_use]
+    pub fn frame_format(&self, key: &EntityPath) -> Option<&FrameFormat> {
+        self.frame_formats.get(key)
+    }
+
+    /// The struct type (e.g. `struct:Rotation2d`) of the single struct values logged to `key`.
+    #[must_use]
+    pub fn struct_type(&self, key: &EntityPath) -> Option<&str> {
+        self.struct_types.get(key).map(String::as_str)
+    }
+
+    /// Gets every registered struct schema, by type name (e.g. `struct:Pose2d`).
+    #[must_use]
+    pub const fn structs(
+        &self,
+    ) -> &HashMap<String, WpiLibStructSchema<UnresolvedWpiLibStructType>> {
+        &self.struct_map
+    }
+
+    /// Whether the struct schema `name` (e.g. `struct:Pose2d`) is registered, along with every
+    /// struct it nests.
+    #[must_use]
+    pub fn can_resolve(&self, name: &str) -> bool {
+        self.struct_map
+            .get(&*struct_type_name(name))
+            .is_some_and(|schema| schema.resolve(&self.struct_map).is_ok())
+    }
+
+    /// Resolves every registered struct schema, by name, or gets the name of the first struct it
+    /// nests that isn't registered.
+    #[must_use]
+    pub fn resolve_all(
+        &self,
+    ) -> Vec<(String, Result<WpiLibStructSchema<WpiLibStructType>, String>)> {
+        let mut resolved = self
+            .struct_map
+            .iter()
+            .map(|(name, schema)| (name.clone(), schema.resolve(&self.struct_map)))
+            .collect::<Vec<_>>();
+        resolved.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        resolved
+    }
+
+    /// The decoders consulted for entry types that aren't built in, to register custom ones with
+    /// before loading.
+    pub const fn decoders_mut(&mut self) -> &mut Decoders {
+        &mut self.decoders
+    }
+
+    /// Registers a struct schema, under `struct:<name>` whether or not `name` has the prefix.
     pub fn add_struct(
         &mut self,
         name: impl Into<String>,
         s: WpiLibStructSchema<UnresolvedWpiLibStructType>,
     ) {
-        self.struct_map.insert(name.into(), s);
+        let name = name.into();
+        self.struct_map
+            .insert(struct_type_name(&name).into_owned(), s);
+    }
+
+    /// Decodes payloads the way this log does, so they can be decoded on other threads before
+    /// being added with [`Self::add_decoded`].
+    #[must_use]
+    pub fn decoder(&self) -> PayloadDecoder<'_> {
+        PayloadDecoder {
+            int32_booleans: &self.config.int32_booleans,
+            struct_map: &self.struct_map,
+            decoders: &self.decoders,
+        }
     }
 
     pub fn add_entry(
@@ -72,10 +485,69 @@ impl EntryLog {
         ty: &str,
         value: &[u8],
     ) -> Result<(), anyhow::Error> {
-        match EntryValue::parse_from_wpilog(ty, value, &self.struct_map) {
+        let decoded = self.decoder().decode(&key, ty, value);
+        self.add_decoded(key, timestamp, ty, value, decoded)
+    }
+
+    /// Adds a payload decoded by [`Self::decoder`], as [`Self::add_entry`] would have. Payloads
+    /// of types that aren't [`PayloadDecoder::is_independent`] must have been decoded since the
+    /// last schema was added.
+    pub fn add_decoded(
+        &mut self,
+        key: EntityPath,
+        timestamp: Timestamp,
+        ty: &str,
+        value: &[u8],
+        decoded: Decoded,
+    ) -> Result<(), anyhow::Error> {
+        let Decoded { narrowed, parsed } = decoded;
+        let value = narrowed.as_deref().unwrap_or(value);
+
+        if ty.starts_with("struct:") && !ty.ends_with(']') {
+            self.struct_types
+                .entry(key.clone())
+                .or_insert_with(|| struct_type_name(ty).into_owned());
+        }
+
+        if self.config.log_schemas
+            && let Ok(EntryValue::StructSchema(schema)) = &parsed
+            && let Some(name) = key.last()
+        {
+            let name = name.unescaped_str();
+            self.documents.push((
+                EntityPath::from_single_string(name)
+                    .join(&EntityPath::from_single_string(".schema")),
+                timestamp,
+                schema_document(name, value, schema),
+            ));
+        }
+
+        if !matches!(ty, "structschema" | "jsonschema") {
+            let status = self.statuses.entry(key.clone()).or_default();
+            match &parsed {
+                Ok(_) => status.decoded = true,
+                Err(EntryValueParseError::UnknownType(_)) => status.unknown_type = true,
+                Err(_) => {}
+            }
+        }
+
+        match parsed {
             Ok(v) => self.add_entryvalue(key, timestamp, v),
+            // one bad schema shouldn't stop the rest of the log loading, the entries that needed it
+            // stay queued and are reported as unresolved
+            Err(e) if matches!(ty, "structschema" | "jsonschema") => {
+                re_log::warn!(
+                    "struct schema {key} at {:.3}s is malformed: {e}",
+                    timestamp.as_secs_f64()
+                );
+
+                Ok(())
+            }
             Err(EntryValueParseError::StructNotFound(s)) => {
-                re_log::info!("struct not found: {s} for key {key} at {}", timestamp.0);
+                re_log::info!(
+                    "struct not found: {s} for key {key} at {:.3}s",
+                    timestamp.as_secs_f64()
+                );
                 self.queued_structs.entry(s).or_default().push((
                     key,
                     timestamp,
@@ -86,6 +558,35 @@ impl EntryLog {
                 Ok(())
             }
             Err(EntryValueParseError::Other(e)) => Err(e),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Drops the oldest samples of the entry at `key` that are past the retention limits, along
+    /// with their pending changes.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn evict(
+        key: &EntityPath,
+        entry: &mut BTreeMap<Timestamp, ArrayRef>,
+        retention: Retention,
+        changed: &mut HashSet<(EntityPath, Timestamp)>,
+    ) {
+        let mut evicted = Vec::new();
+        if let Some(max_samples) = retention.max_samples {
+            while entry.len() > max_samples {
+                evicted.extend(entry.pop_first().map(|(timestamp, _)| timestamp));
+            }
+        }
+
+        if let (Some(max_age), Some((&newest, _))) = (retention.max_age, entry.last_key_value()) {
+            // microseconds
+            let oldest = Timestamp(newest.0.saturating_sub((max_age.max(0.0) * 1e6) as u64));
+            let kept = entry.split_off(&oldest);
+            evicted.extend(std::mem::replace(entry, kept).into_keys());
+        }
+
+        for timestamp in evicted {
+            changed.remove(&(key.clone(), timestamp));
         }
     }
 
@@ -95,37 +596,83 @@ impl EntryLog {
         timestamp: Timestamp,
         value: EntryValue,
     ) -> Result<(), anyhow::Error> {
+        if is_nested(&value) && self.too_deep(&key) {
+            return self.add_entryvalue(key, timestamp, json_text(entry_value_json(value)));
+        }
+
         match value {
+            // each sample of an entity is one value, which everything reading it back expects
+            EntryValue::Arrow(array) if array.len() != 1 => {
+                let warning = format!(
+                    "skipping {key} at {:.3}s: expected a single value, got {}",
+                    timestamp.as_secs_f64(),
+                    array.len()
+                );
+                re_log::warn!("{warning}");
+                self.value_warnings.push(warning);
+            }
             EntryValue::Arrow(array) => {
                 let entry = self.entries.entry(key.clone()).or_default();
-                entry.insert(timestamp, array);
 
-                self.changed.insert((key, timestamp));
+                let mut timestamp = timestamp;
+                if self.config.duplicate_timestamps == DuplicateTimestamps::Nudge {
+                    while entry.contains_key(&timestamp) {
+                        // nowhere left to nudge it to, so it overwrites the last sample instead
+                        let Some(next) = timestamp.0.checked_add(1) else {
+                            break;
+                        };
+                        timestamp = Timestamp(next);
+                    }
+                }
+                if let Some(observer) = &mut self.observer {
+                    observer(&key, timestamp, &array);
+                }
+                entry.insert(timestamp, array);
+                self.changed.insert((key.clone(), timestamp));
+                Self::evict(&key, entry, self.retention, &mut self.changed);
             }
             EntryValue::StructSchema(s) => {
                 let name = key.last().map_or("struct:Unknown", |s| s.unescaped_str());
+                let name = &*struct_type_name(name);
+
+                for (field, label, value) in s.out_of_range_enum_values() {
+                    let warning = format!(
+                        "struct {name}: enum value {label} = {value} doesn't fit the type of field {field}"
+                    );
+                    re_log::warn!("{warning}");
+                    self.schema_warnings.push(warning);
+                }
                 self.add_struct(name, s);
 
-                re_log::info!("new struct schema {name} at {}", timestamp.0);
+                re_log::info!(
+                    "new struct schema {name} at {:.3}s",
+                    timestamp.as_secs_f64()
+                );
 
                 if let Some(queued) = self.queued_structs.remove(name) {
                     for (key, timestamp, ty, data) in queued {
-                        re_log::info!("unqueued struct {name} for {key} at {}", timestamp.0);
-                        self.add_entry(key, timestamp, &ty, &data).unwrap();
+                        re_log::info!(
+                            "unqueued struct {name} for {key} at {:.3}s",
+                            timestamp.as_secs_f64()
+                        );
+                        if let Err(e) = self.add_entry(key.clone(), timestamp, &ty, &data) {
+                            re_log::warn!(
+                                "queued {key} at {:.3}s failed to decode: {e}",
+                                timestamp.as_secs_f64()
+                            );
+                        }
                     }
                 }
             }
-            // treat maps transparently as a set of entries
-            EntryValue::Map(map) => {
-                for (k, v) in map {
-                    self.add_entryvalue(
-                        key.join(&EntityPath::from_file_path(Path::new(&k))),
-                        timestamp,
-                        v,
-                    )
-                    .unwrap();
+            EntryValue::Map(map) if self.config.struct_mode == StructMode::Nested => {
+                match map_to_struct_array(&map) {
+                    Some(array) => self.add_entryvalue(key, timestamp, EntryValue::Arrow(array))?,
+                    // arrays can't be nested into a single row, so fall back to flattening
+                    None => self.add_flattened_map(&key, timestamp, map)?,
                 }
             }
+            // treat maps transparently as a set of entries
+            EntryValue::Map(map) => self.add_flattened_map(&key, timestamp, map)?,
 
             EntryValue::ArrayMap(m) => {
                 let count = m.len();
@@ -133,13 +680,92 @@ impl EntryLog {
             }
             EntryValue::ArrayArrow(a) => {
                 let count = a.len();
-                self.handle_array(key, timestamp, a.into_iter().map(EntryValue::Arrow), count)?;
+                self.handle_array(
+                    key,
+                    timestamp,
+                    a.into_iter().map(array_element_value),
+                    count,
+                )?;
             }
+            EntryValue::Json(json) => self.add_json(key, timestamp, json)?,
         }
 
         Ok(())
     }
 
+    /// Whether `key` is as deep as values are flattened, warning the first time it is.
+    fn too_deep(&self, key: &EntityPath) -> bool {
+        let max_depth = self.config.max_depth();
+        if key.len() < max_depth {
+            return false;
+        }
+
+        re_log::warn_once!(
+            "{key} is nested {max_depth} deep, so its values are logged as JSON text rather than flattened"
+        );
+        true
+    }
+
+    fn add_flattened_map(
+        &mut self,
+        key: &EntityPath,
+        timestamp: Timestamp,
+        map: HashMap<String, EntryValue>,
+    ) -> Result<(), anyhow::Error> {
+        for (k, v) in map {
+            self.add_entryvalue(
+                key.join(&EntityPath::from_file_path(Path::new(&k))),
+                timestamp,
+                v,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn add_json(
+        &mut self,
+        key: EntityPath,
+        timestamp: Timestamp,
+        json: serde_json::Value,
+    ) -> Result<(), anyhow::Error> {
+        if (json.is_array() || json.is_object()) && self.too_deep(&key) {
+            return self.add_entryvalue(key, timestamp, json_text(json));
+        }
+
+        let array: ArrayRef = match json {
+            // nothing to log
+            serde_json::Value::Null => return Ok(()),
+            serde_json::Value::Bool(b) => Arc::new(BooleanArray::from(vec![b])),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(n) => Arc::new(Int64Array::from_iter_values([n])),
+                None => Arc::new(Float64Array::from_iter_values(n.as_f64())),
+            },
+            serde_json::Value::String(s) => Arc::new(StringArray::from_iter_values([s])),
+            serde_json::Value::Array(values) => {
+                let count = values.len();
+                return self.handle_array(
+                    key,
+                    timestamp,
+                    values.into_iter().map(EntryValue::Json),
+                    count,
+                );
+            }
+            serde_json::Value::Object(map) => {
+                for (k, v) in map {
+                    self.add_json(
+                        key.join(&EntityPath::from_file_path(Path::new(&k))),
+                        timestamp,
+                        v,
+                    )?;
+                }
+                return Ok(());
+            }
+        };
+
+        self.add_entryvalue(key, timestamp, EntryValue::Arrow(array))
+    }
+
     fn handle_array(
         &mut self,
         path: EntityPath,
@@ -147,23 +773,96 @@ impl EntryLog {
         arr: impl Iterator<Item = EntryValue>,
         count: usize,
     ) -> Result<(), anyhow::Error> {
-        self.add_entryvalue(
-            path.join(&EntityPath::from_single_string("length")),
-            timestamp,
-            EntryValue::Arrow(Arc::new(Int64Array::from_iter_values([count as i64]))),
-        )?;
+        let settings = self.config.array_settings(&path);
 
-        for (i, value) in arr.enumerate() {
+        if settings.length {
             self.add_entryvalue(
-                path.join(&EntityPath::from_single_string(i.to_string())),
+                array_length(&path),
                 timestamp,
-                value,
+                EntryValue::Arrow(Arc::new(Int64Array::from_iter_values([count as i64]))),
             )?;
         }
 
+        if !settings.indices {
+            return Ok(());
+        }
+
+        for (i, value) in arr
+            .take(settings.max_indices.unwrap_or(usize::MAX))
+            .enumerate()
+        {
+            self.add_entryvalue(array_element(&path, i), timestamp, value)?;
+        }
+
         Ok(())
     }
 
+    /// Marks the end of an entry's lifetime, clearing it (and any children) at `timestamp`.
+    pub fn clear_entry(&mut self, key: EntityPath, timestamp: Timestamp) {
+        self.cleared.push((key, timestamp));
+    }
+
+    /// Counts a record logged at `timestamp` towards [`record_rate_path`], if it's enabled.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn count_record(&mut self, timestamp: Timestamp) {
+        let Some(bucket) = self.config.record_rate.filter(|bucket| *bucket > 0.0) else {
+            return;
+        };
+
+        let index = (timestamp.as_secs_f64() / bucket) as u64;
+        *self.record_counts.entry(index).or_default() += 1;
+    }
+
+    /// Logs the records counted so far as a rate at [`record_rate_path`], dropping to zero for the
+    /// buckets without any, when the logger stalled.
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    pub fn add_record_rate(&mut self) -> Result<(), anyhow::Error> {
+        let Some(bucket) = self.config.record_rate.filter(|bucket| *bucket > 0.0) else {
+            return Ok(());
+        };
+        let mut counts = std::mem::take(&mut self.record_counts)
+            .into_iter()
+            .peekable();
+
+        while let Some((index, count)) = counts.next() {
+            let mut rates = vec![(index, count as f64 / bucket)];
+            // a gap is only bounded by a zero at each end, as a corrupt timestamp can put it
+            // arbitrarily many buckets long
+            if let Some(&(next, _)) = counts.peek()
+                && next > index + 1
+            {
+                rates.push((index + 1, 0.0));
+                if next - 1 > index + 1 {
+                    rates.push((next - 1, 0.0));
+                }
+            }
+
+            for (index, rate) in rates {
+                self.add_entryvalue(
+                    record_rate_path(),
+                    Timestamp((index as f64 * bucket * 1e6) as u64),
+                    EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([rate]))),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the text documents to log and empties the document list.
+    pub fn take_documents(&mut self) -> Vec<(EntityPath, Timestamp, String)> {
+        std::mem::take(&mut self.documents)
+    }
+
+    /// Gets the cleared entries and empties the cleared list.
+    pub fn take_cleared(&mut self) -> Vec<(EntityPath, Timestamp)> {
+        std::mem::take(&mut self.cleared)
+    }
+
     /// Gets the changed entries with their values and clears the changed set.
     pub fn get_changed(&mut self) -> Vec<(EntityPath, Timestamp, ArrayRef)> {
         self.changed
@@ -177,11 +876,79 @@ impl EntryLog {
             .collect()
     }
 
+    /// Gets every entity path that has a value.
+    pub fn entity_paths(&self) -> impl Iterator<Item = &EntityPath> {
+        self.entries.keys()
+    }
+
     #[must_use]
     pub fn get_entry(&self, key: &EntityPath) -> Option<&BTreeMap<Timestamp, ArrayRef>> {
         self.entries.get(key)
     }
 
+    /// Gets every value of `key` as a table with a `timestamp` column, in microseconds, and a
+    /// `value` column.
+    pub fn to_record_batch(&self, key: &EntityPath) -> Result<Option<RecordBatch>, anyhow::Error> {
+        let Some(entry) = self.entries.get(key) else {
+            return Ok(None);
+        };
+
+        let timestamps = UInt64Array::from_iter_values(entry.keys().map(|t| t.0));
+        // each value is a single element, so they line up with their timestamps
+        let values = entry.values().map(AsRef::as_ref).collect::<Vec<_>>();
+        let value = arrow::compute::concat(&values)?;
+
+        let schema = Schema::new(vec![
+            Field::new("timestamp", DataType::UInt64, false),
+            Field::new("value", value.data_type().clone(), true),
+        ]);
+
+        Ok(Some(RecordBatch::try_new(
+            Arc::new(schema),
+            vec![Arc::new(timestamps), value],
+        )?))
+    }
+
+    /// Rebuilds a log from the table [`write_arrow`](crate::export::write_arrow) exports, adding
+    /// the values as they were exported rather than decoding them again.
+    ///
+    /// Struct schemas, struct types and metadata aren't part of the export, so they're lost.
+    pub fn from_record_batches(
+        config: Config,
+        batches: impl IntoIterator<Item = RecordBatch>,
+    ) -> Result<Self, anyhow::Error> {
+        let mut log = Self::with_config(config);
+
+        for batch in batches {
+            let column = |name| {
+                batch
+                    .column_by_name(name)
+                    .ok_or_else(|| anyhow::anyhow!("exported table has no {name} column"))
+            };
+            let entities = arrow::compute::cast(column("entity")?, &DataType::Utf8)?;
+            let entities = entities.as_string::<i32>();
+            let timestamps = column("timestamp")?
+                .as_primitive_opt::<UInt64Type>()
+                .ok_or_else(|| anyhow::anyhow!("exported timestamps aren't u64 microseconds"))?;
+            let values = column("value")?
+                .as_union_opt()
+                .ok_or_else(|| anyhow::anyhow!("exported values aren't a union"))?;
+
+            for row in 0..batch.num_rows() {
+                let key = EntityPath::parse_strict(entities.value(row))?;
+                let timestamp = Timestamp(timestamps.value(row));
+
+                let value = values
+                    .child(values.type_id(row))
+                    .slice(values.value_offset(row), 1);
+
+                log.add_entryvalue(key, timestamp, EntryValue::Arrow(value))?;
+            }
+        }
+
+        Ok(log)
+    }
+
     pub fn get_latest_entry(&self, key: &EntityPath) -> Option<(&Timestamp, &ArrayRef)> {
         self.entries.get(key).and_then(BTreeMap::last_key_value)
     }
@@ -196,3 +963,873 @@ impl EntryLog {
             .and_then(|entry| entry.range(..=time).last())
     }
 }
+
+/// An element of an array value, which is an array itself when it's a single list, or isn't a
+/// single value, so arrays of arrays are exploded all the way down.
+fn array_element_value(element: ArrayRef) -> EntryValue {
+    let values = match element.as_list_opt::<i32>() {
+        Some(list) if list.len() == 1 => list.value(0),
+        _ if element.len() != 1 => element,
+        _ => return EntryValue::Arrow(element),
+    };
+
+    EntryValue::ArrayArrow((0..values.len()).map(|i| values.slice(i, 1)).collect())
+}
+
+/// Whether a value would be flattened into more than one entity.
+fn is_nested(value: &EntryValue) -> bool {
+    match value {
+        EntryValue::Arrow(_) | EntryValue::StructSchema(_) => false,
+        EntryValue::ArrayArrow(_) | EntryValue::Map(_) | EntryValue::ArrayMap(_) => true,
+        EntryValue::Json(json) => json.is_array() || json.is_object(),
+    }
+}
+
+/// Converts a decoded value to JSON, as it would be flattened into entities.
+fn entry_value_json(value: EntryValue) -> serde_json::Value {
+    match value {
+        EntryValue::Arrow(array) if array.len() == 1 => json_value(array.as_ref(), 0),
+        EntryValue::Arrow(array) => (0..array.len())
+            .map(|row| json_value(array.as_ref(), row))
+            .collect(),
+        EntryValue::ArrayArrow(elements) => elements
+            .into_iter()
+            .map(|element| entry_value_json(array_element_value(element)))
+            .collect(),
+        EntryValue::Map(map) => {
+            // json objects keep their insertion order, so sort the fields to keep blobs stable
+            let mut fields = map.into_iter().collect::<Vec<_>>();
+            fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+            fields
+                .into_iter()
+                .map(|(name, value)| (name, entry_value_json(value)))
+                .collect::<serde_json::Map<_, _>>()
+                .into()
+        }
+        EntryValue::ArrayMap(maps) => maps
+            .into_iter()
+            .map(|map| entry_value_json(EntryValue::Map(map)))
+            .collect(),
+        EntryValue::Json(json) => json,
+        EntryValue::StructSchema(_) => serde_json::Value::Null,
+    }
+}
+
+fn json_text(json: serde_json::Value) -> EntryValue {
+    EntryValue::Arrow(Arc::new(StringArray::from_iter_values([json.to_string()])))
+}
+
+/// Converts a decoded struct into a single-row Arrow struct, or `None` if a field isn't a single
+/// value.
+fn map_to_struct_array(map: &HashMap<String, EntryValue>) -> Option<ArrayRef> {
+    let mut fields = map
+        .iter()
+        .map(|(name, value)| {
+            let array = match value {
+                EntryValue::Arrow(array) if array.len() == 1 => array.clone(),
+                EntryValue::Map(map) => map_to_struct_array(map)?,
+                _ => return None,
+            };
+
+            Some((Field::new(name, array.data_type().clone(), true), array))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    fields.sort_by(|(a, _), (b, _)| a.name().cmp(b.name()));
+
+    let (fields, arrays): (Vec<_>, Vec<_>) = fields.into_iter().unzip();
+
+    StructArray::try_new(fields.into(), arrays, None)
+        .ok()
+        .map(|array| Arc::new(array) as ArrayRef)
+}
+
+/// Renders a struct schema's text and field list as markdown.
+fn schema_document(
+    name: &str,
+    raw: &[u8],
+    schema: &WpiLibStructSchema<UnresolvedWpiLibStructType>,
+) -> String {
+    let fields = schema
+        .fields
+        .iter()
+        .map(|(field, data)| {
+            let ty = match &data.ty {
+                UnresolvedWpiLibStructType::Primitive(p) => format!("{p:?}"),
+                UnresolvedWpiLibStructType::Custom(s) => s.clone(),
+            };
+            let count = data.count.map(|c| format!("[{c}]")).unwrap_or_default();
+
+            format!("- `{field}`: {ty}{count}")
+        })
+        .collect::<Vec<_>>();
+
+    format!(
+        "# {name}\n\n```\n{}\n```\n\n## Fields\n\n{}\n",
+        String::from_utf8_lossy(raw),
+        fields.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::Path,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use hashbrown::{HashMap, HashSet};
+    use rerun::{
+        EntityPath,
+        external::arrow::{
+            array::{Array as _, ArrayRef, AsArray as _, Float64Array, Int64Array, ListArray},
+            datatypes::{Float64Type, Int64Type},
+        },
+        time::TimeType,
+    };
+
+    use super::{EntryLog, Timestamp};
+    use crate::{
+        config::{
+            ArrayOverride, ArraySettings, Config, DuplicateTimestamps, EntityPattern, Retention,
+            StructMode, TimePrecision,
+        },
+        values::EntryValue,
+    };
+
+    #[test]
+    fn timestamp_durations() {
+        assert_eq!(Duration::from(Timestamp(1_000_000)), Duration::from_secs(1));
+        assert_eq!(
+            Duration::from(Timestamp(1_500)),
+            Duration::from_micros(1_500)
+        );
+        assert!((Timestamp(1_000_000).as_secs_f64() - 1.0).abs() < f64::EPSILON);
+        assert!((Timestamp(2_500_000).as_secs_f64() - 2.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn time_precision() {
+        let timestamp = Timestamp(1_234_567);
+
+        let nanos = TimePrecision::Nanoseconds.timeline("robotime");
+        let micros = TimePrecision::Microseconds.timeline("robotime");
+        assert_eq!(nanos.typ(), TimeType::DurationNs);
+        assert_eq!(micros.typ(), TimeType::Sequence);
+
+        assert_eq!(
+            timestamp.time_int(nanos.typ()).unwrap().as_i64(),
+            1_234_567_000
+        );
+        assert_eq!(
+            timestamp.time_int(micros.typ()).unwrap().as_i64(),
+            1_234_567
+        );
+
+        // too large for nanoseconds, but not for the timestamp's own microseconds
+        let late = Timestamp(u64::MAX / 1_000);
+        assert!(late.time_int(nanos.typ()).is_err());
+        assert!(late.time_int(micros.typ()).is_ok());
+        assert!(late.time_point(nanos).is_err());
+        assert!(late.time_point(micros).is_ok());
+
+        // too large to even multiply into nanoseconds
+        assert!(Timestamp(u64::MAX).time_int(nanos.typ()).is_err());
+    }
+
+    fn two_field_struct() -> EntryValue {
+        EntryValue::Map(HashMap::from([
+            (
+                "x".to_owned(),
+                EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([1.0]))),
+            ),
+            (
+                "y".to_owned(),
+                EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([2.0]))),
+            ),
+        ]))
+    }
+
+    fn array_log(settings: ArraySettings) -> EntryLog {
+        let mut log = EntryLog::with_config(Config {
+            arrays: vec![ArrayOverride {
+                pattern: EntityPattern("states".to_owned()),
+                settings,
+            }],
+            ..Config::default()
+        });
+        log.add_entryvalue(
+            EntityPath::from_single_string("states"),
+            Timestamp(0),
+            EntryValue::ArrayArrow(
+                (0..4)
+                    .map(|i| Arc::new(Float64Array::from_iter_values([f64::from(i)])) as _)
+                    .collect(),
+            ),
+        )
+        .unwrap();
+
+        log
+    }
+
+    fn paths(paths: &[&str]) -> HashSet<EntityPath> {
+        paths
+            .iter()
+            .map(|p| EntityPath::from_file_path(Path::new(p)))
+            .collect()
+    }
+
+    #[test]
+    fn flattened_struct() {
+        let mut log = EntryLog::new();
+        log.add_entryvalue(
+            EntityPath::from_single_string("pose"),
+            Timestamp(0),
+            two_field_struct(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            log.entity_paths().cloned().collect::<HashSet<_>>(),
+            paths(&["pose/x", "pose/y"])
+        );
+    }
+
+    #[test]
+    fn nested_struct() {
+        let mut log = EntryLog::with_config(Config {
+            struct_mode: StructMode::Nested,
+            ..Config::default()
+        });
+        let key = EntityPath::from_single_string("pose");
+        log.add_entryvalue(key.clone(), Timestamp(0), two_field_struct())
+            .unwrap();
+
+        assert_eq!(
+            log.entity_paths().cloned().collect::<HashSet<_>>(),
+            paths(&["pose"])
+        );
+
+        let (_, value) = log.get_latest_entry(&key).unwrap();
+        let value = value.as_struct();
+        assert_eq!(value.len(), 1);
+        assert_eq!(value.column_names(), ["x", "y"]);
+    }
+
+    #[test]
+    fn array_length_only() {
+        let log = array_log(ArraySettings {
+            indices: false,
+            ..ArraySettings::default()
+        });
+
+        assert_eq!(
+            log.entity_paths().cloned().collect::<HashSet<_>>(),
+            paths(&["states/$length"])
+        );
+    }
+
+    #[test]
+    fn array_capped_indices() {
+        let log = array_log(ArraySettings {
+            length: false,
+            max_indices: Some(2),
+            ..ArraySettings::default()
+        });
+
+        assert_eq!(
+            log.entity_paths().cloned().collect::<HashSet<_>>(),
+            paths(&["states/$0", "states/$1"])
+        );
+    }
+
+    #[test]
+    fn array_full_explosion() {
+        let log = array_log(ArraySettings::default());
+
+        assert_eq!(
+            log.entity_paths().cloned().collect::<HashSet<_>>(),
+            paths(&[
+                "states/$length",
+                "states/$0",
+                "states/$1",
+                "states/$2",
+                "states/$3"
+            ])
+        );
+    }
+
+    #[test]
+    fn nested_arrays() {
+        let grid = EntityPath::from_single_string("grid");
+        let paths = |value| {
+            let mut log = EntryLog::new();
+            log.add_entryvalue(grid.clone(), Timestamp(0), value)
+                .unwrap();
+            log.entity_paths().cloned().collect::<HashSet<_>>()
+        };
+        let rows = [vec![1i64, 2, 3], vec![4, 5, 6], vec![]];
+
+        let mut expected = HashSet::from([super::array_length(&grid)]);
+        for (i, row) in rows.iter().enumerate() {
+            let row_path = super::array_element(&grid, i);
+            // an empty row still says how long it is
+            expected.insert(super::array_length(&row_path));
+            expected.extend((0..row.len()).map(|j| super::array_element(&row_path, j)));
+        }
+        assert_eq!(expected.len(), 10);
+
+        assert_eq!(paths(EntryValue::Json(serde_json::json!(rows))), expected);
+        assert_eq!(
+            paths(EntryValue::ArrayArrow(
+                rows.iter()
+                    .map(|row| Arc::new(Int64Array::from(row.clone())) as ArrayRef)
+                    .collect()
+            )),
+            expected
+        );
+        assert_eq!(
+            paths(EntryValue::ArrayArrow(
+                rows.iter()
+                    .map(|row| {
+                        Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>([Some(
+                            row.iter().copied().map(Some),
+                        )])) as ArrayRef
+                    })
+                    .collect()
+            )),
+            expected
+        );
+    }
+
+    #[test]
+    fn array_children_dont_collide() {
+        let mut log = EntryLog::new();
+        let shooter = EntityPath::from_single_string("shooter");
+        let child = |name: &str| shooter.join(&EntityPath::from_single_string(name));
+
+        // real entries that happen to be named like the children of an exploded array
+        for (name, value) in [("length", 0.5f64), ("0", 1.5)] {
+            log.add_entry(child(name), Timestamp(0), "double", &value.to_le_bytes())
+                .unwrap();
+        }
+        log.add_entry(child("type"), Timestamp(0), "string", b"flywheel")
+            .unwrap();
+        let speeds = [10.0f64.to_le_bytes(), 20.0f64.to_le_bytes()].concat();
+        log.add_entry(shooter.clone(), Timestamp(0), "double[]", &speeds)
+            .unwrap();
+
+        let first = |path: &EntityPath| {
+            log.get_latest_entry(path)
+                .unwrap()
+                .1
+                .as_primitive::<rerun::external::arrow::datatypes::Float64Type>()
+                .value(0)
+        };
+        assert!((first(&child("length")) - 0.5).abs() < f64::EPSILON);
+        assert!((first(&child("0")) - 1.5).abs() < f64::EPSILON);
+        assert!((first(&super::array_element(&shooter, 0)) - 10.0).abs() < f64::EPSILON);
+        assert_eq!(
+            log.get_latest_entry(&super::array_length(&shooter))
+                .unwrap()
+                .1
+                .as_primitive::<rerun::external::arrow::datatypes::Int64Type>()
+                .value(0),
+            2
+        );
+        assert!(log.get_entry(&child("type")).is_some());
+
+        // the `$` survives being written out and parsed back
+        for path in [
+            super::array_length(&shooter),
+            super::array_element(&shooter, 1),
+        ] {
+            assert_eq!(EntityPath::parse_strict(&path.to_string()).ok(), Some(path));
+        }
+    }
+
+    #[test]
+    fn json_is_flattened() {
+        let mut log = EntryLog::new();
+        log.add_entry(
+            EntityPath::from_single_string("auto"),
+            Timestamp(0),
+            "json",
+            br#"{"name":"left","waypoints":[1.5,2],"flip":true,"note":null}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            log.entity_paths().cloned().collect::<HashSet<_>>(),
+            paths(&[
+                "auto/name",
+                "auto/flip",
+                "auto/waypoints/$length",
+                "auto/waypoints/$0",
+                "auto/waypoints/$1",
+            ])
+        );
+    }
+
+    fn log_twice(mode: DuplicateTimestamps) -> EntryLog {
+        let mut log = EntryLog::with_config(Config {
+            duplicate_timestamps: mode,
+            ..Config::default()
+        });
+        for value in [1.0f64, 2.0] {
+            log.add_entry(
+                EntityPath::from_single_string("current"),
+                Timestamp(1_000),
+                "double",
+                &value.to_le_bytes(),
+            )
+            .unwrap();
+        }
+
+        log
+    }
+
+    fn values(log: &EntryLog) -> Vec<(Timestamp, Vec<f64>)> {
+        log.get_entry(&EntityPath::from_single_string("current"))
+            .unwrap()
+            .iter()
+            .map(|(timestamp, value)| {
+                (
+                    *timestamp,
+                    value
+                        .as_primitive::<rerun::external::arrow::datatypes::Float64Type>()
+                        .values()
+                        .to_vec(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_timestamps() {
+        assert_eq!(
+            values(&log_twice(DuplicateTimestamps::LastWins)),
+            [(Timestamp(1_000), vec![2.0])]
+        );
+        assert_eq!(
+            values(&log_twice(DuplicateTimestamps::Nudge)),
+            [(Timestamp(1_000), vec![1.0]), (Timestamp(1_001), vec![2.0])]
+        );
+
+        let mut log = EntryLog::with_config(Config {
+            duplicate_timestamps: DuplicateTimestamps::Nudge,
+            ..Config::default()
+        });
+        for value in [1.0f64, 2.0, 3.0] {
+            log.add_entry(
+                EntityPath::from_single_string("current"),
+                Timestamp(u64::MAX - 1),
+                "double",
+                &value.to_le_bytes(),
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            values(&log),
+            [
+                (Timestamp(u64::MAX - 1), vec![1.0]),
+                (Timestamp(u64::MAX), vec![3.0])
+            ]
+        );
+
+        // concatenating into one sample was dropped, since readers only take single values
+        assert!(serde_json::from_str::<Config>(r#"{"duplicate_timestamps":"concat"}"#).is_err());
+    }
+
+    #[test]
+    fn observer_sees_each_leaf() {
+        let mut log = EntryLog::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        log.set_observer({
+            let seen = seen.clone();
+            move |key, timestamp, _| seen.lock().unwrap().push((key.clone(), timestamp))
+        });
+
+        let leaf = || EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([1.0])));
+        log.add_entryvalue(
+            EntityPath::from_single_string("pose"),
+            Timestamp(1_000),
+            EntryValue::Map(HashMap::from([
+                ("x".to_owned(), leaf()),
+                (
+                    "rotation".to_owned(),
+                    EntryValue::Map(HashMap::from([("value".to_owned(), leaf())])),
+                ),
+            ])),
+        )
+        .unwrap();
+
+        let seen = seen.lock().unwrap().iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(
+            seen,
+            log.get_changed()
+                .into_iter()
+                .map(|(key, timestamp, _)| (key, timestamp))
+                .collect()
+        );
+        assert!(seen.contains(&(
+            EntityPath::from_file_path(Path::new("pose/rotation/value")),
+            Timestamp(1_000)
+        )));
+    }
+
+    #[test]
+    fn int32_booleans() {
+        let read = |int32_booleans: &[&str], ty, data: &[u8]| {
+            let mut log = EntryLog::with_config(Config {
+                int32_booleans: int32_booleans
+                    .iter()
+                    .map(|pattern| EntityPattern((*pattern).to_owned()))
+                    .collect(),
+                ..Config::default()
+            });
+            let key = EntityPath::from_single_string("enabled");
+            log.add_entry(key.clone(), Timestamp(0), ty, data).unwrap();
+
+            let value = |path: &EntityPath| {
+                log.get_entry(path)
+                    .map(|entry| entry[&Timestamp(0)].as_boolean().value(0))
+            };
+            match value(&key) {
+                Some(value) => vec![value],
+                // arrays are exploded into their elements
+                None => (0..)
+                    .map_while(|i| value(&super::array_element(&key, i)))
+                    .collect(),
+            }
+        };
+
+        // 0x100 is a true int32, but its first byte alone is false
+        let int32 = 0x100i32.to_le_bytes();
+        assert_eq!(read(&[], "boolean", &int32), vec![false]);
+        assert_eq!(read(&["enabled"], "boolean", &int32), vec![true]);
+        assert_eq!(read(&["enabled"], "boolean", &[1]), vec![true]);
+
+        let array = [1i32, 0, 1].map(i32::to_le_bytes).concat();
+        assert_eq!(
+            read(&["enabled"], "boolean[]", &array),
+            vec![true, false, true]
+        );
+        // a byte per boolean, as four swerve modules might be, unless the entry is configured
+        assert_eq!(
+            read(&[], "boolean[]", &[1, 0, 0, 0]),
+            vec![true, false, false, false]
+        );
+        assert_eq!(
+            read(&["other"], "boolean[]", &[1, 0, 0, 0]),
+            vec![true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn leaf_must_be_one_value() {
+        let mut log = EntryLog::new();
+        let key = EntityPath::from_single_string("speed");
+
+        for (timestamp, values) in [(1_000, vec![]), (2_000, vec![1.0, 2.0]), (3_000, vec![3.0])] {
+            log.add_entryvalue(
+                key.clone(),
+                Timestamp(timestamp),
+                EntryValue::Arrow(Arc::new(Float64Array::from(values))),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            log.get_entry(&key).unwrap().keys().collect::<Vec<_>>(),
+            [&Timestamp(3_000)]
+        );
+        assert_eq!(log.get_changed().len(), 1);
+        assert_eq!(
+            log.value_warnings(),
+            [
+                "skipping /speed at 0.001s: expected a single value, got 0",
+                "skipping /speed at 0.002s: expected a single value, got 2",
+            ]
+        );
+
+        // colliding values can't merge into one sample of more than one value either
+        for mode in [DuplicateTimestamps::LastWins, DuplicateTimestamps::Nudge] {
+            let log = log_twice(mode);
+            assert!(
+                log.get_entry(&EntityPath::from_single_string("current"))
+                    .unwrap()
+                    .values()
+                    .all(|value| value.len() == 1)
+            );
+        }
+    }
+
+    #[test]
+    fn retention_evicts_oldest() {
+        let log_seconds = |retention| {
+            let mut log = EntryLog::new();
+            log.set_retention(retention);
+            for second in 0..5u32 {
+                log.add_entry(
+                    EntityPath::from_single_string("current"),
+                    Timestamp(u64::from(second) * 1_000_000),
+                    "double",
+                    &f64::from(second).to_le_bytes(),
+                )
+                .unwrap();
+            }
+            // only what's kept is left to log
+            assert_eq!(
+                log.changed.len(),
+                log.get_entry(&EntityPath::from_single_string("current"))
+                    .unwrap()
+                    .len()
+            );
+            values(&log)
+        };
+
+        assert_eq!(
+            log_seconds(Retention {
+                max_samples: Some(2),
+                max_age: None,
+            }),
+            [
+                (Timestamp(3_000_000), vec![3.0]),
+                (Timestamp(4_000_000), vec![4.0])
+            ]
+        );
+        assert_eq!(
+            log_seconds(Retention {
+                max_samples: None,
+                max_age: Some(1.0),
+            }),
+            [
+                (Timestamp(3_000_000), vec![3.0]),
+                (Timestamp(4_000_000), vec![4.0])
+            ]
+        );
+        assert_eq!(log_seconds(Retention::default()).len(), 5);
+
+        // a loaded file isn't cut short by the live client's limits
+        let mut log = EntryLog::with_config(Config {
+            retention: Retention {
+                max_samples: Some(2),
+                max_age: None,
+            },
+            ..Config::default()
+        });
+        for second in 0..5u32 {
+            log.add_entry(
+                EntityPath::from_single_string("current"),
+                Timestamp(u64::from(second) * 1_000_000),
+                "double",
+                &f64::from(second).to_le_bytes(),
+            )
+            .unwrap();
+        }
+        assert_eq!(values(&log).len(), 5);
+    }
+
+    #[test]
+    fn record_rate_gaps() {
+        let mut log = EntryLog::with_config(Config {
+            record_rate: Some(1.0),
+            ..Config::default()
+        });
+        for timestamp in [0, 500_000, 3_000_000, u64::MAX] {
+            log.count_record(Timestamp(timestamp));
+        }
+        log.add_record_rate().unwrap();
+
+        let rate = log
+            .get_entry(&super::record_rate_path())
+            .unwrap()
+            .iter()
+            .map(|(timestamp, value)| (timestamp.0, value.as_primitive::<Float64Type>().value(0)))
+            .collect::<Vec<_>>();
+        // each gap is a zero at either end, however many buckets it spans
+        assert_eq!(rate.len(), 7);
+        assert_eq!(
+            rate[..5],
+            [
+                (0, 2.0),
+                (1_000_000, 0.0),
+                (2_000_000, 0.0),
+                (3_000_000, 1.0),
+                (4_000_000, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn unresolved_structs() {
+        let mut log = EntryLog::new();
+        let translation = [1.0f64.to_le_bytes(), 2.0f64.to_le_bytes()].concat();
+
+        for (name, timestamp) in [("a", 1_000), ("a", 2_000), ("b", 2_000)] {
+            log.add_entry(
+                EntityPath::from_single_string(name),
+                Timestamp(timestamp),
+                "struct:Translation2d",
+                &translation,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            log.unresolved_structs(),
+            [super::UnresolvedStruct {
+                name: "struct:Translation2d".to_owned(),
+                samples: 3,
+                entities: ["a", "b"]
+                    .into_iter()
+                    .map(EntityPath::from_single_string)
+                    .collect(),
+            }]
+        );
+
+        log.add_entry(
+            EntityPath::from_file_path(Path::new(".schema/struct:Translation2d")),
+            Timestamp(3_000),
+            "structschema",
+            b"double x;double y",
+        )
+        .unwrap();
+        assert_eq!(log.unresolved_structs(), []);
+    }
+
+    #[test]
+    fn bare_nested_struct_names() {
+        let mut log = EntryLog::new();
+        log.add_entry(
+            EntityPath::from_file_path(Path::new(".schema/struct:Outer")),
+            Timestamp(0),
+            "structschema",
+            b"Inner inner",
+        )
+        .unwrap();
+        log.add_entry(
+            EntityPath::from_single_string("value"),
+            Timestamp(1_000),
+            "struct:Outer",
+            &1.0f64.to_le_bytes(),
+        )
+        .unwrap();
+
+        // queued under the name the schema is registered with, not the bare field type
+        assert_eq!(
+            log.unresolved_structs()
+                .into_iter()
+                .map(|unresolved| unresolved.name)
+                .collect::<Vec<_>>(),
+            ["struct:Inner"]
+        );
+
+        log.add_entry(
+            EntityPath::from_file_path(Path::new(".schema/struct:Inner")),
+            Timestamp(2_000),
+            "structschema",
+            b"double x",
+        )
+        .unwrap();
+        assert_eq!(log.unresolved_structs(), []);
+
+        let x = log
+            .get_entry(&EntityPath::from_file_path(Path::new("value/inner/x")))
+            .expect("the queued value wasn't decoded");
+        assert_eq!(
+            x[&Timestamp(1_000)].as_primitive::<Float64Type>().values()[..],
+            [1.0]
+        );
+    }
+
+    #[test]
+    fn max_depth() {
+        let mut log = EntryLog::with_config(Config {
+            max_depth: Some(3),
+            ..Config::default()
+        });
+
+        // a/b/c/d/e = 1.0, two parts deeper than the limit
+        let value = ["e", "d", "c", "b"].into_iter().fold(
+            EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([1.0]))),
+            |value, name| EntryValue::Map(HashMap::from([(name.to_owned(), value)])),
+        );
+        log.add_entryvalue(EntityPath::from_single_string("a"), Timestamp(1_000), value)
+            .unwrap();
+
+        let blob = EntityPath::from_file_path(Path::new("a/b/c"));
+        assert_eq!(log.entity_paths().collect::<Vec<_>>(), [&blob]);
+        assert_eq!(
+            log.get_entry(&blob).unwrap()[&Timestamp(1_000)]
+                .as_string::<i32>()
+                .value(0),
+            r#"{"d":{"e":1.0}}"#
+        );
+    }
+
+    #[test]
+    fn resolution_status() {
+        let mut log = EntryLog::new();
+        let schema = |log: &mut EntryLog, name: &str, schema: &[u8]| {
+            log.add_entry(
+                EntityPath::from_file_path(Path::new(".schema"))
+                    .join(&EntityPath::from_single_string(name)),
+                Timestamp(0),
+                "structschema",
+                schema,
+            )
+            .unwrap();
+        };
+
+        schema(
+            &mut log,
+            "struct:Pose2d",
+            b"Translation2d translation;Rotation2d rotation",
+        );
+        schema(&mut log, "struct:Rotation2d", b"double value");
+        assert!(!log.can_resolve("struct:Pose2d"));
+        assert!(log.can_resolve("Rotation2d"));
+        assert!(!log.can_resolve("struct:Missing"));
+        assert_eq!(
+            log.resolve_all()
+                .into_iter()
+                .map(|(name, resolved)| (name, resolved.err()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "struct:Pose2d".to_owned(),
+                    Some("struct:Translation2d".to_owned())
+                ),
+                ("struct:Rotation2d".to_owned(), None),
+            ]
+        );
+
+        schema(&mut log, "struct:Translation2d", b"double x;double y");
+        assert!(log.can_resolve("struct:Pose2d"));
+        assert!(
+            log.resolve_all()
+                .iter()
+                .all(|(_, resolved)| resolved.is_ok())
+        );
+    }
+
+    #[test]
+    fn enum_out_of_range_warns() {
+        let mut log = EntryLog::new();
+        log.add_entry(
+            EntityPath::from_file_path(Path::new(".schema/struct:State")),
+            Timestamp(0),
+            "structschema",
+            b"enum { ok = 1, big = 300 } uint8 state",
+        )
+        .unwrap();
+
+        assert_eq!(
+            log.schema_warnings(),
+            ["struct struct:State: enum value big = 300 doesn't fit the type of field state"]
+        );
+    }
+}
@@ -9,37 +9,33 @@
 #![warn(clippy::nursery, clippy::pedantic)]
 #![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
 
-pub mod wpilog;
-
-use std::path::Path;
-
-use conv::log_changes_to_chunks;
-use hashbrown::HashMap;
-
-use log::{EntryLog, Timestamp};
+use firstrun::{
+    analysis, blueprint,
+    config::Config,
+    conv::{log_changes_to_chunks, renumbered, view_coordinates_chunk},
+    log::EntryLog,
+    nt,
+    wpilog::{self, parse::WpiLogFile},
+};
 use rerun::external::anyhow::Context;
-use rerun::external::nohash_hasher::IntMap;
-use rerun::external::re_log_types::{EntityPathHash, SetStoreInfo, StoreInfo, StoreSource};
+use rerun::external::re_log_types::{SetStoreInfo, StoreInfo, StoreSource};
 use rerun::log::LogMsg;
-use rerun::{ApplicationId, EntityPathPart, RecordingProperties};
+use rerun::{ApplicationId, RecordingProperties};
 use rerun::{
-    DataLoader as _, EntityPath, LoadedData, TimePoint, Timeline,
-    external::{
-        anyhow::{self, anyhow},
-        re_build_info, re_data_loader, re_log,
-    },
+    DataLoader as _, EntityPath, LoadedData, TimePoint,
+    external::{anyhow, re_build_info, re_data_loader, re_log},
     log::{Chunk, RowId},
 };
 use tokio::runtime::Runtime;
-use values::{EntryValue, EntryValueParseError};
-use wpilog::parse::{Payload, WpiLogFile, WpiRecord};
-
-pub mod conv;
-pub mod log;
-pub mod nt;
-pub mod values;
 
 fn main() -> anyhow::Result<std::process::ExitCode> {
+    re_log::setup_logging();
+
+    let args = std::env::args().collect::<Vec<_>>();
+    if let Some(result) = firstrun::cli::run(&args) {
+        return result.map(|()| std::process::ExitCode::SUCCESS);
+    }
+
     std::thread::Builder::new()
         .name("networktables".into())
         .spawn(|| {
@@ -51,18 +47,12 @@ fn main() -> anyhow::Result<std::process::ExitCode> {
         })?;
 
     let main_thread_token = rerun::MainThreadToken::i_promise_i_am_on_the_main_thread();
-    re_log::setup_logging();
 
     re_data_loader::register_custom_data_loader(WpiLogLoader);
 
     let build_info = re_build_info::build_info!();
-    rerun::run(
-        main_thread_token,
-        build_info,
-        rerun::CallSource::Cli,
-        std::env::args(),
-    )
-    .map(std::process::ExitCode::from)
+    rerun::run(main_thread_token, build_info, rerun::CallSource::Cli, args)
+        .map(std::process::ExitCode::from)
 }
 
 /// A custom [`re_data_loader::DataLoader`] that logs the hash of file as a [`rerun::TextDocument`].
@@ -79,11 +69,30 @@ impl re_data_loader::DataLoader for WpiLogLoader {
         path: std::path::PathBuf,
         tx: std::sync::mpsc::Sender<re_data_loader::LoadedData>,
     ) -> Result<(), re_data_loader::DataLoaderError> {
-        let contents = std::fs::read(&path)?;
         if path.is_dir() {
-            return Err(re_data_loader::DataLoaderError::Incompatible(path)); // simply not interested
+            // a directory of rotated parts loads as a single recording
+            let parts = match &wpilog::split::find_parts(&path)?[..] {
+                [] => return Err(re_data_loader::DataLoaderError::Incompatible(path)),
+                [parts] => parts.clone(),
+                logs => {
+                    return Err(anyhow::anyhow!(
+                        "{} holds the parts of {} different logs, open one of them instead",
+                        path.display(),
+                        logs.len()
+                    )
+                    .into());
+                }
+            };
+
+            let contents = parts
+                .iter()
+                .map(std::fs::read)
+                .collect::<Result<Vec<_>, _>>()?;
+            return parse_and_log(settings, &tx, &path, contents);
         }
-        parse_and_log(settings, &tx, &path, &contents)
+
+        let contents = std::fs::read(&path)?;
+        parse_and_log(settings, &tx, &path, vec![contents])
     }
 
     fn load_from_file_contents(
@@ -93,107 +102,33 @@ impl re_data_loader::DataLoader for WpiLogLoader {
         contents: std::borrow::Cow<'_, [u8]>,
         tx: std::sync::mpsc::Sender<re_data_loader::LoadedData>,
     ) -> Result<(), re_data_loader::DataLoaderError> {
-        parse_and_log(settings, &tx, &filepath, &contents)
-    }
-}
-
-struct EntryContext<'log> {
-    ty: &'log str,
-    name: &'log str,
-}
-
-fn handle_data(
-    ty: &str,
-    timestamp: Timestamp,
-    key: EntityPath,
-    data: &[u8],
-    logger: &mut EntryLog,
-) {
-    let kstr = key.to_string();
-    let error = |e: anyhow::Error| {
-        re_log::warn!(
-            "handle_data: Failed to parse entry type {} (data length: {}) (key: {}): {e}",
-            ty,
-            data.len(),
-            kstr,
-        );
-    };
-
-    match logger.add_entry(key, timestamp, ty, data) {
-        Ok(_) => {}
-        Err(e) => error(e),
-    }
-}
-
-fn fill_log<'file>(
-    ctxs: &mut HashMap<u32, EntryContext<'file>>,
-    nt_ctx: &mut EntryLog,
-    record: WpiRecord<'file>,
-) {
-    match record.payload {
-        Payload::Start {
-            entry_id,
-            entry_name,
-            entry_type,
-            entry_metadata,
-        } => {
-            // strip the NT: prefix from the entry name
-            let mut entry_name = entry_name.strip_prefix("NT:").unwrap_or(entry_name);
-            // also strip any leading slashes
-            while let Some(new) = entry_name.strip_prefix('/') {
-                entry_name = new;
-            }
-            ctxs.insert(
-                entry_id,
-                EntryContext {
-                    // NOTE: we _could_ have metadata if we start using it
-                    // metadata: entry_metadata,
-                    ty: entry_type,
-                    name: entry_name,
-                },
-            );
-        }
-        Payload::Raw { entry_id, data } => {
-            let Some(ctx) = ctxs.get(&entry_id) else {
-                re_log::warn!("No context for entry id {entry_id}");
-                return;
-            };
-
-            let key = EntityPath::from_file_path(Path::new(ctx.name));
-
-            handle_data(ctx.ty, record.timestamp, key, data, nt_ctx);
-        }
-        _ => (),
+        parse_and_log(settings, &tx, &filepath, vec![contents.into_owned()])
     }
 }
 
-fn parse_and_log(
+/// Sends the recording's store info and properties, named after the match it was recorded in.
+fn send_store_info(
     settings: &rerun::external::re_data_loader::DataLoaderSettings,
     tx: &std::sync::mpsc::Sender<re_data_loader::LoadedData>,
-    filepath: &std::path::Path,
-    contents: &[u8],
-) -> Result<(), re_data_loader::DataLoaderError> {
-    if !WpiLogFile::is_wpilog(contents) {
-        return Err(re_data_loader::DataLoaderError::Incompatible(
-            filepath.to_owned(),
-        ));
-    }
-
+    application_id: &ApplicationId,
+    log: &mut EntryLog,
+) {
     let store_id = settings
         .opened_store_id
         .clone()
         .unwrap_or_else(|| settings.store_id.clone());
 
+    let row_id = if log.config().deterministic {
+        RowId::from_u128(log.claim_row_ids(1))
+    } else {
+        RowId::new()
+    };
     let _ = tx.send(LoadedData::LogMsg(
         WpiLogLoader::name(&WpiLogLoader),
         LogMsg::SetStoreInfo(SetStoreInfo {
-            row_id: *RowId::new(),
+            row_id: *row_id,
             info: StoreInfo {
-                // TODO: specify an application_id
-                application_id: settings
-                    .application_id
-                    .clone()
-                    .unwrap_or_else(ApplicationId::random),
+                application_id: application_id.clone(),
                 store_id: store_id.clone(),
                 cloned_from: None,
                 store_source: StoreSource::Other("WpiLog".into()),
@@ -202,24 +137,42 @@ fn parse_and_log(
         }),
     ));
 
-    let properties = RecordingProperties::new().with_name("WpiLog");
+    let properties = RecordingProperties::new().with_name(
+        analysis::fms::match_info(log).map_or_else(|| "WpiLog".to_owned(), |info| info.label()),
+    );
 
-    let recording_props = Chunk::builder(EntityPath::recording_properties())
+    match Chunk::builder(EntityPath::recording_properties())
         .with_archetype(RowId::new(), TimePoint::default(), &properties)
-        .build()?;
-
-    tx.send(LoadedData::Chunk(
-        WpiLogLoader::name(&WpiLogLoader),
-        store_id,
-        recording_props,
-    ))
-    .unwrap();
+        .build()
+    {
+        Ok(chunk) => {
+            for chunk in renumbered(vec![chunk], log) {
+                let _ = tx.send(LoadedData::Chunk(
+                    WpiLogLoader::name(&WpiLogLoader),
+                    store_id.clone(),
+                    chunk,
+                ));
+            }
+        }
+        Err(e) => re_log::error!("error building recording properties: {e}"),
+    }
+}
 
-    let timeline = Timeline::new_duration("robotime");
+fn parse_and_log(
+    settings: &rerun::external::re_data_loader::DataLoaderSettings,
+    tx: &std::sync::mpsc::Sender<re_data_loader::LoadedData>,
+    filepath: &std::path::Path,
+    contents: Vec<Vec<u8>>,
+) -> Result<(), re_data_loader::DataLoaderError> {
+    if !contents.iter().all(|part| WpiLogFile::is_wpilog(part)) {
+        return Err(re_data_loader::DataLoaderError::Incompatible(
+            filepath.to_owned(),
+        ));
+    }
 
-    let contents = contents.to_vec();
     let tx = tx.clone();
     let settings = settings.clone();
+    let filepath = filepath.to_owned();
     std::thread::Builder::new()
         .name("WpiLogFile::parse".into())
         .spawn(move || {
@@ -228,32 +181,92 @@ fn parse_and_log(
             let contents = contents;
 
             {
-                let mut ctxs = HashMap::new();
-                let mut nt_ctx = EntryLog::new();
-
-                let (_, _log) = WpiLogFile::parse(contents.as_slice(), |record| {
-                    fill_log(&mut ctxs, &mut nt_ctx, record);
-                })
-                .map_err(|e| {
-                    re_log::error!("WPI DataLog file error: {e}");
-                    re_data_loader::DataLoaderError::Other(e.into())
-                })
-                .unwrap();
-
-                for chunk in log_changes_to_chunks(
+                let config = Config::from_env().unwrap_or_else(|e| {
+                    re_log::warn!("failed to load config, using defaults: {e:#}");
+                    Config::default()
+                });
+                let timeline = config.time_precision.timeline("robotime");
+                let mut nt_ctx = EntryLog::with_config(config);
+
+                wpilog::load::load_parts(&contents, &mut nt_ctx);
+
+                // identifying the recording needs the match info, so it's only sent once loaded;
+                // the blueprint is also only applied to recordings of the same application
+                let application_id = settings
+                    .application_id
+                    .clone()
+                    .unwrap_or_else(|| analysis::fms::application_id(&nt_ctx, &filepath));
+                send_store_info(&settings, &tx, &application_id, &mut nt_ctx);
+
+                let mut chunks = log_changes_to_chunks(
                     &settings.store_id,
-                    &settings
-                        .application_id
-                        .unwrap_or_else(ApplicationId::random),
+                    &application_id,
                     timeline,
                     &mut nt_ctx,
+                );
+                re_log::info!("{}", nt_ctx.coverage());
+                chunks.extend(view_coordinates_chunk(nt_ctx.config().coordinates));
+
+                match analysis::phase::match_phase_chunks(
+                    &nt_ctx,
+                    &nt_ctx.config().match_phase,
+                    timeline,
                 ) {
-                    tx.send(LoadedData::Chunk(
-                        WpiLogLoader::name(&WpiLogLoader),
-                        settings.store_id.clone(),
-                        chunk,
-                    ))
-                    .unwrap();
+                    Ok(phase_chunks) => chunks.extend(phase_chunks),
+                    Err(e) => re_log::warn!("failed to derive match phases: {e}"),
+                }
+
+                match analysis::commands::command_chunks(
+                    &nt_ctx,
+                    &nt_ctx.config().commands,
+                    timeline,
+                ) {
+                    Ok(command_chunks) => chunks.extend(command_chunks),
+                    Err(e) => re_log::warn!("failed to derive running commands: {e}"),
+                }
+
+                match analysis::trajectory::trajectory_chunks(&nt_ctx, timeline) {
+                    Ok(trajectory_chunks) => chunks.extend(trajectory_chunks),
+                    Err(e) => re_log::warn!("failed to draw trajectories: {e}"),
+                }
+
+                match analysis::apriltags::apriltag_chunks(&nt_ctx) {
+                    Ok(apriltag_chunks) => chunks.extend(apriltag_chunks),
+                    Err(e) => re_log::warn!("failed to draw AprilTags: {e}"),
+                }
+
+                // numbered only once everything is assembled, so no two chunks share an id
+                let chunks = renumbered(chunks, &mut nt_ctx);
+
+                match blueprint::default_blueprint(&application_id, &chunks, &nt_ctx) {
+                    Ok(messages) => {
+                        for message in messages {
+                            // the viewer stopped listening, so there's no one to send to
+                            if tx
+                                .send(LoadedData::LogMsg(
+                                    WpiLogLoader::name(&WpiLogLoader),
+                                    message,
+                                ))
+                                .is_err()
+                            {
+                                break;
+                            }
+                        }
+                    }
+                    Err(e) => re_log::warn!("failed to build the default blueprint: {e}"),
+                }
+
+                for chunk in chunks {
+                    if tx
+                        .send(LoadedData::Chunk(
+                            WpiLogLoader::name(&WpiLogLoader),
+                            settings.store_id.clone(),
+                            chunk,
+                        ))
+                        .is_err()
+                    {
+                        break;
+                    }
                 }
             }
 
@@ -0,0 +1,447 @@
+//! A live NetworkTables 4 client, logging every topic the robot publishes as it arrives.
+//!
+//! Values are logged at the server's timestamps, the same robot time a `.wpilog` records, and
+//! sent to the viewer every [`NtConfig::flush_interval`] seconds.
+
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt as _, StreamExt as _};
+use hashbrown::HashMap;
+use rerun::{
+    ApplicationId, StoreId, StoreKind,
+    external::{anyhow, re_log},
+};
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite::{Message, client::IntoClientRequest as _, http::HeaderValue};
+
+use super::{
+    frame::{self, Topic},
+    msgpack::{self, Value},
+};
+use crate::{
+    config::Config,
+    conv::{log_changes_to_chunks, renumbered, view_coordinates_chunk},
+    log::{EntryLog, Timestamp},
+};
+
+/// The subprotocols NT4 servers accept, newest first.
+pub const SUBPROTOCOLS: &str = "v4.1.networktables.first.wpi.edu, networktables.first.wpi.edu";
+
+/// How often the offset to the server's clock is measured again.
+const PING_INTERVAL: Duration = Duration::from_secs(3);
+/// How long to wait before connecting again after the connection fails or drops.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Where the live client connects to.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct NtConfig {
+    /// The robot or simulator's address, like `10.TE.AM.2` or `localhost`. Nothing is logged
+    /// live if unset.
+    pub address: Option<String>,
+    pub port: u16,
+    /// The name this client identifies itself to the server with.
+    pub client_name: String,
+    /// How often, in seconds, newly received values are sent to the viewer.
+    pub flush_interval: f64,
+}
+
+impl Default for NtConfig {
+    fn default() -> Self {
+        Self {
+            address: None,
+            port: 5810,
+            client_name: "firstrun".to_owned(),
+            flush_interval: 0.05,
+        }
+    }
+}
+
+impl NtConfig {
+    /// The server's websocket URL, if an address is configured.
+    #[must_use]
+    pub fn url(&self) -> Option<String> {
+        let address = self.address.as_ref()?;
+        Some(format!(
+            "ws://{address}:{}/nt/{}",
+            self.port, self.client_name
+        ))
+    }
+}
+
+/// Estimates the offset from this client's clock to the server's from RTT pings.
+#[derive(Debug)]
+pub struct ServerClock {
+    start: Instant,
+    /// Microseconds added to this client's time to get the server's, once measured.
+    offset: Option<i64>,
+}
+
+impl Default for ServerClock {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            offset: None,
+        }
+    }
+}
+
+impl ServerClock {
+    /// Microseconds since the clock was created.
+    #[must_use]
+    pub fn now(&self) -> i64 {
+        i64::try_from(self.start.elapsed().as_micros()).unwrap_or(i64::MAX)
+    }
+
+    /// A binary frame pinging the server with this client's time `now`, which the server echoes
+    /// back at its own time.
+    #[must_use]
+    pub fn ping(now: i64) -> Vec<u8> {
+        let mut frame = Vec::new();
+        msgpack::write(
+            &Value::Array(vec![
+                Value::Int(frame::RTT_TOPIC_ID),
+                Value::UInt(0),
+                // the `int` type id
+                Value::UInt(2),
+                Value::Int(now),
+            ]),
+            &mut frame,
+        );
+        frame
+    }
+
+    /// Measures the offset from the server's reply, sent at `server`, to a ping sent at `sent` and
+    /// received at `now`, assuming the reply took half of the round trip.
+    pub fn pong(&mut self, server: Timestamp, sent: i64, now: i64) {
+        let round_trip = now.saturating_sub(sent).max(0);
+        let server = i64::try_from(server.0).unwrap_or(i64::MAX);
+        self.offset = Some(server.saturating_add(round_trip / 2).saturating_sub(now));
+    }
+
+    /// Moves a time on this client's clock onto the server's, once the offset is measured.
+    #[must_use]
+    pub fn to_server(&self, local: i64) -> Option<Timestamp> {
+        let server = local.saturating_add(self.offset?);
+        Some(Timestamp(u64::try_from(server).unwrap_or(0)))
+    }
+}
+
+/// A JSON control message from the server.
+#[derive(Deserialize)]
+struct ControlMessage {
+    method: String,
+    params: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct Unannounce {
+    id: i64,
+}
+
+/// What a connection has learned so far: the topics the server announced, and its clock.
+#[derive(Debug, Default)]
+pub struct Session {
+    topics: HashMap<i64, Topic>,
+    pub clock: ServerClock,
+}
+
+impl Session {
+    /// The text frame subscribing to every topic and its values.
+    #[must_use]
+    pub fn subscribe_all() -> String {
+        serde_json::json!([{
+            "method": "subscribe",
+            "params": {
+                "topics": [""],
+                "subuid": 1,
+                "options": {"prefix": true},
+            },
+        }])
+        .to_string()
+    }
+
+    #[must_use]
+    pub fn topics(&self) -> &HashMap<i64, Topic> {
+        &self.topics
+    }
+
+    /// Handles a text frame of JSON control messages, tracking the topics announced and
+    /// unannounced. Property changes don't affect how values are decoded, so they're ignored.
+    pub fn handle_text(&mut self, text: &str) -> Result<(), anyhow::Error> {
+        let messages: Vec<ControlMessage> = serde_json::from_str(text)?;
+
+        for message in messages {
+            match message.method.as_str() {
+                "announce" => {
+                    let topic: Topic = serde_json::from_value(message.params)?;
+                    re_log::debug!("{} announced as {}", topic.name, topic.ty);
+                    self.topics.insert(topic.id, topic);
+                }
+                "unannounce" => {
+                    let Unannounce { id } = serde_json::from_value(message.params)?;
+                    self.topics.remove(&id);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the values of a binary frame, received at `now` on this client's clock, to `log`, and
+    /// measures the offset to the server's clock from any RTT replies in it.
+    pub fn handle_binary(
+        &mut self,
+        log: &mut EntryLog,
+        data: &[u8],
+        now: i64,
+    ) -> Result<(), anyhow::Error> {
+        let messages = frame::parse_messages(data)?;
+
+        for message in &messages {
+            if message.topic_id == frame::RTT_TOPIC_ID {
+                match message.value.as_i64() {
+                    Some(sent) => self.clock.pong(message.timestamp, sent, now),
+                    None => re_log::warn_once!("ignoring RTT reply that isn't an integer"),
+                }
+            }
+        }
+
+        frame::add_messages(log, &messages, &self.topics, self.clock.to_server(now));
+        Ok(())
+    }
+}
+
+/// Logs every topic of the configured server live, connecting again whenever the connection
+/// fails or drops.
+pub async fn begin_logging() {
+    let config = Config::from_env().unwrap_or_else(|e| {
+        re_log::warn!("failed to load config, using defaults: {e:#}");
+        Config::default()
+    });
+    let Some(url) = config.nt.url() else {
+        re_log::info!("no NetworkTables address configured, so nothing is logged live");
+        return;
+    };
+
+    let application_id = ApplicationId::from(config.nt.client_name.as_str());
+    let recording = match rerun::RecordingStreamBuilder::new(application_id.clone()).connect_grpc()
+    {
+        Ok(recording) => recording,
+        Err(e) => {
+            re_log::error!("failed to connect to the viewer: {e}");
+            return;
+        }
+    };
+    let store_id = recording.store_info().map_or_else(
+        || StoreId::random(StoreKind::Recording),
+        |info| info.store_id,
+    );
+    let timeline = config.time_precision.timeline("robotime");
+    let flush_interval = Duration::from_secs_f64(config.nt.flush_interval.max(0.001));
+
+    let view_coordinates = view_coordinates_chunk(config.coordinates);
+    let retention = config.retention;
+
+    let mut log = EntryLog::with_config(config);
+    log.set_retention(retention);
+    let mut connected = || {
+        if let Some(chunk) = &view_coordinates {
+            recording.send_chunk(chunk.clone());
+        }
+    };
+    let mut flush = |log: &mut EntryLog| {
+        let chunks = log_changes_to_chunks(&store_id, &application_id, timeline, log);
+        for chunk in renumbered(chunks, log) {
+            recording.send_chunk(chunk);
+        }
+    };
+
+    loop {
+        re_log::info!("connecting to NetworkTables at {url}");
+        if let Err(e) =
+            log_connection(&url, flush_interval, &mut log, &mut connected, &mut flush).await
+        {
+            re_log::warn!("NetworkTables connection to {url} failed: {e:#}");
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Logs the values of one connection until it closes, calling `connected` once it's established.
+async fn log_connection(
+    url: &str,
+    flush_interval: Duration,
+    log: &mut EntryLog,
+    connected: &mut impl FnMut(),
+    flush: &mut impl FnMut(&mut EntryLog),
+) -> Result<(), anyhow::Error> {
+    let mut request = url.into_client_request()?;
+    request.headers_mut().insert(
+        "Sec-WebSocket-Protocol",
+        HeaderValue::from_static(SUBPROTOCOLS),
+    );
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+    re_log::info!("connected to NetworkTables at {url}");
+    connected();
+
+    // topic ids only mean something within a connection
+    let mut session = Session::default();
+    socket.send(Message::Text(Session::subscribe_all())).await?;
+
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    let mut flush_timer = tokio::time::interval(flush_interval);
+
+    loop {
+        tokio::select! {
+            message = socket.next() => {
+                let Some(message) = message else {
+                    break;
+                };
+                match message? {
+                    Message::Text(text) => {
+                        if let Err(e) = session.handle_text(&text) {
+                            re_log::warn_once!("ignoring invalid control message: {e}");
+                        }
+                    }
+                    Message::Binary(data) => {
+                        let now = session.clock.now();
+                        if let Err(e) = session.handle_binary(log, &data, now) {
+                            re_log::warn_once!("ignoring invalid binary frame: {e}");
+                        }
+                    }
+                    Message::Close(_) => break,
+                    _ => {}
+                }
+            }
+            _ = ping.tick() => {
+                let now = session.clock.now();
+                socket.send(Message::Binary(ServerClock::ping(now))).await?;
+            }
+            _ = flush_timer.tick() => flush(log),
+        }
+    }
+
+    flush(log);
+    re_log::info!("NetworkTables at {url} closed the connection");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rerun::{
+        EntityPath,
+        external::arrow::{array::AsArray as _, datatypes::Float64Type},
+    };
+
+    use super::{ServerClock, Session};
+    use crate::{
+        log::{EntryLog, Timestamp},
+        nt::{
+            frame,
+            msgpack::{self, Value},
+        },
+    };
+
+    fn message(topic_id: i64, timestamp: u64, type_id: u64, value: Value<'_>) -> Vec<u8> {
+        let mut frame = Vec::new();
+        msgpack::write(
+            &Value::Array(vec![
+                Value::Int(topic_id),
+                Value::UInt(timestamp),
+                Value::UInt(type_id),
+                value,
+            ]),
+            &mut frame,
+        );
+        frame
+    }
+
+    #[test]
+    fn announce_and_unannounce() {
+        let mut session = Session::default();
+        session
+            .handle_text(
+                r#"[
+                    {"method": "announce", "params": {"name": "/drive/speed", "id": 3, "type": "double", "pubuid": 1, "properties": {}}},
+                    {"method": "properties", "params": {"name": "/drive/speed", "update": {"persistent": true}}},
+                    {"method": "announce", "params": {"name": "/drive/pose", "id": 4, "type": "struct:Pose2d", "properties": {}}}
+                ]"#,
+            )
+            .unwrap();
+        assert_eq!(session.topics().len(), 2);
+        assert_eq!(session.topics()[&4].ty, "struct:Pose2d");
+
+        session
+            .handle_text(
+                r#"[{"method": "unannounce", "params": {"name": "/drive/speed", "id": 3}}]"#,
+            )
+            .unwrap();
+        assert_eq!(session.topics().keys().copied().collect::<Vec<_>>(), [4]);
+
+        assert!(session.handle_text("not json").is_err());
+    }
+
+    #[test]
+    fn ping_is_an_rtt_message() {
+        let ping = ServerClock::ping(1_234);
+        let messages = frame::parse_messages(&ping).unwrap();
+        let [message] = &messages[..] else {
+            panic!("expected one message, got {}", messages.len());
+        };
+        assert_eq!(message.topic_id, frame::RTT_TOPIC_ID);
+        assert_eq!(message.value.as_i64(), Some(1_234));
+    }
+
+    #[test]
+    fn offset_from_round_trip() {
+        let mut clock = ServerClock::default();
+        assert_eq!(clock.to_server(500), None);
+
+        // sent at 1000, received at 1400: the server replied 200us in, at its 50_000
+        clock.pong(Timestamp(50_000), 1_000, 1_400);
+        assert_eq!(clock.to_server(1_400), Some(Timestamp(50_200)));
+        assert_eq!(clock.to_server(2_400), Some(Timestamp(51_200)));
+    }
+
+    #[test]
+    fn latency_once_synced() {
+        let mut session = Session::default();
+        session
+            .handle_text(
+                r#"[{"method": "announce", "params": {"name": "/drive/speed", "id": 3, "type": "double", "properties": {}}}]"#,
+            )
+            .unwrap();
+        let mut log = EntryLog::new();
+        let latency = EntityPath::from_file_path(Path::new("drive/speed/.latency"));
+
+        // before the first RTT reply there's no telling when a value was sent on this clock
+        session
+            .handle_binary(&mut log, &message(3, 1_000_000, 1, Value::F64(2.5)), 0)
+            .unwrap();
+        assert!(
+            log.get_entry(&EntityPath::from_file_path(Path::new("drive/speed")))
+                .is_some()
+        );
+        assert!(log.get_latest_entry(&latency).is_none());
+
+        // pinged at 100 and answered at the server's 2_000_000, received at 300
+        session
+            .handle_binary(
+                &mut log,
+                &message(frame::RTT_TOPIC_ID, 2_000_000, 2, Value::Int(100)),
+                300,
+            )
+            .unwrap();
+        session
+            .handle_binary(&mut log, &message(3, 2_000_000, 1, Value::F64(3.0)), 4_200)
+            .unwrap();
+
+        let (timestamp, value) = log.get_latest_entry(&latency).unwrap();
+        assert_eq!(*timestamp, Timestamp(2_000_000));
+        assert!((value.as_primitive::<Float64Type>().value(0) - 0.004).abs() < 1e-9);
+    }
+}
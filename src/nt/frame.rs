@@ -0,0 +1,454 @@
+//! Decoding of NT4 binary WebSocket frames.
+//!
+//! Each frame holds one or more `MessagePack` arrays of `[topic id, timestamp, type id, value]`.
+//! Values are re-encoded into their DataLog representation so they go through the same decoder as
+//! values read from a `.wpilog`. Struct and struct array topics arrive as raw bytes, which are
+//! decoded with the schemas published on their `/.schema/struct:<name>` topics.
+
+use hashbrown::HashMap;
+use rerun::{
+    EntityPath,
+    external::{
+        anyhow::{self, Context as _, anyhow, bail},
+        re_log,
+    },
+};
+use serde::Deserialize;
+
+use super::msgpack::{self, Value};
+use crate::{
+    log::{EntryLog, Timestamp},
+    values::{
+        EntryValue, decode_payload,
+        decoders::Decoders,
+        parse::wpistruct::{UnresolvedWpiLibStructType, WpiLibStructSchema},
+    },
+};
+
+/// The topic id the server uses for round-trip time pings.
+pub const RTT_TOPIC_ID: i64 = -1;
+
+/// The meta-entry a live value's latency is logged under.
+pub const LATENCY_META_ENTRY: &str = ".latency";
+
+/// A topic the server has announced.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Topic {
+    pub id: i64,
+    pub name: String,
+    /// The NT4 type string (e.g. `double`, `int[]`, `struct:Pose2d`).
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+impl Topic {
+    /// The DataLog type string for this topic's values.
+    ///
+    /// NT4 calls 64-bit integers `int`, while DataLogs call them `int64`.
+    #[must_use]
+    pub fn wpilog_type(&self) -> &str {
+        match self.ty.as_str() {
+            "int" => "int64",
+            "int[]" => "int64[]",
+            ty => ty,
+        }
+    }
+}
+
+/// A single binary frame message, before its value is decoded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawMessage<'f> {
+    pub topic_id: i64,
+    /// The server timestamp, in microseconds.
+    pub timestamp: Timestamp,
+    pub type_id: u8,
+    pub value: Value<'f>,
+}
+
+impl<'f> RawMessage<'f> {
+    fn from_value(value: Value<'f>) -> Result<Self, anyhow::Error> {
+        let Value::Array(fields) = value else {
+            bail!("binary message isn't an array");
+        };
+        let [topic_id, timestamp, type_id, value] = <[Value; 4]>::try_from(fields)
+            .map_err(|f| anyhow!("binary message has {} fields, expected 4", f.len()))?;
+
+        Ok(Self {
+            topic_id: topic_id.as_i64().context("invalid topic id")?,
+            timestamp: Timestamp(
+                timestamp
+                    .as_i64()
+                    .and_then(|t| u64::try_from(t).ok())
+                    .context("invalid timestamp")?,
+            ),
+            type_id: type_id
+                .as_i64()
+                .and_then(|t| u8::try_from(t).ok())
+                .context("invalid type id")?,
+            value,
+        })
+    }
+
+    /// Re-encodes the value the way a DataLog stores it.
+    pub fn to_wpilog_bytes(&self) -> Result<Vec<u8>, anyhow::Error> {
+        encode_value(self.type_id, &self.value)
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn encode_value(type_id: u8, value: &Value<'_>) -> Result<Vec<u8>, anyhow::Error> {
+    let mismatch = || anyhow!("value {value:?} doesn't match type id {type_id}");
+
+    Ok(match (type_id, value) {
+        (0, Value::Bool(b)) => vec![u8::from(*b)],
+        (1, v) => v.as_f64().ok_or_else(mismatch)?.to_le_bytes().to_vec(),
+        (2, v) => v.as_i64().ok_or_else(mismatch)?.to_le_bytes().to_vec(),
+        (3, v) => (v.as_f64().ok_or_else(mismatch)? as f32)
+            .to_le_bytes()
+            .to_vec(),
+        (4 | 5, Value::Str(s)) => s.as_bytes().to_vec(),
+        (4 | 5, Value::Bin(b)) => b.to_vec(),
+        (16..=19, Value::Array(values)) => values
+            .iter()
+            .map(|v| encode_value(type_id - 16, v))
+            .collect::<Result<Vec<_>, _>>()?
+            .concat(),
+        (20, Value::Array(values)) => {
+            // string arrays are a count, then each string prefixed by its length
+            let mut out = (values.len() as u32).to_le_bytes().to_vec();
+            for value in values {
+                let Value::Str(s) = value else {
+                    return Err(mismatch());
+                };
+                out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+                out.extend_from_slice(s.as_bytes());
+            }
+            out
+        }
+        _ => return Err(mismatch()),
+    })
+}
+
+/// Splits a binary frame into its messages, including RTT pings.
+pub fn parse_messages(mut data: &[u8]) -> Result<Vec<RawMessage<'_>>, anyhow::Error> {
+    let mut messages = Vec::new();
+
+    while !data.is_empty() {
+        let (remaining, value) =
+            msgpack::parse(data).map_err(|e| anyhow!("invalid binary frame: {e}"))?;
+        data = remaining;
+
+        messages.push(RawMessage::from_value(value)?);
+    }
+
+    Ok(messages)
+}
+
+/// Splits a binary frame into its messages, skipping RTT pings.
+pub fn parse_frame(data: &[u8]) -> Result<Vec<RawMessage<'_>>, anyhow::Error> {
+    let mut messages = parse_messages(data)?;
+    messages.retain(|message| message.topic_id != RTT_TOPIC_ID);
+
+    Ok(messages)
+}
+
+/// Decodes a binary frame into `(topic, server timestamp, value)` tuples.
+pub fn decode_frame<'t>(
+    data: &[u8],
+    topics: &'t HashMap<i64, Topic>,
+    struct_map: &HashMap<String, WpiLibStructSchema<UnresolvedWpiLibStructType>>,
+    decoders: &Decoders,
+) -> Result<Vec<(&'t Topic, Timestamp, EntryValue)>, anyhow::Error> {
+    parse_frame(data)?
+        .into_iter()
+        .map(|message| {
+            let topic = topics
+                .get(&message.topic_id)
+                .ok_or_else(|| anyhow!("value for unannounced topic {}", message.topic_id))?;
+
+            let value = decode_payload(
+                topic.wpilog_type(),
+                &message.to_wpilog_bytes()?,
+                struct_map,
+                decoders,
+            )
+            .map_err(|e| anyhow!("failed to decode {}: {e}", topic.name))?;
+
+            Ok((topic, message.timestamp, value))
+        })
+        .collect()
+}
+
+/// Adds the values of a frame's messages to `log` at their server timestamps, skipping RTT pings
+/// and any that fail to decode.
+///
+/// NT4 messages only carry the time the server sent them, so `received` is the time this client
+/// received the frame, already moved onto the server's clock by the offset estimated from RTT
+/// pings. Once it's known, each value also gets a `<topic>/.latency` scalar of how many seconds
+/// after being sent it was received.
+#[allow(clippy::cast_precision_loss)]
+pub fn add_messages(
+    log: &mut EntryLog,
+    messages: &[RawMessage<'_>],
+    topics: &HashMap<i64, Topic>,
+    received: Option<Timestamp>,
+) {
+    for message in messages {
+        if message.topic_id == RTT_TOPIC_ID {
+            continue;
+        }
+        let Some(topic) = topics.get(&message.topic_id) else {
+            re_log::warn_once!("skipping value for unannounced topic {}", message.topic_id);
+            continue;
+        };
+        let key = crate::wpilog::load::entry_path(&topic.name);
+
+        let added = message.to_wpilog_bytes().and_then(|bytes| {
+            log.add_entry(key.clone(), message.timestamp, topic.wpilog_type(), &bytes)
+        });
+        if let Err(e) = added {
+            re_log::warn_once!("failed to decode {}: {e}", topic.name);
+            continue;
+        }
+
+        let Some(received) = received else {
+            continue;
+        };
+        // a skewed offset estimate can put the receipt before the send
+        let latency = (i128::from(received.0) - i128::from(message.timestamp.0)) as f64 / 1e6;
+        if let Err(e) = log.add_entry(
+            key.join(&EntityPath::from_single_string(LATENCY_META_ENTRY)),
+            message.timestamp,
+            "double",
+            &latency.to_le_bytes(),
+        ) {
+            re_log::warn_once!("failed to log the latency of {}: {e}", topic.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use hashbrown::HashMap;
+    use rerun::{
+        EntityPath,
+        external::arrow::{
+            array::{AsArray as _, Float64Array, Int64Array},
+            datatypes::Float64Type,
+        },
+    };
+
+    use super::Topic;
+    use crate::{
+        log::{EntryLog, Timestamp},
+        values::{
+            EntryValue, decode_payload, decoders::Decoders, parse::wpistruct::WpiLibStructSchema,
+        },
+    };
+
+    fn topics() -> HashMap<i64, Topic> {
+        HashMap::from([
+            (
+                3,
+                Topic {
+                    id: 3,
+                    name: "/drive/speed".to_owned(),
+                    ty: "double".to_owned(),
+                },
+            ),
+            (
+                4,
+                Topic {
+                    id: 4,
+                    name: "/drive/ticks".to_owned(),
+                    ty: "int".to_owned(),
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn double_frame() {
+        let frame = [
+            0x94, // array of 4
+            0x03, // topic id 3
+            0xce, 0x00, 0x0f, 0x42, 0x40, // timestamp = 1,000,000 us
+            0x01, // type id 1 (double)
+            0xcb, 0x40, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 2.5
+        ];
+
+        let topics = topics();
+        let values =
+            super::decode_frame(&frame, &topics, &HashMap::new(), &Decoders::default()).unwrap();
+
+        assert_eq!(
+            values,
+            vec![(
+                &topics[&3],
+                Timestamp(1_000_000),
+                EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([2.5])))
+            )]
+        );
+    }
+
+    #[test]
+    fn multiple_messages_and_rtt() {
+        let frame = [
+            0x94, 0xff, 0x00, 0x02, 0x05, // RTT ping, skipped
+            0x94, 0x04, 0x0a, 0x02, 0xfb, // topic 4 at 10 us, int -5
+            0x94, 0x03, 0x0b, 0x01, 0x07, // topic 3 at 11 us, double sent as an int
+        ];
+
+        let topics = topics();
+        let values =
+            super::decode_frame(&frame, &topics, &HashMap::new(), &Decoders::default()).unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                (
+                    &topics[&4],
+                    Timestamp(10),
+                    EntryValue::Arrow(Arc::new(Int64Array::from_iter_values([-5])))
+                ),
+                (
+                    &topics[&3],
+                    Timestamp(11),
+                    EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([7.0])))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn struct_array_frame() {
+        let topics = HashMap::from([(
+            5,
+            Topic {
+                id: 5,
+                name: "/vision/targets".to_owned(),
+                ty: "struct:Pose2d[]".to_owned(),
+            },
+        )]);
+        let struct_map = HashMap::from([(
+            "struct:Pose2d".to_owned(),
+            WpiLibStructSchema::parse(b"double x;double y;double rotation").unwrap(),
+        )]);
+
+        let poses = [[1.0f64, 2.0, 0.5], [3.0, 4.0, -0.5]];
+        // topic 5 at 10 us, type id 5 (raw), bin 8 of the two structs
+        let mut frame = vec![0x94, 0x05, 0x0a, 0x05, 0xc4, 48];
+        frame.extend(poses.iter().flatten().flat_map(|v| v.to_le_bytes()));
+
+        let values =
+            super::decode_frame(&frame, &topics, &struct_map, &Decoders::default()).unwrap();
+        let [(topic, timestamp, EntryValue::ArrayMap(structs))] = &values[..] else {
+            panic!("struct array didn't decode to maps: {values:?}");
+        };
+        assert_eq!(topic.name, "/vision/targets");
+        assert_eq!(*timestamp, Timestamp(10));
+
+        let field = |i: usize, name: &str| {
+            let EntryValue::Arrow(array) = &structs[i][name] else {
+                panic!("{name} isn't a single value");
+            };
+            array.as_primitive::<Float64Type>().values()[..].to_vec()
+        };
+        assert_eq!(structs.len(), 2);
+        assert_eq!(field(0, "x"), [1.0]);
+        assert_eq!(field(1, "y"), [4.0]);
+        assert_eq!(field(1, "rotation"), [-0.5]);
+    }
+
+    #[test]
+    fn same_value_as_datalog() {
+        let topics = HashMap::from([
+            (
+                6,
+                Topic {
+                    id: 6,
+                    name: "/shooter/speeds".to_owned(),
+                    ty: "double[]".to_owned(),
+                },
+            ),
+            (
+                7,
+                Topic {
+                    id: 7,
+                    name: "/auto/names".to_owned(),
+                    ty: "string[]".to_owned(),
+                },
+            ),
+        ]);
+
+        let frame = [
+            0x94, 0x06, 0x00, 0x11, // topic 6, double[]
+            0x92, 0x01, 0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // [1, 1.5]
+            0x94, 0x07, 0x00, 0x14, // topic 7, string[]
+            0x92, 0xa1, b'a', 0xa2, b'b', b'c', // ["a", "bc"]
+        ];
+        let logged: [(&str, Vec<u8>); 2] = [
+            ("double[]", [1.0f64, 1.5].map(f64::to_le_bytes).concat()),
+            (
+                "string[]",
+                [
+                    &2u32.to_le_bytes()[..],
+                    &1u32.to_le_bytes(),
+                    b"a",
+                    &2u32.to_le_bytes(),
+                    b"bc",
+                ]
+                .concat(),
+            ),
+        ];
+
+        let values =
+            super::decode_frame(&frame, &topics, &HashMap::new(), &Decoders::default()).unwrap();
+        assert_eq!(values.len(), logged.len());
+        for ((_, _, live), (ty, bytes)) in values.into_iter().zip(logged) {
+            let file = decode_payload(ty, &bytes, &HashMap::new(), &Decoders::default()).unwrap();
+            assert_eq!(live, file, "{ty}");
+        }
+    }
+
+    #[test]
+    fn unannounced_topic() {
+        let frame = [0x94, 0x09, 0x00, 0x01, 0x00];
+
+        assert!(
+            super::decode_frame(&frame, &topics(), &HashMap::new(), &Decoders::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn latency_scalar() {
+        let frame = [
+            0x94, // array of 4
+            0x03, // topic id 3
+            0xce, 0x00, 0x0f, 0x42, 0x40, // timestamp = 1,000,000 us
+            0x01, // type id 1 (double)
+            0xcb, 0x40, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 2.5
+        ];
+
+        let mut log = EntryLog::new();
+        let messages = super::parse_frame(&frame).unwrap();
+        super::add_messages(&mut log, &messages, &topics(), Some(Timestamp(1_004_000)));
+
+        let value = |path: &str| {
+            let (timestamp, value) = log
+                .get_latest_entry(&EntityPath::from_file_path(Path::new(path)))
+                .unwrap();
+            (*timestamp, value.as_primitive::<Float64Type>().value(0))
+        };
+        let (timestamp, speed) = value("drive/speed");
+        assert_eq!(timestamp, Timestamp(1_000_000));
+        assert!((speed - 2.5).abs() < f64::EPSILON);
+
+        // logged alongside the value, on the server's clock
+        let (timestamp, latency) = value("drive/speed/.latency");
+        assert_eq!(timestamp, Timestamp(1_000_000));
+        assert!((latency - 0.004).abs() < 1e-9);
+    }
+}
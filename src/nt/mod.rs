@@ -1,6 +1,6 @@
-use rerun::external::re_log;
+pub mod client;
+pub mod frame;
+pub mod msgpack;
+pub mod replay;
 
-pub async fn begin_logging() {
-    // TODO
-    re_log::info!("Starting NetworkTables client");
-}
+pub use client::begin_logging;
@@ -0,0 +1,386 @@
+//! A minimal `MessagePack` reader, covering what NT4 puts on the wire.
+
+use nom::{
+    IResult,
+    bytes::complete as bcomplete,
+    error::ErrorKind,
+    number::complete::{self as ncomplete},
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidFormat(nom::error::ErrorKind),
+    InvalidString,
+    /// Arrays and maps were nested deeper than [`MAX_DEPTH`].
+    TooDeep,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidFormat(kind) => write!(f, "Invalid format: {kind:?}"),
+            Self::InvalidString => write!(f, "Invalid string"),
+            Self::TooDeep => write!(f, "Nested deeper than {MAX_DEPTH} levels"),
+        }
+    }
+}
+impl std::error::Error for ParseError {}
+
+impl<T> nom::error::ParseError<T> for ParseError {
+    fn from_error_kind(_input: T, kind: nom::error::ErrorKind) -> Self {
+        Self::InvalidFormat(kind)
+    }
+
+    fn append(_input: T, kind: nom::error::ErrorKind, _other: Self) -> Self {
+        Self::InvalidFormat(kind)
+    }
+}
+
+/// How deep arrays and maps can nest, far deeper than anything NT4 sends, so that a malicious
+/// frame can't overflow the stack.
+pub const MAX_DEPTH: usize = 32;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    F32(f32),
+    F64(f64),
+    Str(&'a str),
+    Bin(&'a [u8]),
+    Array(Vec<Value<'a>>),
+    Map(Vec<(Value<'a>, Value<'a>)>),
+}
+
+impl Value<'_> {
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Self::Int(i) => Some(i),
+            Self::UInt(u) => i64::try_from(u).ok(),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Self::F32(f) => Some(f64::from(f)),
+            Self::F64(f) => Some(f),
+            Self::Int(i) => Some(i as f64),
+            Self::UInt(u) => Some(u as f64),
+            _ => None,
+        }
+    }
+}
+
+fn parse_str(input: &[u8], len: usize) -> IResult<&[u8], Value<'_>, ParseError> {
+    let (input, bytes) = bcomplete::take(len)(input)?;
+    let string =
+        std::str::from_utf8(bytes).map_err(|_| nom::Err::Failure(ParseError::InvalidString))?;
+    Ok((input, Value::Str(string)))
+}
+
+fn parse_bin(input: &[u8], len: usize) -> IResult<&[u8], Value<'_>, ParseError> {
+    let (input, bytes) = bcomplete::take(len)(input)?;
+    Ok((input, Value::Bin(bytes)))
+}
+
+fn parse_array(
+    mut input: &[u8],
+    len: usize,
+    depth: usize,
+) -> IResult<&[u8], Value<'_>, ParseError> {
+    let mut values = Vec::new();
+    for _ in 0..len {
+        let (remaining, value) = parse_nested(input, depth + 1)?;
+        values.push(value);
+        input = remaining;
+    }
+    Ok((input, Value::Array(values)))
+}
+
+fn parse_map(mut input: &[u8], len: usize, depth: usize) -> IResult<&[u8], Value<'_>, ParseError> {
+    let mut values = Vec::new();
+    for _ in 0..len {
+        let (remaining, key) = parse_nested(input, depth + 1)?;
+        let (remaining, value) = parse_nested(remaining, depth + 1)?;
+        values.push((key, value));
+        input = remaining;
+    }
+    Ok((input, Value::Map(values)))
+}
+
+/// Parses a single `MessagePack` value.
+pub fn parse(input: &[u8]) -> IResult<&[u8], Value<'_>, ParseError> {
+    parse_nested(input, 0)
+}
+
+/// Parses a value `depth` arrays and maps deep.
+fn parse_nested(input: &[u8], depth: usize) -> IResult<&[u8], Value<'_>, ParseError> {
+    if depth > MAX_DEPTH {
+        return Err(nom::Err::Failure(ParseError::TooDeep));
+    }
+
+    let (input, marker) = ncomplete::u8(input)?;
+
+    match marker {
+        // positive fixint
+        0x00..=0x7f => Ok((input, Value::UInt(u64::from(marker)))),
+        // fixmap
+        0x80..=0x8f => parse_map(input, usize::from(marker & 0x0f), depth),
+        // fixarray
+        0x90..=0x9f => parse_array(input, usize::from(marker & 0x0f), depth),
+        // fixstr
+        0xa0..=0xbf => parse_str(input, usize::from(marker & 0x1f)),
+        0xc0 => Ok((input, Value::Nil)),
+        0xc2 => Ok((input, Value::Bool(false))),
+        0xc3 => Ok((input, Value::Bool(true))),
+        0xc4 => {
+            let (input, len) = ncomplete::u8(input)?;
+            parse_bin(input, usize::from(len))
+        }
+        0xc5 => {
+            let (input, len) = ncomplete::be_u16(input)?;
+            parse_bin(input, usize::from(len))
+        }
+        0xc6 => {
+            let (input, len) = ncomplete::be_u32(input)?;
+            parse_bin(input, len as usize)
+        }
+        0xca => ncomplete::be_f32(input).map(|(i, f)| (i, Value::F32(f))),
+        0xcb => ncomplete::be_f64(input).map(|(i, f)| (i, Value::F64(f))),
+        0xcc => ncomplete::u8(input).map(|(i, u)| (i, Value::UInt(u.into()))),
+        0xcd => ncomplete::be_u16(input).map(|(i, u)| (i, Value::UInt(u.into()))),
+        0xce => ncomplete::be_u32(input).map(|(i, u)| (i, Value::UInt(u.into()))),
+        0xcf => ncomplete::be_u64(input).map(|(i, u)| (i, Value::UInt(u))),
+        0xd0 => ncomplete::be_i8(input).map(|(i, n)| (i, Value::Int(n.into()))),
+        0xd1 => ncomplete::be_i16(input).map(|(i, n)| (i, Value::Int(n.into()))),
+        0xd2 => ncomplete::be_i32(input).map(|(i, n)| (i, Value::Int(n.into()))),
+        0xd3 => ncomplete::be_i64(input).map(|(i, n)| (i, Value::Int(n))),
+        0xd9 => {
+            let (input, len) = ncomplete::u8(input)?;
+            parse_str(input, usize::from(len))
+        }
+        0xda => {
+            let (input, len) = ncomplete::be_u16(input)?;
+            parse_str(input, usize::from(len))
+        }
+        0xdb => {
+            let (input, len) = ncomplete::be_u32(input)?;
+            parse_str(input, len as usize)
+        }
+        0xdc => {
+            let (input, len) = ncomplete::be_u16(input)?;
+            parse_array(input, usize::from(len), depth)
+        }
+        0xdd => {
+            let (input, len) = ncomplete::be_u32(input)?;
+            parse_array(input, len as usize, depth)
+        }
+        0xde => {
+            let (input, len) = ncomplete::be_u16(input)?;
+            parse_map(input, usize::from(len), depth)
+        }
+        0xdf => {
+            let (input, len) = ncomplete::be_u32(input)?;
+            parse_map(input, len as usize, depth)
+        }
+        // negative fixint
+        0xe0..=0xff => Ok((input, Value::Int(i64::from(marker.cast_signed())))),
+        // ext types and the reserved marker aren't used by NT4
+        _ => Err(nom::Err::Failure(ParseError::InvalidFormat(ErrorKind::Tag))),
+    }
+}
+
+/// Appends the `MessagePack` encoding of `value` to `out`, in its smallest form.
+#[allow(clippy::cast_possible_truncation)]
+pub fn write(value: &Value<'_>, out: &mut Vec<u8>) {
+    match *value {
+        Value::Nil => out.push(0xc0),
+        Value::Bool(b) => out.push(if b { 0xc3 } else { 0xc2 }),
+        Value::Int(i) if i >= 0 => write(&Value::UInt(i.cast_unsigned()), out),
+        Value::Int(i) if i >= -32 => out.push((i as i8).cast_unsigned()),
+        Value::Int(i) if i >= i64::from(i8::MIN) => {
+            out.push(0xd0);
+            out.push((i as i8).cast_unsigned());
+        }
+        Value::Int(i) if i >= i64::from(i16::MIN) => {
+            out.push(0xd1);
+            out.extend_from_slice(&(i as i16).to_be_bytes());
+        }
+        Value::Int(i) if i >= i64::from(i32::MIN) => {
+            out.push(0xd2);
+            out.extend_from_slice(&(i as i32).to_be_bytes());
+        }
+        Value::Int(i) => {
+            out.push(0xd3);
+            out.extend_from_slice(&i.to_be_bytes());
+        }
+        Value::UInt(u) => match u {
+            0..0x80 => out.push(u as u8),
+            0x80..0x100 => {
+                out.push(0xcc);
+                out.push(u as u8);
+            }
+            0x100..0x1_0000 => {
+                out.push(0xcd);
+                out.extend_from_slice(&(u as u16).to_be_bytes());
+            }
+            0x1_0000..0x1_0000_0000 => {
+                out.push(0xce);
+                out.extend_from_slice(&(u as u32).to_be_bytes());
+            }
+            _ => {
+                out.push(0xcf);
+                out.extend_from_slice(&u.to_be_bytes());
+            }
+        },
+        Value::F32(f) => {
+            out.push(0xca);
+            out.extend_from_slice(&f.to_be_bytes());
+        }
+        Value::F64(f) => {
+            out.push(0xcb);
+            out.extend_from_slice(&f.to_be_bytes());
+        }
+        Value::Str(s) => {
+            match s.len() {
+                len @ 0..32 => out.push(0xa0 | len as u8),
+                len => write_len(out, len, Some(0xd9), 0xda, 0xdb),
+            }
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Bin(b) => {
+            write_len(out, b.len(), Some(0xc4), 0xc5, 0xc6);
+            out.extend_from_slice(b);
+        }
+        Value::Array(ref values) => {
+            match values.len() {
+                len @ 0..16 => out.push(0x90 | len as u8),
+                len => write_len(out, len, None, 0xdc, 0xdd),
+            }
+            for value in values {
+                write(value, out);
+            }
+        }
+        Value::Map(ref entries) => {
+            match entries.len() {
+                len @ 0..16 => out.push(0x80 | len as u8),
+                len => write_len(out, len, None, 0xde, 0xdf),
+            }
+            for (key, value) in entries {
+                write(key, out);
+                write(value, out);
+            }
+        }
+    }
+}
+
+/// Writes the marker and length of a value too long for its fixed-length form, with the marker
+/// for the smallest length that fits. Arrays and maps have no 8-bit length.
+#[allow(clippy::cast_possible_truncation)]
+fn write_len(out: &mut Vec<u8>, len: usize, len8: Option<u8>, len16: u8, len32: u8) {
+    match len8 {
+        Some(marker) if len <= usize::from(u8::MAX) => {
+            out.push(marker);
+            out.push(len as u8);
+        }
+        _ if len <= usize::from(u16::MAX) => {
+            out.push(len16);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            out.push(len32);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseError, Value};
+
+    #[test]
+    fn scalars() {
+        assert_eq!(super::parse(&[0x05]).unwrap().1, Value::UInt(5));
+        assert_eq!(super::parse(&[0xfb]).unwrap().1, Value::Int(-5));
+        assert_eq!(super::parse(&[0xc3]).unwrap().1, Value::Bool(true));
+        assert_eq!(
+            super::parse(&[0xce, 0x00, 0x0f, 0x42, 0x40]).unwrap().1,
+            Value::UInt(1_000_000)
+        );
+        assert_eq!(
+            super::parse(&[0xa3, b'a', b'b', b'c']).unwrap().1,
+            Value::Str("abc")
+        );
+    }
+
+    #[test]
+    fn nested() {
+        let (input, value) = super::parse(&[0x92, 0x01, 0x91, 0xc0]).unwrap();
+
+        assert!(input.is_empty());
+        assert_eq!(
+            value,
+            Value::Array(vec![Value::UInt(1), Value::Array(vec![Value::Nil])])
+        );
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let long = "x".repeat(300);
+        let values = [
+            Value::Nil,
+            Value::Bool(false),
+            Value::UInt(5),
+            Value::UInt(200),
+            Value::UInt(70_000),
+            Value::UInt(u64::MAX),
+            Value::Int(-5),
+            Value::Int(-100),
+            Value::Int(-40_000),
+            Value::Int(i64::MIN),
+            Value::F32(1.5),
+            Value::F64(-2.25),
+            Value::Str("abc"),
+            Value::Str(&long),
+            Value::Bin(&[1, 2, 3]),
+            Value::Array((0..20).map(Value::UInt).collect()),
+            Value::Map(vec![(Value::Str("a"), Value::Array(vec![Value::Nil]))]),
+        ];
+
+        for value in values {
+            let mut out = Vec::new();
+            super::write(&value, &mut out);
+
+            let (rest, parsed) = super::parse(&out).unwrap();
+            assert!(rest.is_empty(), "{value:?}");
+            assert_eq!(parsed, value);
+        }
+
+        // positive numbers are written in their smallest form, whatever their variant
+        let mut out = Vec::new();
+        super::write(&Value::Int(1_000_000), &mut out);
+        assert_eq!(out, [0xce, 0x00, 0x0f, 0x42, 0x40]);
+    }
+
+    #[test]
+    fn too_deep() {
+        let nested = |depth| {
+            let mut data = vec![0x91; depth];
+            data.push(0xc0);
+            data
+        };
+
+        assert!(super::parse(&nested(super::MAX_DEPTH)).is_ok());
+        // a frame of nothing but one-element arrays would otherwise recurse until it overflowed
+        assert_eq!(
+            super::parse(&nested(100_000)),
+            Err(nom::Err::Failure(ParseError::TooDeep))
+        );
+    }
+}
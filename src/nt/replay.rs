@@ -0,0 +1,461 @@
+//! Re-publishing the NetworkTables entries of a DataLog at real time, or a multiple of it.
+
+use std::{io, net::TcpStream, time::Duration};
+
+use hashbrown::HashMap;
+use rerun::external::{
+    anyhow::{self, Context as _, anyhow, bail},
+    re_log,
+};
+use tokio_tungstenite::tungstenite::{
+    self, Message, WebSocket, client::IntoClientRequest as _, http::HeaderValue,
+    stream::MaybeTlsStream,
+};
+
+use super::{
+    client::SUBPROTOCOLS,
+    msgpack::{self, Value},
+};
+use crate::{
+    config::EntryFilter,
+    log::Timestamp,
+    wpilog::{
+        load::{entry_name, nt_topic},
+        parse::{Payload, WpiRecord},
+    },
+};
+
+/// A value to re-publish, in its DataLog encoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayMessage<'log> {
+    /// The topic name, without the `NT:` prefix.
+    pub topic: &'log str,
+    pub ty: &'log str,
+    pub timestamp: Timestamp,
+    pub data: &'log [u8],
+}
+
+/// Collects the values of every `NT:` entry that `filter` allows, in timestamp order.
+///
+/// Entries logged by the robot program itself rather than mirrored from NetworkTables are skipped.
+#[must_use]
+pub fn nt_messages<'log>(
+    records: &[WpiRecord<'log>],
+    filter: &EntryFilter,
+) -> Vec<ReplayMessage<'log>> {
+    let mut topics = HashMap::new();
+    let mut messages = Vec::new();
+
+    for record in records {
+        match record.payload {
+            Payload::Start {
+                entry_id,
+                entry_name,
+                entry_type,
+                ..
+            } => {
+                if let Some(topic) = nt_topic(entry_name)
+                    && filter.allows(self::entry_name(entry_name))
+                {
+                    topics.insert(entry_id, (topic, entry_type));
+                }
+            }
+            Payload::Finish { entry_id } => {
+                topics.remove(&entry_id);
+            }
+            Payload::Raw { entry_id, data } => {
+                if let Some(&(topic, ty)) = topics.get(&entry_id) {
+                    messages.push(ReplayMessage {
+                        topic,
+                        ty,
+                        timestamp: record.timestamp,
+                        data,
+                    });
+                }
+            }
+            Payload::SetMetadata { .. } => {}
+        }
+    }
+
+    // records aren't guaranteed to be written in timestamp order
+    messages.sort_by_key(|message| message.timestamp);
+
+    messages
+}
+
+/// Waits between replayed messages.
+pub trait Clock {
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// A [`Clock`] that actually waits.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Publishes `messages` in order, waiting out the time between them divided by `speed`.
+pub fn replay(
+    messages: &[ReplayMessage<'_>],
+    speed: f64,
+    clock: &mut impl Clock,
+    mut publish: impl FnMut(&ReplayMessage<'_>) -> Result<(), anyhow::Error>,
+) -> Result<(), anyhow::Error> {
+    if !speed.is_finite() || speed <= 0.0 {
+        bail!("replay speed must be positive, got {speed}");
+    }
+
+    let mut previous: Option<Timestamp> = None;
+    for message in messages {
+        if let Some(previous) = previous {
+            let elapsed = Duration::from_micros(message.timestamp.0.saturating_sub(previous.0));
+            if !elapsed.is_zero() {
+                clock.sleep(elapsed.div_f64(speed));
+            }
+        }
+        previous = Some(message.timestamp);
+
+        publish(message)?;
+    }
+
+    Ok(())
+}
+
+/// The NT4 type string and binary type id of a DataLog type.
+///
+/// Anything that isn't a primitive or an array of them, like structs and protobufs, is sent as
+/// raw bytes under its own type string.
+#[must_use]
+pub fn nt_type(ty: &str) -> (&str, u8) {
+    match ty {
+        "boolean" => (ty, 0),
+        "double" => (ty, 1),
+        "int64" => ("int", 2),
+        "float" => (ty, 3),
+        "string" | "json" => (ty, 4),
+        "boolean[]" => (ty, 16),
+        "double[]" => (ty, 17),
+        "int64[]" => ("int[]", 18),
+        "float[]" => (ty, 19),
+        "string[]" => (ty, 20),
+        _ => (ty, 5),
+    }
+}
+
+fn le_bytes<const N: usize>(data: &[u8]) -> Result<[u8; N], anyhow::Error> {
+    data.try_into()
+        .map_err(|_| anyhow!("expected {N} bytes, got {}", data.len()))
+}
+
+fn take_u32(data: &mut &[u8]) -> Result<usize, anyhow::Error> {
+    let (len, rest) = data
+        .split_first_chunk::<4>()
+        .context("string array cut off")?;
+    *data = rest;
+    Ok(u32::from_le_bytes(*len) as usize)
+}
+
+/// Decodes a DataLog value into the msgpack value NT4 sends for `type_id`.
+fn decode_value(type_id: u8, data: &[u8]) -> Result<Value<'_>, anyhow::Error> {
+    Ok(match type_id {
+        0 => Value::Bool(le_bytes::<1>(data)? != [0]),
+        1 => Value::F64(f64::from_le_bytes(le_bytes(data)?)),
+        2 => Value::Int(i64::from_le_bytes(le_bytes(data)?)),
+        3 => Value::F32(f32::from_le_bytes(le_bytes(data)?)),
+        4 => Value::Str(std::str::from_utf8(data)?),
+        16..=19 => {
+            let size = [1, 8, 8, 4][usize::from(type_id - 16)];
+            if data.len() % size != 0 {
+                bail!(
+                    "{} bytes isn't a whole number of {size} byte elements",
+                    data.len()
+                );
+            }
+            Value::Array(
+                data.chunks_exact(size)
+                    .map(|element| decode_value(type_id - 16, element))
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        20 => {
+            // a count, then each string prefixed by its length
+            let mut data = data;
+            let count = take_u32(&mut data)?;
+            let mut strings = Vec::new();
+            for _ in 0..count {
+                let len = take_u32(&mut data)?;
+                let (string, rest) = data.split_at_checked(len).context("string array cut off")?;
+                strings.push(Value::Str(std::str::from_utf8(string)?));
+                data = rest;
+            }
+            Value::Array(strings)
+        }
+        _ => Value::Bin(data),
+    })
+}
+
+/// Encodes a binary frame setting the topic published as `pubuid` to a DataLog value.
+pub fn value_frame(
+    pubuid: i64,
+    timestamp: Timestamp,
+    ty: &str,
+    data: &[u8],
+) -> Result<Vec<u8>, anyhow::Error> {
+    let (_, type_id) = nt_type(ty);
+    let value = decode_value(type_id, data).with_context(|| format!("invalid {ty} value"))?;
+
+    let mut frame = Vec::new();
+    msgpack::write(
+        &Value::Array(vec![
+            Value::Int(pubuid),
+            Value::UInt(timestamp.0),
+            Value::UInt(u64::from(type_id)),
+            value,
+        ]),
+        &mut frame,
+    );
+    Ok(frame)
+}
+
+/// Publishes replayed values to an NT4 server.
+///
+/// Values keep their logged timestamps, so subscribers see the robot time the log recorded.
+pub struct Publisher {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+    /// The pubuid each topic was published under.
+    pubuids: HashMap<String, i64>,
+}
+
+impl Publisher {
+    /// Connects to the NT4 server at `url`.
+    pub fn connect(url: &str) -> Result<Self, anyhow::Error> {
+        let mut request = url.into_client_request()?;
+        request.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            HeaderValue::from_static(SUBPROTOCOLS),
+        );
+        let (socket, _) =
+            tungstenite::connect(request).with_context(|| format!("failed to connect to {url}"))?;
+
+        Ok(Self {
+            socket,
+            pubuids: HashMap::new(),
+        })
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        match self.socket.get_ref() {
+            MaybeTlsStream::Plain(stream) => stream.set_nonblocking(nonblocking),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads whatever the server has sent since the last message without waiting for more, so
+    /// it doesn't pile up over a long replay.
+    ///
+    /// Nothing it sends needs handling, as websocket pings are answered with the next write and
+    /// the announcements of the topics being published are only for subscribers.
+    fn drain(&mut self) -> Result<(), anyhow::Error> {
+        self.set_nonblocking(true)?;
+        let drained = loop {
+            match self.socket.read() {
+                Ok(Message::Close(_)) => break Err(anyhow!("the server closed the connection")),
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => {
+                    break Ok(());
+                }
+                Err(e) => break Err(e.into()),
+            }
+        };
+        self.set_nonblocking(false)?;
+
+        drained
+    }
+
+    /// Sends a message's value, publishing its topic first if it's new.
+    ///
+    /// A value that isn't valid for its type is skipped rather than ending the replay.
+    pub fn publish(&mut self, message: &ReplayMessage<'_>) -> Result<(), anyhow::Error> {
+        self.drain()?;
+
+        let pubuid = match self.pubuids.get(message.topic) {
+            Some(&pubuid) => pubuid,
+            None => {
+                let pubuid = i64::try_from(self.pubuids.len())?;
+                let publish = serde_json::json!([{
+                    "method": "publish",
+                    "params": {
+                        "name": message.topic,
+                        "pubuid": pubuid,
+                        "type": nt_type(message.ty).0,
+                        "properties": {},
+                    },
+                }]);
+                self.socket.send(Message::Text(publish.to_string()))?;
+                self.pubuids.insert(message.topic.to_owned(), pubuid);
+                pubuid
+            }
+        };
+
+        let frame = match value_frame(pubuid, message.timestamp, message.ty, message.data) {
+            Ok(frame) => frame,
+            Err(e) => {
+                re_log::warn_once!(
+                    "skipping {} values that can't be published: {e}",
+                    message.topic
+                );
+                return Ok(());
+            }
+        };
+        self.socket.send(Message::Binary(frame))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Clock;
+    use crate::{
+        config::{EntityPattern, EntryFilter},
+        log::Timestamp,
+        nt::frame,
+        wpilog::parse::{Payload, WpiRecord},
+    };
+
+    #[derive(Default)]
+    struct MockClock(Vec<Duration>);
+
+    impl Clock for MockClock {
+        fn sleep(&mut self, duration: Duration) {
+            self.0.push(duration);
+        }
+    }
+
+    #[test]
+    fn replays_in_order_with_scaled_delays() {
+        let record = |timestamp, payload| WpiRecord {
+            timestamp: Timestamp(timestamp),
+            payload,
+        };
+        let records = [
+            record(
+                0,
+                Payload::Start {
+                    entry_id: 1,
+                    entry_name: "NT:/drive/speed",
+                    entry_type: "double",
+                    entry_metadata: "",
+                },
+            ),
+            record(
+                0,
+                Payload::Start {
+                    entry_id: 2,
+                    entry_name: "messages",
+                    entry_type: "string",
+                    entry_metadata: "",
+                },
+            ),
+            record(
+                0,
+                Payload::Start {
+                    entry_id: 3,
+                    entry_name: "NT:/drive/excluded",
+                    entry_type: "double",
+                    entry_metadata: "",
+                },
+            ),
+            record(
+                500_000,
+                Payload::Raw {
+                    entry_id: 3,
+                    data: b"filtered",
+                },
+            ),
+            record(
+                3_000_000,
+                Payload::Raw {
+                    entry_id: 1,
+                    data: b"c",
+                },
+            ),
+            record(
+                1_000_000,
+                Payload::Raw {
+                    entry_id: 1,
+                    data: b"a",
+                },
+            ),
+            record(
+                1_500_000,
+                Payload::Raw {
+                    entry_id: 2,
+                    data: b"skipped",
+                },
+            ),
+            record(
+                2_000_000,
+                Payload::Raw {
+                    entry_id: 1,
+                    data: b"b",
+                },
+            ),
+        ];
+
+        let filter = EntryFilter {
+            exclude: vec![EntityPattern("drive/excluded".to_owned())],
+            ..EntryFilter::default()
+        };
+        let messages = super::nt_messages(&records, &filter);
+        let mut clock = MockClock::default();
+        let mut published = Vec::new();
+        super::replay(&messages, 2.0, &mut clock, |message| {
+            assert_eq!(message.topic, "/drive/speed");
+            published.push(message.data.to_vec());
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(published, [b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+        assert_eq!(clock.0, [Duration::from_millis(500); 2]);
+    }
+
+    #[test]
+    fn value_frames_decode_to_the_logged_bytes() {
+        let strings = [
+            &2u32.to_le_bytes()[..],
+            &1u32.to_le_bytes(),
+            b"a",
+            &2u32.to_le_bytes(),
+            b"bc",
+        ]
+        .concat();
+        let values: [(&str, Vec<u8>); 7] = [
+            ("boolean", vec![1]),
+            ("double", 2.5f64.to_le_bytes().to_vec()),
+            ("int64", (-5i64).to_le_bytes().to_vec()),
+            ("string", b"hi".to_vec()),
+            ("double[]", [1.0f64, 1.5].map(f64::to_le_bytes).concat()),
+            ("string[]", strings),
+            ("struct:Pose2d", vec![0; 24]),
+        ];
+
+        for (ty, data) in values {
+            let frame = super::value_frame(3, Timestamp(1_000), ty, &data).unwrap();
+            let messages = frame::parse_messages(&frame).unwrap();
+            let [message] = &messages[..] else {
+                panic!("expected one message for {ty}, got {}", messages.len());
+            };
+            assert_eq!(message.topic_id, 3);
+            assert_eq!(message.timestamp, Timestamp(1_000));
+            assert_eq!(message.to_wpilog_bytes().unwrap(), data, "{ty}");
+        }
+
+        assert!(super::value_frame(3, Timestamp(0), "double", &[0; 4]).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! Decoders for entry types the crate doesn't know about, registered before loading.
+
+use rerun::external::anyhow;
+
+use super::EntryValue;
+
+/// Decodes a value given its full entry type, including any array suffix.
+pub type DecodeFn = dyn Fn(&str, &[u8]) -> Result<EntryValue, anyhow::Error> + Send + Sync;
+
+/// Custom decoders, each for the entry types starting with a prefix.
+///
+/// They're only consulted for types that aren't built in.
+#[derive(Default)]
+pub struct Decoders {
+    decoders: Vec<(String, Box<DecodeFn>)>,
+}
+
+impl Decoders {
+    /// Decodes every type starting with `prefix`, such as `mytype:`, with `decoder`.
+    ///
+    /// Later registrations take precedence, so a more specific prefix should be registered after
+    /// a more general one.
+    pub fn register(
+        &mut self,
+        prefix: impl Into<String>,
+        decoder: impl Fn(&str, &[u8]) -> Result<EntryValue, anyhow::Error> + Send + Sync + 'static,
+    ) {
+        self.decoders.push((prefix.into(), Box::new(decoder)));
+    }
+
+    /// Finds the decoder for `ty`, if one was registered.
+    #[must_use]
+    pub fn find(&self, ty: &str) -> Option<&DecodeFn> {
+        self.decoders
+            .iter()
+            .rev()
+            .find(|(prefix, _)| ty.starts_with(prefix.as_str()))
+            .map(|(_, decoder)| decoder.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use hashbrown::HashMap;
+    use rerun::external::arrow::array::{AsArray as _, Int64Array};
+
+    use super::Decoders;
+    use crate::values::{EntryValue, EntryValueParseError};
+
+    #[test]
+    fn custom_prefix() {
+        let mut decoders = Decoders::default();
+        decoders.register("mytype:", |ty, data| {
+            assert_eq!(ty, "mytype:counter");
+            Ok(EntryValue::Arrow(Arc::new(Int64Array::from_iter_values([
+                i64::try_from(data.len())?,
+            ]))))
+        });
+
+        let EntryValue::Arrow(value) =
+            EntryValue::parse_from_wpilog("mytype:counter", &[0; 3], &HashMap::new(), &decoders)
+                .unwrap()
+        else {
+            panic!("custom type wasn't decoded by its decoder");
+        };
+        assert_eq!(
+            value
+                .as_primitive::<rerun::external::arrow::datatypes::Int64Type>()
+                .value(0),
+            3
+        );
+
+        // built in types can't be overridden
+        assert!(matches!(
+            EntryValue::parse_from_wpilog("double", &[0; 8], &HashMap::new(), &decoders),
+            Ok(EntryValue::Arrow(_))
+        ));
+        assert!(matches!(
+            EntryValue::parse_from_wpilog("other:counter", &[], &HashMap::new(), &decoders),
+            Err(EntryValueParseError::UnknownType(_))
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+//! Uncompressed camera frames logged as `raw` entries, described by the entry's metadata.
+
+use rerun::{
+    Image, PixelFormat,
+    external::anyhow::{self, bail},
+};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FrameLayout {
+    /// Packed 4:2:2, two bytes per pixel.
+    Yuyv,
+    /// Planar 4:2:0, a full-size luma plane followed by an interleaved half-size chroma plane.
+    Nv12,
+}
+
+/// The layout of a raw frame, read from entry metadata such as
+/// `{"format":"YUYV","width":640,"height":480}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct FrameFormat {
+    pub format: FrameLayout,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FrameFormat {
+    /// Reads the frame format from entry metadata, or `None` if it doesn't describe a frame.
+    #[must_use]
+    pub fn from_metadata(metadata: &str) -> Option<Self> {
+        serde_json::from_str(metadata).ok()
+    }
+
+    /// The number of bytes a frame of this format takes up, or `None` if that's more than can be
+    /// addressed.
+    #[must_use]
+    pub const fn frame_len(&self) -> Option<usize> {
+        let Some(pixels) = (self.width as usize).checked_mul(self.height as usize) else {
+            return None;
+        };
+
+        match self.format {
+            FrameLayout::Yuyv => pixels.checked_mul(2),
+            FrameLayout::Nv12 => pixels.checked_add(pixels / 2),
+        }
+    }
+
+    pub fn to_image(&self, data: &[u8]) -> Result<Image, anyhow::Error> {
+        // each chroma sample covers a 2x2 block, which an odd side would leave half of
+        if self.format == FrameLayout::Nv12 && (self.width % 2 != 0 || self.height % 2 != 0) {
+            bail!(
+                "{}x{} NV12 frame should have even sides",
+                self.width,
+                self.height
+            );
+        }
+        let Some(len) = self.frame_len() else {
+            bail!("{}x{} frame is too large", self.width, self.height);
+        };
+        if data.len() != len {
+            bail!(
+                "{}x{} {:?} frame should be {len} bytes, got {}",
+                self.width,
+                self.height,
+                self.format,
+                data.len()
+            );
+        }
+
+        let pixel_format = match self.format {
+            FrameLayout::Yuyv => PixelFormat::YUY2,
+            FrameLayout::Nv12 => PixelFormat::NV12,
+        };
+
+        Ok(Image::from_pixel_format(
+            [self.width, self.height],
+            pixel_format,
+            data.to_vec(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameFormat, FrameLayout};
+
+    #[test]
+    fn yuyv_frame() {
+        let format =
+            FrameFormat::from_metadata(r#"{"format":"YUYV","width":2,"height":2}"#).unwrap();
+        assert_eq!(format.format, FrameLayout::Yuyv);
+
+        // two rows of Y0 U Y1 V
+        let frame = [16, 128, 235, 128, 81, 90, 145, 240];
+        assert!(format.to_image(&frame).is_ok());
+        assert!(format.to_image(&frame[..6]).is_err());
+
+        assert_eq!(
+            FrameFormat::from_metadata(r#"{"source":"limelight"}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn nv12_frame() {
+        let format =
+            FrameFormat::from_metadata(r#"{"format":"NV12","width":2,"height":2}"#).unwrap();
+        // four luma samples, then one pair of chroma samples
+        assert!(format.to_image(&[16, 32, 64, 128, 90, 240]).is_ok());
+
+        let odd = FrameFormat::from_metadata(r#"{"format":"NV12","width":3,"height":3}"#).unwrap();
+        assert!(odd.to_image(&[0; 13]).is_err());
+    }
+
+    #[test]
+    fn oversized_frame() {
+        let format = FrameFormat {
+            format: FrameLayout::Yuyv,
+            width: u32::MAX,
+            height: u32::MAX,
+        };
+        assert_eq!(format.frame_len(), None);
+        assert!(format.to_image(&[]).is_err());
+    }
+}
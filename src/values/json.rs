@@ -0,0 +1,96 @@
+//! Converting decoded values to JSON, for exports and for values nested too deeply to flatten.
+
+use rerun::external::{
+    arrow::{
+        array::{Array, ArrayRef, AsArray as _},
+        datatypes::{
+            DataType, Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type,
+            UInt8Type, UInt16Type, UInt32Type, UInt64Type,
+        },
+    },
+    re_log,
+};
+use serde_json::{Map, Value};
+
+/// Converts the value at `row` of `array` to JSON.
+pub(crate) fn json_value(array: &dyn Array, row: usize) -> Value {
+    if array.is_null(row) {
+        return Value::Null;
+    }
+
+    match array.data_type() {
+        DataType::Boolean => array.as_boolean().value(row).into(),
+        DataType::Int8 => array.as_primitive::<Int8Type>().value(row).into(),
+        DataType::Int16 => array.as_primitive::<Int16Type>().value(row).into(),
+        DataType::Int32 => array.as_primitive::<Int32Type>().value(row).into(),
+        DataType::Int64 => array.as_primitive::<Int64Type>().value(row).into(),
+        DataType::UInt8 => array.as_primitive::<UInt8Type>().value(row).into(),
+        DataType::UInt16 => array.as_primitive::<UInt16Type>().value(row).into(),
+        DataType::UInt32 => array.as_primitive::<UInt32Type>().value(row).into(),
+        DataType::UInt64 => array.as_primitive::<UInt64Type>().value(row).into(),
+        DataType::Float32 => array.as_primitive::<Float32Type>().value(row).into(),
+        DataType::Float64 => array.as_primitive::<Float64Type>().value(row).into(),
+        DataType::Utf8 => array.as_string::<i32>().value(row).into(),
+        DataType::LargeUtf8 => array.as_string::<i64>().value(row).into(),
+        DataType::Binary => base64(array.as_binary::<i32>().value(row)).into(),
+        DataType::LargeBinary => base64(array.as_binary::<i64>().value(row)).into(),
+        DataType::Struct(_) => {
+            let array = array.as_struct();
+            Value::Object(
+                array
+                    .column_names()
+                    .into_iter()
+                    .zip(array.columns())
+                    .map(|(name, column)| (name.to_owned(), json_value(column.as_ref(), row)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        DataType::List(_) => list_json(&array.as_list::<i32>().value(row)),
+        DataType::LargeList(_) => list_json(&array.as_list::<i64>().value(row)),
+        DataType::FixedSizeList(..) => list_json(&array.as_fixed_size_list().value(row)),
+        ty => {
+            re_log::warn_once!("Can't export {ty} values as JSON");
+            Value::Null
+        }
+    }
+}
+
+fn list_json(values: &ArrayRef) -> Value {
+    Value::Array(
+        (0..values.len())
+            .map(|row| json_value(values.as_ref(), row))
+            .collect(),
+    )
+}
+
+/// Standard, padded base64, as used for raw values in JSON.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | u32::from(byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn base64_padding() {
+        assert_eq!(super::base64(b""), "");
+        assert_eq!(super::base64(b"f"), "Zg==");
+        assert_eq!(super::base64(b"fo"), "Zm8=");
+        assert_eq!(super::base64(b"foo"), "Zm9v");
+    }
+}
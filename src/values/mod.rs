@@ -4,14 +4,15 @@ use hashbrown::HashMap;
 use nom::{Finish as _, IResult};
 use parse::wpistruct::{
     UnresolvedWpiLibStructType, WpiLibStructData, WpiLibStructPrimitives, WpiLibStructSchema,
-    WpiLibStructType,
+    WpiLibStructType, WpiLibStructValues, struct_type_name,
 };
 use rerun::external::{
     anyhow::{self, Context, anyhow, bail},
     arrow::{
         array::{
-            ArrayRef, BinaryArray, BooleanArray, Float32Array, Float64Array, Int64Array, NullArray,
-            StringArray, StructArray,
+            ArrayRef, BinaryArray, BooleanArray, Float32Array, Float64Array, Int8Array, Int16Array,
+            Int32Array, Int64Array, NullArray, StringArray, StructArray, UInt8Array, UInt16Array,
+            UInt32Array, UInt64Array,
         },
         datatypes::DataType,
     },
@@ -19,8 +20,14 @@ use rerun::external::{
 };
 
 use crate::log::EntryLog;
+use decoders::Decoders;
 
+pub mod decoders;
+pub mod image;
+pub mod json;
 pub mod parse;
+#[cfg(feature = "serialize")]
+pub mod serialize;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum EntryValue {
@@ -30,6 +37,9 @@ pub enum EntryValue {
 
     Map(HashMap<String, EntryValue>),
     ArrayMap(Vec<HashMap<String, EntryValue>>),
+
+    /// Kept as parsed so it can be re-serialized, and flattened into entities when logged.
+    Json(serde_json::Value),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -59,12 +69,25 @@ impl<T> nom::error::ParseError<T> for ParseError {
 #[derive(Debug)]
 pub enum EntryValueParseError {
     StructNotFound(String),
+    /// A fixed-size value had fewer bytes than its type needs.
+    Truncated {
+        ty: &'static str,
+        need: usize,
+        got: usize,
+    },
+    UnknownType(String),
+    InvalidUtf8,
     Other(anyhow::Error),
 }
 impl std::fmt::Display for EntryValueParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::StructNotFound(s) => write!(f, "Struct not found: {s}"),
+            Self::Truncated { ty, need, got } => {
+                write!(f, "Truncated {ty}: needs {need} bytes, got {got}")
+            }
+            Self::UnknownType(ty) => write!(f, "Unknown data type: {ty}"),
+            Self::InvalidUtf8 => write!(f, "Invalid UTF-8 in string"),
             Self::Other(err) => write!(f, "{}", err),
         }
     }
@@ -76,22 +99,132 @@ impl From<anyhow::Error> for EntryValueParseError {
     }
 }
 
+/// Decodes a value of the DataLog type `ty`, the one decoder both `.wpilog` records and live
+/// NetworkTables values go through.
+///
+/// Struct types are looked up in `struct_map`, and types that aren't built in are handed to the
+/// decoder registered for them in `decoders`.
+pub fn decode_payload(
+    ty: &str,
+    data: &[u8],
+    struct_map: &HashMap<String, WpiLibStructSchema<UnresolvedWpiLibStructType>>,
+    decoders: &Decoders,
+) -> Result<EntryValue, EntryValueParseError> {
+    let (element, is_array, declared) = EntryValue::split_array_suffix(ty);
+
+    // a fixed-size element type says up front how many bytes `[N]` of them take
+    if let Some(declared) = declared
+        && let Some(size) =
+            EntryValue::primitive_datatype(element).and_then(EntryValue::datatype_size)
+        && size.checked_mul(declared.get()) != Some(data.len())
+    {
+        return Err(anyhow!(
+            "{element}[{declared}] needs {} bytes, but got {}",
+            size.saturating_mul(declared.get()),
+            data.len()
+        )
+        .into());
+    }
+
+    let value = match EntryValue::parse_from_wpilog_inner(element, is_array, data, struct_map) {
+        Err(EntryValueParseError::UnknownType(unknown)) => {
+            return match decoders.find(ty) {
+                Some(decoder) => Ok(decoder(ty, data)?),
+                None => Err(EntryValueParseError::UnknownType(unknown)),
+            };
+        }
+        value => value?,
+    };
+
+    // `[N]` is a length-N array, the same as a struct field declared with one
+    if let Some(declared) = declared {
+        let len = match &value {
+            EntryValue::ArrayArrow(a) => a.len(),
+            EntryValue::ArrayMap(m) => m.len(),
+            _ => 1,
+        };
+        if len != declared.get() {
+            return Err(anyhow!("{element}[{declared}] has {len} elements").into());
+        }
+    }
+
+    Ok(value)
+}
+
 impl EntryValue {
+    /// Narrows a `boolean` or `boolean[]` written as 4-byte `int32`s to a byte each, or `None` if
+    /// it isn't one or its length isn't a whole number of `int32`s.
+    ///
+    /// Only entries configured to have them should be narrowed, as a `boolean[]` of a multiple
+    /// of four bytes can't be told apart from one of `int32`s.
+    #[must_use]
+    pub fn narrow_int32_booleans(ty: &str, data: &[u8]) -> Option<Vec<u8>> {
+        let int32s = || {
+            data.chunks_exact(4)
+                .map(|b| u8::from(b != [0; 4]))
+                .collect::<Vec<_>>()
+        };
+
+        match ty {
+            "boolean" if data.len() == 4 => Some(int32s()),
+            "boolean[]" if data.len() % 4 == 0 => Some(int32s()),
+            _ => None,
+        }
+    }
+
+    /// Decodes a DataLog value; see [`decode_payload`].
     pub fn parse_from_wpilog(
-        mut ty: &str,
+        ty: &str,
         data: &[u8],
         struct_map: &HashMap<String, WpiLibStructSchema<UnresolvedWpiLibStructType>>,
+        decoders: &Decoders,
     ) -> Result<EntryValue, EntryValueParseError> {
-        let is_array = ty.strip_suffix("[]").map(|st| ty = st).is_some();
+        decode_payload(ty, data, struct_map, decoders)
+    }
+
+    /// Splits the array suffix off of a type, returning the element type, whether it's an array,
+    /// and the array's length if it's declared as `[N]` rather than `[]`.
+    fn split_array_suffix(ty: &str) -> (&str, bool, Option<NonZero<usize>>) {
+        let Some((element, len)) = ty.strip_suffix(']').and_then(|ty| ty.rsplit_once('[')) else {
+            return (ty, false, None);
+        };
+
+        if len.is_empty() {
+            return (element, true, None);
+        }
+
+        match len.parse() {
+            Ok(len) => (element, true, Some(len)),
+            // not an array suffix, so it'll be reported as an unknown type
+            Err(_) => (ty, false, None),
+        }
+    }
+
+    /// The Arrow type of a DataLog primitive type, without its array suffix.
+    fn primitive_datatype(ty: &str) -> Option<DataType> {
+        Some(match ty {
+            "raw" => DataType::Binary,
+            "boolean" => DataType::Boolean,
+            "int64" => DataType::Int64,
+            "float" => DataType::Float32,
+            "double" => DataType::Float64,
+            "string" => DataType::Utf8,
+            _ => return None,
+        })
+    }
+
+    fn parse_from_wpilog_inner(
+        ty: &str,
+        is_array: bool,
+        data: &[u8],
+        struct_map: &HashMap<String, WpiLibStructSchema<UnresolvedWpiLibStructType>>,
+    ) -> Result<EntryValue, EntryValueParseError> {
+        if let Some(datatype) = Self::primitive_datatype(ty) {
+            return Self::parse_datatype(data, is_array, datatype);
+        }
 
         Ok(match ty {
-            "raw" => Self::parse_datatype(data, is_array, DataType::Binary)?,
-            "boolean" => Self::parse_datatype(data, is_array, DataType::Boolean)?,
-            "int64" => Self::parse_datatype(data, is_array, DataType::Int64)?,
-            "float" => Self::parse_datatype(data, is_array, DataType::Float32)?,
-            "double" => Self::parse_datatype(data, is_array, DataType::Float64)?,
-            "string" => Self::parse_datatype(data, is_array, DataType::Utf8)?,
-            "json" => return Err(anyhow!("json not implemented").into()),
+            "json" => Self::Json(serde_json::from_slice(data).context("invalid json")?),
             "structschema" => {
                 let s = WpiLibStructSchema::parse(data)?;
 
@@ -99,21 +232,23 @@ impl EntryValue {
 
                 Self::StructSchema(s)
             }
+            "jsonschema" => Self::StructSchema(WpiLibStructSchema::from_json(
+                &serde_json::from_slice(data).context("invalid json schema")?,
+            )?),
             s => {
                 if s.starts_with("struct:") {
+                    let name = struct_type_name(s);
                     let resolved = struct_map
-                        .get(s)
-                        .ok_or_else(|| EntryValueParseError::StructNotFound(ty.into()))
+                        .get(&*name)
+                        .ok_or_else(|| EntryValueParseError::StructNotFound(name.into_owned()))
                         .and_then(|s| {
                             s.resolve(struct_map)
                                 .map_err(|s| EntryValueParseError::StructNotFound(s))
                         })?;
 
-                    dbg!(Self::parse_from_struct(data, resolved, is_array)?)
+                    Self::parse_from_struct(data, resolved, is_array)?
                 } else {
-                    return Err(
-                        anyhow!("unknown data type {ty} (data length: {})", data.len()).into(),
-                    );
+                    return Err(EntryValueParseError::UnknownType(ty.to_owned()));
                 }
             }
         })
@@ -124,12 +259,18 @@ impl EntryValue {
         is_array: bool,
         ty: DataType,
     ) -> Result<EntryValue, EntryValueParseError> {
-        if is_array {
-            // TODO: handle strings
+        if is_array && ty == DataType::Utf8 {
+            Ok(EntryValue::ArrayArrow(Self::parse_string_array(data)?))
+        } else if is_array {
             let size = Self::datatype_size(ty.clone())
                 .ok_or_else(|| anyhow!("datatype {ty} cannot be used as an array"))?;
+            if data.len() % size != 0 {
+                return Err(
+                    anyhow!("{} bytes isn't a whole number of {ty} elements", data.len()).into(),
+                );
+            }
             let array = data
-                .windows(size)
+                .chunks_exact(size)
                 .map(|d| Self::parse_datatype_single(d, ty.clone()))
                 .collect::<Result<_, _>>()?;
             Ok(EntryValue::ArrayArrow(array))
@@ -139,6 +280,38 @@ impl EntryValue {
         }
     }
 
+    /// Parses a `string[]`: a 4-byte count, then each string as a 4-byte length and its UTF-8
+    /// bytes.
+    fn parse_string_array(mut data: &[u8]) -> Result<Vec<ArrayRef>, EntryValueParseError> {
+        let count = u32::from_le_bytes(Self::fixed_size(data, "string[]")?);
+        data = &data[4..];
+
+        (0..count)
+            .map(|_| {
+                let (rest, string) = Self::parse_prefixed_string(data)?;
+                data = rest;
+                Ok(string)
+            })
+            .collect()
+    }
+
+    /// Reads a `u32` length followed by that many bytes of UTF-8, returning the bytes after it.
+    fn parse_prefixed_string(data: &[u8]) -> Result<(&[u8], ArrayRef), EntryValueParseError> {
+        let len = u32::from_le_bytes(Self::fixed_size(data, "string")?) as usize;
+        let data = &data[4..];
+
+        let string = data.get(..len).ok_or(EntryValueParseError::Truncated {
+            ty: "string",
+            need: len,
+            got: data.len(),
+        })?;
+
+        Ok((
+            &data[len..],
+            Self::parse_datatype_single(string, DataType::Utf8)?,
+        ))
+    }
+
     // Returns the size of the datatype in the datalog spec.
     //
     // A return value of `None` indicates that the datatype is variable-sized, and cannot be used
@@ -146,74 +319,144 @@ impl EntryValue {
     fn datatype_size(ty: DataType) -> Option<usize> {
         match ty {
             DataType::Binary | DataType::Utf8 => None,
-            DataType::Boolean => Some(1),
-            DataType::Float32 => Some(4),
-            DataType::Int64 | DataType::Float64 => Some(8),
+            DataType::Boolean | DataType::Int8 | DataType::UInt8 => Some(1),
+            DataType::Int16 | DataType::UInt16 => Some(2),
+            DataType::Int32 | DataType::UInt32 | DataType::Float32 => Some(4),
+            DataType::Int64 | DataType::UInt64 | DataType::Float64 => Some(8),
             _ => None,
         }
     }
 
-    fn parse_datatype_single(data: &[u8], ty: DataType) -> Result<ArrayRef, anyhow::Error> {
+    fn parse_datatype_single(data: &[u8], ty: DataType) -> Result<ArrayRef, EntryValueParseError> {
         Ok(match ty {
             // the raw data
             DataType::Binary => Arc::new(BinaryArray::from_iter_values([data])),
             // single byte (0=false, 1=true)
             DataType::Boolean => {
-                Arc::new(BooleanArray::from_iter(data.first().map(|&b| Some(b != 0))))
+                let [b] = Self::fixed_size(data, "boolean")?;
+                Arc::new(BooleanArray::from(vec![b != 0]))
             }
             // 8-byte (64-bit) signed value
-            DataType::Int64 => data
-                .get(0..8)
-                .with_context(|| anyhow!("not enough data for int64"))?
-                .try_into()
-                .map(|b| Arc::new(Int64Array::from_iter_values([i64::from_le_bytes(b)])))?,
+            DataType::Int64 => Arc::new(Int64Array::from_iter_values([i64::from_le_bytes(
+                Self::fixed_size(data, "int64")?,
+            )])),
+            // the narrower and unsigned integers only appear as struct fields
+            DataType::Int8 => Arc::new(Int8Array::from_iter_values([i8::from_le_bytes(
+                Self::fixed_size(data, "int8")?,
+            )])),
+            DataType::Int16 => Arc::new(Int16Array::from_iter_values([i16::from_le_bytes(
+                Self::fixed_size(data, "int16")?,
+            )])),
+            DataType::Int32 => Arc::new(Int32Array::from_iter_values([i32::from_le_bytes(
+                Self::fixed_size(data, "int32")?,
+            )])),
+            DataType::UInt8 => Arc::new(UInt8Array::from_iter_values([u8::from_le_bytes(
+                Self::fixed_size(data, "uint8")?,
+            )])),
+            DataType::UInt16 => Arc::new(UInt16Array::from_iter_values([u16::from_le_bytes(
+                Self::fixed_size(data, "uint16")?,
+            )])),
+            DataType::UInt32 => Arc::new(UInt32Array::from_iter_values([u32::from_le_bytes(
+                Self::fixed_size(data, "uint32")?,
+            )])),
+            DataType::UInt64 => Arc::new(UInt64Array::from_iter_values([u64::from_le_bytes(
+                Self::fixed_size(data, "uint64")?,
+            )])),
             // 4-byte (32-bit) IEEE-754 value
-            DataType::Float32 => data
-                .get(0..4)
-                .with_context(|| anyhow!("not enough data for float"))?
-                .try_into()
-                .map(|b| Arc::new(Float32Array::from_iter_values([f32::from_le_bytes(b)])))?,
+            DataType::Float32 => Arc::new(Float32Array::from_iter_values([f32::from_le_bytes(
+                Self::fixed_size(data, "float")?,
+            )])),
             // 8-byte (64-bit) IEEE-754 value
-            DataType::Float64 => data
-                .get(0..8)
-                .with_context(|| anyhow!("not enough data for double"))?
-                .try_into()
-                .map(|b| Arc::new(Float64Array::from_iter_values([f64::from_le_bytes(b)])))?,
-            // UTF-8 encoded string data
-            DataType::Utf8 => Arc::new(StringArray::from_iter_values([String::from_utf8_lossy(
-                data,
+            DataType::Float64 => Arc::new(Float64Array::from_iter_values([f64::from_le_bytes(
+                Self::fixed_size(data, "double")?,
             )])),
-            _ => bail!("unsupported datatype"),
+            // UTF-8 encoded string data
+            DataType::Utf8 => {
+                Arc::new(StringArray::from_iter_values([std::str::from_utf8(data)
+                    .map_err(|_| {
+                    EntryValueParseError::InvalidUtf8
+                })?]))
+            }
+            _ => return Err(anyhow!("unsupported datatype {ty}").into()),
         })
     }
 
+    /// Unpacks `len` booleans from `bytes`, least significant bit of the first byte first.
+    fn unpack_booleans(bytes: &[u8], len: usize) -> ArrayRef {
+        // the padding past the last element should be zero, or the schema has the length wrong
+        if bytes
+            .last()
+            .is_some_and(|last| len % 8 != 0 && last >> (len % 8) != 0)
+        {
+            re_log::warn_once!("packed bool array has bits set past its {len} elements");
+        }
+
+        Arc::new(BooleanArray::from(
+            (0..len)
+                .map(|i| (bytes[i / 8] >> (i % 8)) & 1 != 0)
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    /// Reads the leading `N` bytes of a fixed-size value of type `ty`.
+    fn fixed_size<const N: usize>(
+        data: &[u8],
+        ty: &'static str,
+    ) -> Result<[u8; N], EntryValueParseError> {
+        data.get(..N)
+            .and_then(|b| b.try_into().ok())
+            .ok_or(EntryValueParseError::Truncated {
+                ty,
+                need: N,
+                got: data.len(),
+            })
+    }
+
     fn parse_from_struct(
         data: &[u8],
         schema: WpiLibStructSchema<WpiLibStructType>,
         is_array: bool,
     ) -> Result<EntryValue, anyhow::Error> {
         let value = if is_array {
-            re_log::warn!(
-                "parsing array value of {} bytes. schema size: {}. {} instances.",
-                data.len(),
-                schema.size(),
-                data.len() as f32 / schema.size() as f32
-            );
-            EntryValue::ArrayMap(
-                data.windows(schema.size())
-                    .map(|d| {
-                        let (data, this) = Self::parse_from_struct_single(d, &schema)?;
+            let Some(size) = schema.size() else {
+                // variable length structs can't be split up front, so decode them one after another
+                let mut data = data;
+                let mut structs = Vec::new();
+                while !data.is_empty() {
+                    let (rest, map) = Self::parse_from_struct_single(data, &schema)?;
+                    if rest.len() == data.len() {
+                        bail!("struct array element consumed no bytes");
+                    }
+                    data = rest;
+                    structs.push(map);
+                }
 
-                        dbg!(&this);
-                        debug_assert_eq!(data.len(), 0);
+                return Ok(EntryValue::ArrayMap(structs));
+            };
+            if size == 0 || data.len() % size != 0 {
+                bail!(
+                    "struct array of {} bytes isn't a whole number of {size} byte structs",
+                    data.len()
+                );
+            }
 
-                        Ok::<_, anyhow::Error>(this)
-                    })
-                    .collect::<Result<Vec<_>, _>>()
-                    .unwrap(),
+            EntryValue::ArrayMap(
+                data.chunks_exact(size)
+                    .map(|d| Ok(Self::parse_from_struct_single(d, &schema)?.1))
+                    .collect::<Result<Vec<_>, anyhow::Error>>()?,
             )
         } else {
-            EntryValue::Map(Self::parse_from_struct_single(data, &schema)?.1)
+            let (rest, map) = Self::parse_from_struct_single(data, &schema)?;
+            // usually data logged before the struct's schema changed
+            if !rest.is_empty() {
+                re_log::warn_once!(
+                    "struct payload has {} bytes left over after its {} byte schema, which may not match the data",
+                    rest.len(),
+                    data.len() - rest.len()
+                );
+            }
+
+            EntryValue::Map(map)
         };
 
         Ok(value)
@@ -228,13 +471,15 @@ impl EntryValue {
         for (name, field) in &schema.fields {
             let this = match &field.ty {
                 WpiLibStructType::Primitive(p) => {
-                    let (new_data, this) = Self::parse_from_primitive(data, field, p)?;
+                    let (new_data, this) = Self::parse_from_primitive(data, field, p)
+                        .map_err(|e| anyhow!("field `{name}`: {e}"))?;
                     data = new_data;
 
                     this
                 }
                 WpiLibStructType::Custom(s) => {
-                    let (new_data, this) = Self::parse_from_struct_single(data, &s)?;
+                    let (new_data, this) = Self::parse_from_struct_single(data, &s)
+                        .map_err(|e| anyhow!("field `{name}`: {e}"))?;
                     data = new_data;
 
                     EntryValue::Map(this)
@@ -251,12 +496,420 @@ impl EntryValue {
         field: &WpiLibStructData<WpiLibStructType>,
         ty: &WpiLibStructPrimitives,
     ) -> Result<(&'d [u8], EntryValue), anyhow::Error> {
-        let (data, value) = nom::bytes::complete::take::<_, _, ()>(
-            ty.size() * field.count.map_or(1, NonZero::get),
-        )(data)?;
+        // a corrupt count can ask for more bytes than could possibly be there
+        let count = field.count.map_or(1, NonZero::get);
+
+        let Some(size) = ty.size() else {
+            // variable length, so each element says how long it is
+            let mut data = data;
+            let mut strings = Vec::new();
+            for _ in 0..count {
+                let (rest, string) = Self::parse_prefixed_string(data)?;
+                data = rest;
+                strings.push(string);
+            }
+
+            let value = if field.count.is_some() {
+                EntryValue::ArrayArrow(strings)
+            } else {
+                EntryValue::Arrow(strings.remove(0))
+            };
+            return Ok((data, value));
+        };
+        if field.value == WpiLibStructValues::Packed {
+            let len = count.div_ceil(8);
+            if len > data.len() {
+                bail!(
+                    "{count} element packed field doesn't fit in the {} remaining bytes",
+                    data.len()
+                );
+            }
+            let (packed, data) = data.split_at(len);
+            let bits = Self::unpack_booleans(packed, count);
+
+            return Ok((
+                data,
+                EntryValue::ArrayArrow((0..count).map(|i| bits.slice(i, 1)).collect()),
+            ));
+        }
+
+        let len = size
+            .checked_mul(count)
+            .filter(|len| *len <= data.len())
+            .ok_or_else(|| {
+                anyhow!(
+                    "{count} element {ty:?} field doesn't fit in the {} remaining bytes",
+                    data.len()
+                )
+            })?;
+        let (value, data) = (&data[..len], &data[len..]);
+
+        // `char[N]` is a fixed-length, NUL-padded string rather than an array of characters
+        if *ty == WpiLibStructPrimitives::Char {
+            let string = std::str::from_utf8(value)
+                .map_err(|_| EntryValueParseError::InvalidUtf8)?
+                .trim_end_matches('\0');
+
+            return Ok((
+                data,
+                EntryValue::Arrow(Arc::new(StringArray::from_iter_values([string]))),
+            ));
+        }
 
         let value = Self::parse_datatype(value, field.count.is_some(), ty.datatype())?;
 
         Ok((data, value))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hashbrown::HashMap;
+    use rerun::external::arrow::{
+        array::AsArray as _,
+        datatypes::{
+            Float64Type, Int8Type, Int16Type, Int32Type, Int64Type, UInt8Type, UInt16Type,
+            UInt32Type, UInt64Type,
+        },
+    };
+
+    use super::{
+        EntryValue, EntryValueParseError, decoders::Decoders, parse::wpistruct::WpiLibStructSchema,
+    };
+
+    fn decode(ty: &str, data: &[u8]) -> EntryValue {
+        EntryValue::parse_from_wpilog(ty, data, &HashMap::new(), &Decoders::default()).unwrap()
+    }
+
+    #[test]
+    fn scalar_and_length_one_array() {
+        let data = 1.5f64.to_le_bytes();
+
+        assert!(matches!(decode("double", &data), EntryValue::Arrow(_)));
+        assert!(matches!(decode("double[]", &data), EntryValue::ArrayArrow(a) if a.len() == 1));
+        assert!(matches!(decode("double[1]", &data), EntryValue::ArrayArrow(a) if a.len() == 1));
+
+        let two = [1.0f64.to_le_bytes(), 2.0f64.to_le_bytes()].concat();
+        assert!(matches!(decode("double[2]", &two), EntryValue::ArrayArrow(a) if a.len() == 2));
+        assert!(
+            EntryValue::parse_from_wpilog(
+                "double[2]",
+                &data,
+                &HashMap::new(),
+                &Decoders::default()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn declared_array_length() {
+        let four = [1.0f64, 2.0, 3.0, 4.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        assert!(matches!(decode("double[]", &four), EntryValue::ArrayArrow(a) if a.len() == 4));
+        assert!(matches!(decode("double[4]", &four), EntryValue::ArrayArrow(a) if a.len() == 4));
+
+        for data in [&four[..24], &[four.clone(), four.clone()].concat()] {
+            let err = EntryValue::parse_from_wpilog(
+                "double[4]",
+                data,
+                &HashMap::new(),
+                &Decoders::default(),
+            )
+            .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("double[4] needs 32 bytes, but got {}", data.len())
+            );
+        }
+    }
+
+    #[test]
+    fn struct_scalar_and_length_one_array() {
+        let schema = WpiLibStructSchema::parse(b"double a;double b[1]")
+            .unwrap()
+            .resolve(&HashMap::new())
+            .unwrap();
+        let data = [1.0f64.to_le_bytes(), 2.0f64.to_le_bytes()].concat();
+
+        let EntryValue::Map(map) = EntryValue::parse_from_struct(&data, schema, false).unwrap()
+        else {
+            panic!("struct didn't decode to a map");
+        };
+
+        assert!(matches!(map["a"], EntryValue::Arrow(_)));
+        assert!(matches!(&map["b"], EntryValue::ArrayArrow(a) if a.len() == 1));
+    }
+
+    #[test]
+    fn packed_booleans() {
+        let schema = WpiLibStructSchema::parse(b"packed bool flags[8];int8 after")
+            .unwrap()
+            .resolve(&HashMap::new())
+            .unwrap();
+        assert_eq!(schema.size(), Some(2));
+
+        let EntryValue::Map(map) =
+            EntryValue::parse_from_struct(&[0b1010_0101, 7], schema, false).unwrap()
+        else {
+            panic!("struct didn't decode to a map");
+        };
+
+        let EntryValue::ArrayArrow(flags) = &map["flags"] else {
+            panic!("flags didn't decode to an array");
+        };
+        assert_eq!(
+            flags
+                .iter()
+                .map(|flag| flag.as_boolean().value(0))
+                .collect::<Vec<_>>(),
+            [true, false, true, false, false, true, false, true]
+        );
+        let EntryValue::Arrow(after) = &map["after"] else {
+            panic!("after didn't decode to a value");
+        };
+        assert_eq!(after.as_primitive::<Int8Type>().value(0), 7);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let data = br#"{"path":[{"x":1.5,"y":-2}],"reversed":false,"name":"left"}"#;
+
+        let EntryValue::Json(json) =
+            EntryValue::parse_from_wpilog("json", data, &HashMap::new(), &Decoders::default())
+                .unwrap()
+        else {
+            panic!("json entry didn't decode to a json value");
+        };
+
+        assert_eq!(
+            json,
+            serde_json::from_slice::<serde_json::Value>(data).unwrap()
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json.to_string()).unwrap(),
+            json
+        );
+    }
+
+    #[test]
+    fn misaligned_struct_array() {
+        let schema = WpiLibStructSchema::parse(b"double x;double y")
+            .unwrap()
+            .resolve(&HashMap::new())
+            .unwrap();
+
+        let aligned = [0u8; 32];
+        assert!(
+            matches!(EntryValue::parse_from_struct(&aligned, schema.clone(), true).unwrap(), EntryValue::ArrayMap(m) if m.len() == 2)
+        );
+
+        let err = EntryValue::parse_from_struct(&[0u8; 20], schema, true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "struct array of 20 bytes isn't a whole number of 16 byte structs"
+        );
+    }
+
+    #[test]
+    fn structured_errors() {
+        let parse = |ty, data| {
+            EntryValue::parse_from_wpilog(ty, data, &HashMap::new(), &Decoders::default())
+        };
+
+        assert!(matches!(
+            parse("double", &[0; 3]),
+            Err(EntryValueParseError::Truncated {
+                ty: "double",
+                need: 8,
+                got: 3
+            })
+        ));
+        assert!(matches!(
+            parse("boolean", &[]),
+            Err(EntryValueParseError::Truncated {
+                ty: "boolean",
+                need: 1,
+                got: 0
+            })
+        ));
+        assert!(matches!(
+            parse("quaternion", &[]),
+            Err(EntryValueParseError::UnknownType(ty)) if ty == "quaternion"
+        ));
+        assert!(matches!(
+            parse("string", &[0xff, 0xfe]),
+            Err(EntryValueParseError::InvalidUtf8)
+        ));
+        assert!(matches!(
+            parse("struct:Missing", &[]),
+            Err(EntryValueParseError::StructNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn fixed_length_char_array() {
+        let schema = WpiLibStructSchema::parse(b"char name[4];int32 id")
+            .unwrap()
+            .resolve(&HashMap::new())
+            .unwrap();
+        let data = [&b"ab\0\0"[..], &7i32.to_le_bytes()].concat();
+
+        let EntryValue::Map(map) = EntryValue::parse_from_struct(&data, schema, false).unwrap()
+        else {
+            panic!("struct didn't decode to a map");
+        };
+
+        let EntryValue::Arrow(name) = &map["name"] else {
+            panic!("char[4] didn't decode to a single string");
+        };
+        assert_eq!(name.as_string::<i32>().value(0), "ab");
+
+        let EntryValue::Arrow(id) = &map["id"] else {
+            panic!("int32 didn't decode to a single value");
+        };
+        assert_eq!(id.as_primitive::<Int32Type>().value(0), 7);
+
+        // a top-level string stays variable-length
+        let EntryValue::Arrow(string) = EntryValue::parse_from_wpilog(
+            "string",
+            b"abc\0",
+            &HashMap::new(),
+            &Decoders::default(),
+        )
+        .unwrap() else {
+            panic!("string didn't decode to a single value");
+        };
+        assert_eq!(string.as_string::<i32>().value(0), "abc\0");
+    }
+
+    #[test]
+    fn integer_field_widths() {
+        let schema = WpiLibStructSchema::parse(
+            b"int8 a;int16 b;int32 c;int64 d;uint8 e;uint16 f;uint32 g;uint64 h",
+        )
+        .unwrap()
+        .resolve(&HashMap::new())
+        .unwrap();
+        let data = [
+            &(-8i8).to_le_bytes()[..],
+            &(-1_600i16).to_le_bytes(),
+            &(-320_000i32).to_le_bytes(),
+            &(-6_400_000_000i64).to_le_bytes(),
+            &u8::MAX.to_le_bytes(),
+            &0xBEEFu16.to_le_bytes(),
+            &0xDEAD_BEEFu32.to_le_bytes(),
+            &u64::MAX.to_le_bytes(),
+        ]
+        .concat();
+
+        let EntryValue::Map(map) = EntryValue::parse_from_struct(&data, schema, false).unwrap()
+        else {
+            panic!("struct didn't decode to a map");
+        };
+        let field = |name: &str| {
+            let EntryValue::Arrow(array) = &map[name] else {
+                panic!("{name} didn't decode to a single value");
+            };
+            array.clone()
+        };
+
+        assert_eq!(field("a").as_primitive::<Int8Type>().value(0), -8);
+        assert_eq!(field("b").as_primitive::<Int16Type>().value(0), -1_600);
+        assert_eq!(field("c").as_primitive::<Int32Type>().value(0), -320_000);
+        assert_eq!(
+            field("d").as_primitive::<Int64Type>().value(0),
+            -6_400_000_000
+        );
+        assert_eq!(field("e").as_primitive::<UInt8Type>().value(0), u8::MAX);
+        assert_eq!(field("f").as_primitive::<UInt16Type>().value(0), 0xBEEF);
+        assert_eq!(
+            field("g").as_primitive::<UInt32Type>().value(0),
+            0xDEAD_BEEF
+        );
+        assert_eq!(field("h").as_primitive::<UInt64Type>().value(0), u64::MAX);
+    }
+
+    #[test]
+    fn absurd_field_count() {
+        let schema = WpiLibStructSchema::parse(b"double x[9223372036854775807]")
+            .unwrap()
+            .resolve(&HashMap::new())
+            .unwrap();
+
+        let err = EntryValue::parse_from_struct(&[0; 16], schema.clone(), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "field `x`: 9223372036854775807 element Double field doesn't fit in the 16 remaining bytes"
+        );
+        assert!(EntryValue::parse_from_struct(&[0; 16], schema, true).is_err());
+    }
+
+    #[test]
+    fn struct_payload_size_mismatch() {
+        let schema = WpiLibStructSchema::parse(b"double x;double y")
+            .unwrap()
+            .resolve(&HashMap::new())
+            .unwrap();
+        let data = [1.0f64.to_le_bytes(), 2.0f64.to_le_bytes()].concat();
+
+        // extra bytes, like a field since removed from the schema, are left over with a warning
+        let long = [&data[..], &3.0f64.to_le_bytes()].concat();
+        let EntryValue::Map(map) =
+            EntryValue::parse_from_struct(&long, schema.clone(), false).unwrap()
+        else {
+            panic!("struct didn't decode to a map");
+        };
+        let EntryValue::Arrow(y) = &map["y"] else {
+            panic!("y didn't decode to a value");
+        };
+        assert!((y.as_primitive::<Float64Type>().value(0) - 2.0).abs() < f64::EPSILON);
+
+        // too few bytes say which field they ran out in
+        let err = EntryValue::parse_from_struct(&data[..12], schema, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "field `y`: 1 element Double field doesn't fit in the 4 remaining bytes"
+        );
+    }
+
+    #[test]
+    fn variable_length_struct_array() {
+        let schema = WpiLibStructSchema::parse(b"int32 id; string name; double value")
+            .unwrap()
+            .resolve(&HashMap::new())
+            .unwrap();
+
+        let mut data = Vec::new();
+        for (id, name, value) in [(1i32, "intake", 0.5f64), (2, "", 1.5)] {
+            data.extend(id.to_le_bytes());
+            data.extend(u32::try_from(name.len()).unwrap().to_le_bytes());
+            data.extend(name.as_bytes());
+            data.extend(value.to_le_bytes());
+        }
+
+        let EntryValue::ArrayMap(structs) =
+            EntryValue::parse_from_struct(&data, schema, true).unwrap()
+        else {
+            panic!("struct array didn't decode to maps");
+        };
+        assert_eq!(structs.len(), 2);
+
+        let field = |i: usize, name: &str| {
+            let EntryValue::Arrow(array) = &structs[i][name] else {
+                panic!("{name} isn't a single value");
+            };
+            array.clone()
+        };
+        assert_eq!(field(0, "id").as_primitive::<Int32Type>().value(0), 1);
+        assert_eq!(field(0, "name").as_string::<i32>().value(0), "intake");
+        assert_eq!(field(1, "name").as_string::<i32>().value(0), "");
+        assert_eq!(
+            field(1, "value").as_primitive::<Float64Type>().values()[..],
+            [1.5]
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use std::{
     borrow::Cow,
     num::{NonZero, NonZeroUsize},
+    ops::RangeInclusive,
 };
 
 use hashbrown::HashMap;
+use indexmap::IndexMap;
 use nom::{
     IResult, Parser,
     branch::alt,
@@ -21,6 +23,7 @@ use rerun::external::{
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum WpiLibStructPrimitives {
     Bool,
     Char,
@@ -34,6 +37,9 @@ pub enum WpiLibStructPrimitives {
     Uint64,
     Float,
     Double,
+    /// Not part of the WPILib struct format, but used by some custom schemas: a `u32` length
+    /// followed by that many bytes of UTF-8, so the field has no fixed size.
+    String,
 }
 impl TryFrom<&str> for WpiLibStructPrimitives {
     type Error = ();
@@ -52,23 +58,46 @@ impl TryFrom<&str> for WpiLibStructPrimitives {
             "uint64" => Self::Uint64,
             "float" | "float32" => Self::Float,
             "double" | "float64" => Self::Double,
+            "string" => Self::String,
             _ => return Err(()),
         })
     }
 }
 impl WpiLibStructPrimitives {
-    pub fn size(self) -> usize {
+    /// The size of the type in bytes, or `None` if it's variable length.
+    pub fn size(self) -> Option<usize> {
         use WpiLibStructPrimitives::*;
-        match self {
+        Some(match self {
             Bool | Char | Int8 | Uint8 => 1,
             Int16 | Uint16 => 2,
             Int32 | Uint32 | Float => 4,
             Int64 | Uint64 | Double => 8,
-        }
+            String => return None,
+        })
+    }
+}
+
+impl WpiLibStructPrimitives {
+    /// The range of values an integer type can hold, or `None` for non-integer types.
+    #[must_use]
+    pub const fn int_range(self) -> Option<RangeInclusive<i64>> {
+        Some(match self {
+            Self::Int8 => i8::MIN as i64..=i8::MAX as i64,
+            Self::Int16 => i16::MIN as i64..=i16::MAX as i64,
+            Self::Int32 => i32::MIN as i64..=i32::MAX as i64,
+            Self::Int64 => i64::MIN..=i64::MAX,
+            Self::Uint8 => 0..=u8::MAX as i64,
+            Self::Uint16 => 0..=u16::MAX as i64,
+            Self::Uint32 => 0..=u32::MAX as i64,
+            // enum values are parsed as `i64`, so none can go past its maximum anyway
+            Self::Uint64 => 0..=i64::MAX,
+            Self::Bool | Self::Char | Self::Float | Self::Double | Self::String => return None,
+        })
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnresolvedWpiLibStructType {
     Primitive(WpiLibStructPrimitives),
     Custom(String),
@@ -91,6 +120,7 @@ impl WpiLibStructPrimitives {
             Self::Uint64 => DataType::UInt64,
             Self::Float => DataType::Float32,
             Self::Double => DataType::Float64,
+            Self::String => DataType::Utf8,
         }
     }
 }
@@ -103,6 +133,7 @@ impl<'a> From<Cow<'a, str>> for UnresolvedWpiLibStructType {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum WpiLibStructType {
     Primitive(WpiLibStructPrimitives),
     Custom(WpiLibStructSchema<WpiLibStructType>),
@@ -116,7 +147,7 @@ impl WpiLibStructType {
         }
     }
 
-    pub fn size(&self) -> usize {
+    pub fn size(&self) -> Option<usize> {
         match self {
             Self::Primitive(p) => p.size(),
             Self::Custom(s) => s.size(),
@@ -125,12 +156,17 @@ impl WpiLibStructType {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum WpiLibStructValues {
     Value,
     Enum(HashMap<String, i64>),
+    /// A `bool` array declared `packed`, one bit per element, least significant bit first, rather
+    /// than a byte each.
+    Packed,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct WpiLibStructData<ValueType> {
     /// A Some value dictates that this is an array
     pub count: Option<NonZeroUsize>,
@@ -139,14 +175,27 @@ pub struct WpiLibStructData<ValueType> {
 }
 
 impl WpiLibStructData<WpiLibStructType> {
-    pub fn size(&self) -> usize {
-        self.ty.size() * self.count.map_or(1, NonZero::get)
+    /// The size of this field in bytes, accounting for array fields, or `None` if it's variable
+    /// length.
+    pub fn size(&self) -> Option<usize> {
+        if self.value == WpiLibStructValues::Packed {
+            return Some(self.count.map_or(1, NonZero::get).div_ceil(8));
+        }
+
+        // saturates rather than overflowing for a corrupt count, which then can't match any data
+        Some(
+            self.ty
+                .size()?
+                .saturating_mul(self.count.map_or(1, NonZero::get)),
+        )
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct WpiLibStructSchema<ValueType> {
-    pub fields: HashMap<String, WpiLibStructData<ValueType>>,
+    /// In declaration order, which is the order they're laid out in.
+    pub fields: IndexMap<String, WpiLibStructData<ValueType>>,
 }
 
 impl WpiLibStructSchema<WpiLibStructType> {
@@ -165,8 +214,12 @@ impl WpiLibStructSchema<WpiLibStructType> {
     }
 
     #[must_use]
-    pub fn size(&self) -> usize {
-        self.fields.iter().map(|(_, data)| data.size()).sum()
+    /// The packed size of the struct in bytes: the sum of its fields, with nested structs sized
+    /// recursively. `None` if any field is variable length.
+    pub fn size(&self) -> Option<usize> {
+        self.fields.iter().try_fold(0usize, |size, (_, data)| {
+            Some(size.saturating_add(data.size()?))
+        })
     }
 }
 
@@ -178,36 +231,32 @@ pub fn identifier(input: &[u8]) -> IResult<&[u8], &[u8]> {
     .parse(input)
 }
 
+/// A type name, which may be qualified with a `.`-separated namespace, e.g. `frc.Pose2d`.
+pub fn type_name(input: &[u8]) -> IResult<&[u8], &[u8]> {
+    recognize(pair(identifier, many0_count(pair(tag("."), identifier)))).parse(input)
+}
+
 fn struct_parser(
     data: &[u8],
 ) -> IResult<&[u8], (String, WpiLibStructData<UnresolvedWpiLibStructType>)> {
-    println!("struct parsing");
-    dbg!(String::from_utf8_lossy(data));
-
     let (data, wpienum) = enum_parser(data)
         .map(|(d, h)| (d, Some(h)))
         .unwrap_or((data, None));
 
     let (data, _) = multispace0(data)?;
 
-    dbg!(String::from_utf8_lossy(data));
-
-    let (data, typename) = identifier(data)?;
+    let (data, packed) = (tag::<_, _, NomErr<_>>("packed"), multispace1)
+        .parse(data)
+        .map_or((data, false), |(data, _)| (data, true));
 
-    dbg!(String::from_utf8_lossy(typename));
+    let (data, typename) = type_name(data)?;
 
     let (data, _) = multispace1(data)?;
 
-    dbg!(String::from_utf8_lossy(data));
-
-    let (data, identifier_name) = identifier(data).unwrap();
-
-    dbg!(String::from_utf8_lossy(identifier_name));
+    let (data, identifier_name) = identifier(data)?;
 
     let (data, _) = multispace0::<_, nom::error::Error<_>>(data)?;
 
-    dbg!(String::from_utf8_lossy(data));
-
     let (data, count) = delimited(
         tag("["),
         (
@@ -223,13 +272,20 @@ fn struct_parser(
     .parse(data)
     .unwrap_or((data, None));
 
-    dbg!(count);
-
     let name = String::from_utf8_lossy(identifier_name).into_owned();
     let ty = UnresolvedWpiLibStructType::from(String::from_utf8_lossy(typename));
 
     let wpistruct = if let Some(wenum) = wpienum {
         WpiLibStructValues::Enum(wenum)
+    } else if packed {
+        if ty == UnresolvedWpiLibStructType::Primitive(WpiLibStructPrimitives::Bool)
+            && count.is_some()
+        {
+            WpiLibStructValues::Packed
+        } else {
+            re_log::warn!("field {name} is declared packed, but only bool arrays can be");
+            WpiLibStructValues::Value
+        }
     } else {
         WpiLibStructValues::Value
     };
@@ -248,8 +304,6 @@ fn struct_parser(
 }
 
 fn enum_parser(data: &[u8]) -> IResult<&[u8], HashMap<String, i64>> {
-    println!("enum parsing");
-
     let mut values = HashMap::new();
 
     let (data, _) = tag::<_, _, NomErr<_>>("enum")
@@ -260,8 +314,6 @@ fn enum_parser(data: &[u8]) -> IResult<&[u8], HashMap<String, i64>> {
 
     let (mut data, _) = tag("{")(data)?;
 
-    dbg!(String::from_utf8_lossy(data));
-
     loop {
         let (new_data, _) = multispace0::<_, NomErr<_>>(data).unwrap_or((data, &[]));
 
@@ -270,13 +322,9 @@ fn enum_parser(data: &[u8]) -> IResult<&[u8], HashMap<String, i64>> {
             break;
         }
 
-        dbg!(String::from_utf8_lossy(new_data));
-
         let (new_data, identifier) = identifier(new_data)?;
         let identifier = String::from_utf8_lossy(identifier).into_owned();
 
-        dbg!(&identifier);
-
         let (new_data, _) = multispace0(new_data)?;
 
         let (new_data, _) = tag("=")(new_data)?;
@@ -285,28 +333,24 @@ fn enum_parser(data: &[u8]) -> IResult<&[u8], HashMap<String, i64>> {
 
         let (new_data, value) = nom::character::complete::i64(new_data)?;
 
-        dbg!(value);
-
         let (new_data, _) = multispace0(new_data)?;
 
         values.insert(identifier, value);
 
-        let (new_data, _) = tag::<_, _, NomErr<_>>(";")(new_data).unwrap_or((new_data, &[]));
-
-        dbg!(String::from_utf8_lossy(new_data));
+        // values are separated by commas, but be lenient and accept semicolons too
+        let (new_data, _) = alt((tag::<_, _, NomErr<_>>(","), tag(";")))
+            .parse(new_data)
+            .unwrap_or((new_data, &[]));
 
         data = new_data;
     }
 
-    dbg!(&values);
-    dbg!(String::from_utf8_lossy(data));
-
     Ok((data, values))
 }
 
 impl WpiLibStructSchema<UnresolvedWpiLibStructType> {
     pub fn parse(mut data: &[u8]) -> Result<Self, anyhow::Error> {
-        let mut fields = HashMap::new();
+        let mut fields = IndexMap::new();
 
         loop {
             data = match multispace0::<_, nom::error::Error<_>>(data) {
@@ -314,13 +358,12 @@ impl WpiLibStructSchema<UnresolvedWpiLibStructType> {
                 Err(_) => data,
             };
 
-            println!("Parsing data: {data:?}");
-
             let Ok((remaining, (name, inner))) = struct_parser(data) else {
                 break;
             };
 
             fields.insert(name, inner);
+            data = remaining;
 
             if let Ok((remaining, _)) = tag::<_, _, nom::error::Error<_>>(";")(remaining) {
                 data = remaining;
@@ -329,9 +372,87 @@ impl WpiLibStructSchema<UnresolvedWpiLibStructType> {
             }
         }
 
+        let (data, _) = multispace0::<_, nom::error::Error<_>>(data).unwrap_or((data, &[]));
+        if !data.is_empty() {
+            anyhow::bail!(
+                "malformed struct schema field: {:?}",
+                String::from_utf8_lossy(data)
+            );
+        }
+
+        Ok(Self { fields })
+    }
+
+    /// Converts a JSON schema, an object mapping each field name to its type, such as
+    /// `{"x":"double","modules":"SwerveModuleState[4]"}`.
+    ///
+    /// Fields that aren't a type name are skipped with a warning, leaving a partial schema.
+    pub fn from_json(schema: &serde_json::Value) -> Result<Self, anyhow::Error> {
+        let Some(schema) = schema.as_object() else {
+            anyhow::bail!("json schema should be an object of field types, got {schema}");
+        };
+
+        let mut fields = IndexMap::new();
+        for (name, ty) in schema {
+            let Some(ty) = ty.as_str() else {
+                re_log::warn!("json schema field {name} has unsupported type {ty}, skipping it");
+                continue;
+            };
+
+            let (ty, count) = match ty.strip_suffix(']').and_then(|ty| ty.split_once('[')) {
+                Some((ty, count)) => match count.trim().parse::<NonZeroUsize>() {
+                    Ok(count) => (ty, Some(count)),
+                    Err(_) => {
+                        re_log::warn!(
+                            "json schema field {name} has invalid array length {count}, skipping it"
+                        );
+                        continue;
+                    }
+                },
+                None => (ty, None),
+            };
+
+            fields.insert(
+                name.clone(),
+                WpiLibStructData {
+                    count,
+                    value: WpiLibStructValues::Value,
+                    ty: Cow::Borrowed(ty.trim()).into(),
+                },
+            );
+        }
+
         Ok(Self { fields })
     }
 
+    /// Finds enum values that don't fit in their field's integer type, as `(field, label, value)`.
+    ///
+    /// These usually mean the schema doesn't match the data, and will misrender labels.
+    #[must_use]
+    pub fn out_of_range_enum_values(&self) -> Vec<(&str, &str, i64)> {
+        let mut out_of_range = self
+            .fields
+            .iter()
+            .filter_map(|(name, data)| match (&data.value, &data.ty) {
+                (WpiLibStructValues::Enum(values), UnresolvedWpiLibStructType::Primitive(p)) => {
+                    Some((name, values, p.int_range()?))
+                }
+                _ => None,
+            })
+            .flat_map(|(name, values, range)| {
+                values
+                    .iter()
+                    .filter(move |(_, value)| !range.contains(*value))
+                    .map(move |(label, value)| (name.as_str(), label.as_str(), *value))
+            })
+            .collect::<Vec<_>>();
+        out_of_range.sort_unstable();
+
+        out_of_range
+    }
+
+    /// Resolves every custom field type against `struct_map`, or returns the (normalized) name
+    /// of the first struct that isn't registered.
     pub fn resolve(
         &self,
         struct_map: &HashMap<String, Self>,
@@ -351,10 +472,14 @@ impl WpiLibStructSchema<UnresolvedWpiLibStructType> {
                                     WpiLibStructType::Primitive(p)
                                 }
                                 UnresolvedWpiLibStructType::Custom(ref s) => {
+                                    // maps built by hand may key schemas by their bare names
+                                    let name = struct_type_name(s);
+                                    let bare = name.strip_prefix("struct:").unwrap_or(&name);
                                     WpiLibStructType::Custom(
                                         struct_map
-                                            .get(s)
-                                            .ok_or_else(|| s.clone())?
+                                            .get(&*name)
+                                            .or_else(|| struct_map.get(bare))
+                                            .ok_or_else(|| name.clone().into_owned())?
                                             .resolve(struct_map)?,
                                     )
                                 }
@@ -362,11 +487,25 @@ impl WpiLibStructSchema<UnresolvedWpiLibStructType> {
                         },
                     ))
                 })
-                .collect::<Result<HashMap<_, _>, _>>()?,
+                .collect::<Result<IndexMap<_, _>, _>>()?,
         })
     }
 }
 
+/// The name a struct schema is registered under, with the `struct:` prefix that schema texts
+/// usually leave off their field types, and without any namespace (`frc.Pose2d` is `Pose2d`).
+#[must_use]
+pub fn struct_type_name(name: &str) -> Cow<'_, str> {
+    let bare = name.strip_prefix("struct:").unwrap_or(name);
+    let unqualified = bare.rsplit('.').next().unwrap_or(bare);
+
+    if unqualified.len() + "struct:".len() == name.len() {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!("struct:{unqualified}"))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::num::NonZeroUsize;
@@ -377,6 +516,7 @@ mod test {
 
     use super::WpiLibStructSchema;
     use hashbrown::HashMap;
+    use indexmap::IndexMap;
 
     #[test]
     fn basic_struct() {
@@ -386,7 +526,7 @@ mod test {
 
         assert_eq!(
             wpistruct.fields,
-            HashMap::from([(
+            IndexMap::from([(
                 "value".to_string(),
                 WpiLibStructData {
                     count: None,
@@ -405,7 +545,7 @@ mod test {
 
         assert_eq!(
             wpistruct.fields,
-            HashMap::from([(
+            IndexMap::from([(
                 "arr".to_string(),
                 WpiLibStructData {
                     count: NonZeroUsize::new(4),
@@ -424,7 +564,7 @@ mod test {
 
         assert_eq!(
             wpistruct.fields,
-            HashMap::from([(
+            IndexMap::from([(
                 "val".to_string(),
                 WpiLibStructData {
                     count: None,
@@ -443,7 +583,7 @@ mod test {
 
         assert_eq!(
             wpistruct.fields,
-            HashMap::from([
+            IndexMap::from([
                 (
                     "something".to_string(),
                     WpiLibStructData {
@@ -474,4 +614,172 @@ mod test {
             ])
         );
     }
+
+    #[test]
+    fn flat_size() {
+        let schema = WpiLibStructSchema::parse(b"bool a; int32 b; double c").unwrap();
+
+        assert_eq!(
+            schema.resolve(&HashMap::new()).unwrap().size(),
+            Some(1 + 4 + 8)
+        );
+    }
+
+    #[test]
+    fn array_size() {
+        let schema = WpiLibStructSchema::parse(b"double x[3]").unwrap();
+
+        assert_eq!(schema.resolve(&HashMap::new()).unwrap().size(), Some(3 * 8));
+    }
+
+    #[test]
+    fn nested_size() {
+        let inner = WpiLibStructSchema::parse(b"double x; double y").unwrap();
+        let struct_map = HashMap::from([("Translation2d".to_string(), inner)]);
+
+        let outer =
+            WpiLibStructSchema::parse(b"Translation2d translation; int16 id; uint8 flags[2]")
+                .unwrap()
+                .resolve(&struct_map)
+                .unwrap();
+
+        assert_eq!(outer.size(), Some(2 * 8 + 2 + 2));
+
+        let named = WpiLibStructSchema::parse(b"Translation2d translation; string name")
+            .unwrap()
+            .resolve(&struct_map)
+            .unwrap();
+        assert_eq!(named.size(), None);
+    }
+
+    #[test]
+    fn resolves_bare_or_prefixed_names() {
+        let inner = || WpiLibStructSchema::parse(b"double x; double y").unwrap();
+        let outer = WpiLibStructSchema::parse(b"Translation2d a; frc.Translation2d b").unwrap();
+
+        for key in ["Translation2d", "struct:Translation2d"] {
+            let struct_map = HashMap::from([(key.to_owned(), inner())]);
+            assert_eq!(
+                outer.resolve(&struct_map).unwrap().size(),
+                Some(4 * 8),
+                "{key}"
+            );
+        }
+        assert_eq!(
+            outer.resolve(&HashMap::new()).unwrap_err(),
+            "struct:Translation2d"
+        );
+    }
+
+    #[test]
+    fn enum_out_of_range() {
+        let schema = WpiLibStructSchema::parse(
+            b"enum { ok = 1, big = 300, neg = -1 } uint8 state; int16 other",
+        )
+        .unwrap();
+
+        assert_eq!(
+            schema.out_of_range_enum_values(),
+            vec![("state", "big", 300), ("state", "neg", -1)]
+        );
+    }
+
+    #[test]
+    fn enum_range_bounds() {
+        let schema = WpiLibStructSchema::parse(
+            b"enum { low = -128, high = 127, over = 128 } int8 a; \
+              enum { top = 255, under = -1 } uint8 b; \
+              enum { max = 9223372036854775807, negative = -1 } uint64 c",
+        )
+        .unwrap();
+
+        // the ends of each type's range still fit
+        assert_eq!(
+            schema.out_of_range_enum_values(),
+            vec![
+                ("a", "over", 128),
+                ("b", "under", -1),
+                ("c", "negative", -1)
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_struct() {
+        // a type with no name following it
+        assert!(WpiLibStructSchema::parse(b"int32").is_err());
+        assert!(WpiLibStructSchema::parse(b"int32 ").is_err());
+        assert!(WpiLibStructSchema::parse(b"double x; int32 ").is_err());
+    }
+
+    #[test]
+    fn json_schema() {
+        let schema = serde_json::json!({
+            "x": "double",
+            "modules": "SwerveModuleState[4]",
+            "nested": { "unsupported": true },
+        });
+
+        let wpistruct = WpiLibStructSchema::from_json(&schema).unwrap();
+
+        assert_eq!(
+            wpistruct.fields,
+            IndexMap::from([
+                (
+                    "x".to_string(),
+                    WpiLibStructData {
+                        count: None,
+                        value: WpiLibStructValues::Value,
+                        ty: UnresolvedWpiLibStructType::Primitive(WpiLibStructPrimitives::Double)
+                    }
+                ),
+                (
+                    "modules".to_string(),
+                    WpiLibStructData {
+                        count: NonZeroUsize::new(4),
+                        value: WpiLibStructValues::Value,
+                        ty: UnresolvedWpiLibStructType::Custom("SwerveModuleState".to_string())
+                    }
+                ),
+            ])
+        );
+
+        assert!(WpiLibStructSchema::from_json(&serde_json::json!(["double"])).is_err());
+    }
+
+    #[test]
+    fn json_schema_declaration_order() {
+        let schema = serde_json::from_str(r#"{"y": "double", "x": "double"}"#).unwrap();
+        let wpistruct = WpiLibStructSchema::from_json(&schema).unwrap();
+
+        // the fields are decoded in this order, so it has to be the declared one
+        assert_eq!(wpistruct.fields.keys().collect::<Vec<_>>(), ["y", "x"]);
+    }
+
+    #[test]
+    fn qualified_type_names() {
+        let outer = WpiLibStructSchema::parse(b"frc.Pose2d pose;double t").unwrap();
+        assert_eq!(
+            outer.fields["pose"].ty,
+            UnresolvedWpiLibStructType::Custom("frc.Pose2d".to_string())
+        );
+
+        // registered by its unqualified name, as WPILib does
+        let struct_map = HashMap::from([
+            (
+                "struct:Pose2d".to_string(),
+                WpiLibStructSchema::parse(b"double x;double y").unwrap(),
+            ),
+            ("struct:Outer".to_string(), outer.clone()),
+        ]);
+        let resolved = outer.resolve(&struct_map).unwrap();
+        assert_eq!(resolved.size(), Some(24));
+
+        assert_eq!(super::struct_type_name("frc.Pose2d"), "struct:Pose2d");
+        assert_eq!(
+            super::struct_type_name("struct:frc.Pose2d"),
+            "struct:Pose2d"
+        );
+        assert_eq!(super::struct_type_name("struct:Pose2d"), "struct:Pose2d");
+    }
 }
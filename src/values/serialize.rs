@@ -0,0 +1,223 @@
+//! A serde-friendly mirror of [`EntryValue`], whose Arrow arrays can't derive serde directly.
+
+use std::sync::Arc;
+
+use hashbrown::HashMap;
+use rerun::external::{
+    anyhow::{self, bail},
+    arrow::{
+        array::{
+            Array, ArrayRef, AsArray as _, BinaryArray, BooleanArray, Float32Array, Float64Array,
+            Int8Array, Int16Array, Int32Array, Int64Array, StringArray, UInt8Array, UInt16Array,
+            UInt32Array, UInt64Array,
+        },
+        datatypes::{
+            DataType, Float32Type, Float64Type, Int8Type, Int16Type, Int32Type, Int64Type,
+            UInt8Type, UInt16Type, UInt32Type, UInt64Type,
+        },
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    EntryValue,
+    parse::wpistruct::{UnresolvedWpiLibStructType, WpiLibStructSchema},
+};
+
+/// The values of an Arrow array of one of the types the decoder produces.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "values", rename_all = "snake_case")]
+pub enum ArrowValues {
+    Boolean(Vec<Option<bool>>),
+    Int8(Vec<Option<i8>>),
+    Int16(Vec<Option<i16>>),
+    Int32(Vec<Option<i32>>),
+    Int64(Vec<Option<i64>>),
+    UInt8(Vec<Option<u8>>),
+    UInt16(Vec<Option<u16>>),
+    UInt32(Vec<Option<u32>>),
+    UInt64(Vec<Option<u64>>),
+    Float32(Vec<Option<f32>>),
+    Float64(Vec<Option<f64>>),
+    Utf8(Vec<Option<String>>),
+    Binary(Vec<Option<Vec<u8>>>),
+}
+
+impl TryFrom<&dyn Array> for ArrowValues {
+    type Error = anyhow::Error;
+
+    fn try_from(array: &dyn Array) -> Result<Self, Self::Error> {
+        Ok(match array.data_type() {
+            DataType::Boolean => Self::Boolean(array.as_boolean().iter().collect()),
+            DataType::Int8 => Self::Int8(array.as_primitive::<Int8Type>().iter().collect()),
+            DataType::Int16 => Self::Int16(array.as_primitive::<Int16Type>().iter().collect()),
+            DataType::Int32 => Self::Int32(array.as_primitive::<Int32Type>().iter().collect()),
+            DataType::Int64 => Self::Int64(array.as_primitive::<Int64Type>().iter().collect()),
+            DataType::UInt8 => Self::UInt8(array.as_primitive::<UInt8Type>().iter().collect()),
+            DataType::UInt16 => Self::UInt16(array.as_primitive::<UInt16Type>().iter().collect()),
+            DataType::UInt32 => Self::UInt32(array.as_primitive::<UInt32Type>().iter().collect()),
+            DataType::UInt64 => Self::UInt64(array.as_primitive::<UInt64Type>().iter().collect()),
+            DataType::Float32 => {
+                Self::Float32(array.as_primitive::<Float32Type>().iter().collect())
+            }
+            DataType::Float64 => {
+                Self::Float64(array.as_primitive::<Float64Type>().iter().collect())
+            }
+            DataType::Utf8 => Self::Utf8(
+                array
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|s| s.map(str::to_owned))
+                    .collect(),
+            ),
+            DataType::Binary => Self::Binary(
+                array
+                    .as_binary::<i32>()
+                    .iter()
+                    .map(|b| b.map(<[u8]>::to_vec))
+                    .collect(),
+            ),
+            ty => bail!("can't serialize arrays of type {ty}"),
+        })
+    }
+}
+
+impl From<ArrowValues> for ArrayRef {
+    fn from(values: ArrowValues) -> Self {
+        match values {
+            ArrowValues::Boolean(v) => Arc::new(BooleanArray::from(v)),
+            ArrowValues::Int8(v) => Arc::new(Int8Array::from(v)),
+            ArrowValues::Int16(v) => Arc::new(Int16Array::from(v)),
+            ArrowValues::Int32(v) => Arc::new(Int32Array::from(v)),
+            ArrowValues::Int64(v) => Arc::new(Int64Array::from(v)),
+            ArrowValues::UInt8(v) => Arc::new(UInt8Array::from(v)),
+            ArrowValues::UInt16(v) => Arc::new(UInt16Array::from(v)),
+            ArrowValues::UInt32(v) => Arc::new(UInt32Array::from(v)),
+            ArrowValues::UInt64(v) => Arc::new(UInt64Array::from(v)),
+            ArrowValues::Float32(v) => Arc::new(Float32Array::from(v)),
+            ArrowValues::Float64(v) => Arc::new(Float64Array::from(v)),
+            ArrowValues::Utf8(v) => Arc::new(StringArray::from_iter(v)),
+            ArrowValues::Binary(v) => Arc::new(BinaryArray::from_iter(v)),
+        }
+    }
+}
+
+/// [`EntryValue`] with its Arrow arrays replaced by [`ArrowValues`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SerializableEntryValue {
+    Arrow(ArrowValues),
+    ArrayArrow(Vec<ArrowValues>),
+    StructSchema(WpiLibStructSchema<UnresolvedWpiLibStructType>),
+
+    Map(HashMap<String, SerializableEntryValue>),
+    ArrayMap(Vec<HashMap<String, SerializableEntryValue>>),
+
+    Json(serde_json::Value),
+}
+
+fn map_to_serializable(
+    map: &HashMap<String, EntryValue>,
+) -> Result<HashMap<String, SerializableEntryValue>, anyhow::Error> {
+    map.iter()
+        .map(|(k, v)| Ok((k.clone(), SerializableEntryValue::try_from(v)?)))
+        .collect()
+}
+
+fn map_from_serializable(
+    map: HashMap<String, SerializableEntryValue>,
+) -> HashMap<String, EntryValue> {
+    map.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+impl TryFrom<&EntryValue> for SerializableEntryValue {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &EntryValue) -> Result<Self, Self::Error> {
+        Ok(match value {
+            EntryValue::Arrow(a) => Self::Arrow(ArrowValues::try_from(a.as_ref())?),
+            EntryValue::ArrayArrow(a) => Self::ArrayArrow(
+                a.iter()
+                    .map(|a| ArrowValues::try_from(a.as_ref()))
+                    .collect::<Result<_, _>>()?,
+            ),
+            EntryValue::StructSchema(s) => Self::StructSchema(s.clone()),
+            EntryValue::Map(m) => Self::Map(map_to_serializable(m)?),
+            EntryValue::ArrayMap(m) => Self::ArrayMap(
+                m.iter()
+                    .map(map_to_serializable)
+                    .collect::<Result<_, _>>()?,
+            ),
+            EntryValue::Json(json) => Self::Json(json.clone()),
+        })
+    }
+}
+
+impl From<SerializableEntryValue> for EntryValue {
+    fn from(value: SerializableEntryValue) -> Self {
+        match value {
+            SerializableEntryValue::Arrow(a) => Self::Arrow(a.into()),
+            SerializableEntryValue::ArrayArrow(a) => {
+                Self::ArrayArrow(a.into_iter().map(Into::into).collect())
+            }
+            SerializableEntryValue::StructSchema(s) => Self::StructSchema(s),
+            SerializableEntryValue::Map(m) => Self::Map(map_from_serializable(m)),
+            SerializableEntryValue::ArrayMap(m) => {
+                Self::ArrayMap(m.into_iter().map(map_from_serializable).collect())
+            }
+            SerializableEntryValue::Json(json) => Self::Json(json),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use hashbrown::HashMap;
+    use rerun::external::arrow::array::{Float64Array, StringArray, UInt8Array};
+
+    use super::SerializableEntryValue;
+    use crate::values::{EntryValue, parse::wpistruct::WpiLibStructSchema};
+
+    #[test]
+    fn entry_value_round_trip() {
+        let value = EntryValue::Map(HashMap::from([
+            (
+                "x".to_owned(),
+                EntryValue::Arrow(Arc::new(Float64Array::from_iter_values([1.5]))),
+            ),
+            (
+                "name".to_owned(),
+                EntryValue::Arrow(Arc::new(StringArray::from_iter_values(["robot"]))),
+            ),
+            (
+                "flags".to_owned(),
+                EntryValue::ArrayArrow(vec![
+                    Arc::new(UInt8Array::from_iter_values([1])),
+                    Arc::new(UInt8Array::from_iter_values([2])),
+                ]),
+            ),
+        ]));
+
+        let json =
+            serde_json::to_string(&SerializableEntryValue::try_from(&value).unwrap()).unwrap();
+        let back: EntryValue = serde_json::from_str::<SerializableEntryValue>(&json)
+            .unwrap()
+            .into();
+
+        assert_eq!(back, value);
+    }
+
+    #[test]
+    fn schema_round_trip() {
+        let schema =
+            WpiLibStructSchema::parse(b"enum { a = 1, b = 2 } int8 state; double x[2]").unwrap();
+
+        let json = serde_json::to_string(&schema).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<WpiLibStructSchema<_>>(&json).unwrap(),
+            schema
+        );
+    }
+}
@@ -0,0 +1,982 @@
+//! Decoding a DataLog's records into an [`EntryLog`].
+
+use hashbrown::HashMap;
+use rerun::{EntityPath, EntityPathPart, external::re_log};
+
+use super::{
+    parse::{Payload, WpiLogFile, WpiRecord},
+    split::{PartSpan, continuous_parts, part_offsets},
+};
+use crate::{
+    config::{EarlyTimestamps, WallClockFilter, WallTime},
+    log::{Decoded, EntryLog, PayloadDecoder, Timestamp},
+    values::image::FrameFormat,
+};
+
+/// Decodes the parts of a (possibly split) log into `log`, in order.
+///
+/// Parts that fail to parse are logged and skipped.
+pub fn load_parts(parts: &[Vec<u8>], log: &mut EntryLog) {
+    let threads = log.config().threads(parts.iter().map(Vec::len).sum());
+    let parts = parse_parts(parts, threads.get());
+
+    let spans = parts
+        .iter()
+        .map(|part| PartSpan::from_records(&part.records))
+        .collect::<Vec<_>>();
+
+    let continuous = continuous_parts(&spans);
+    if continuous < parts.len() {
+        re_log::warn!(
+            "only the first {continuous} of {} parts continue one another by systemTime, the rest are from another log and are skipped",
+            parts.len()
+        );
+    }
+
+    let early_timestamps = log.config().early_timestamps;
+    let wall_clock = log.config().wall_clock;
+
+    // entry ids are only unique within a single part
+    for ((part, offset), span) in parts
+        .into_iter()
+        .zip(part_offsets(&spans))
+        .zip(&spans)
+        .take(continuous)
+    {
+        let records = handle_wall_clock(part.records, span, &wall_clock);
+
+        let records = handle_early_timestamps(records, early_timestamps);
+        let mut decoded = decode_records(&records, log, threads.get()).into_iter();
+
+        let mut ctxs = HashMap::new();
+        for mut record in records {
+            record.timestamp = Timestamp(record.timestamp.0.saturating_add(offset));
+            fill_log_decoded(&mut ctxs, log, record, decoded.next().flatten());
+        }
+    }
+
+    if let Err(e) = log.add_record_rate() {
+        re_log::warn!("failed to log the record rate: {e}");
+    }
+
+    for (key, count) in log.decode_errors() {
+        re_log::warn!("entity {key} failed to decode {count} times");
+    }
+
+    for unresolved in log.unresolved_structs() {
+        let entities = unresolved
+            .entities
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        re_log::warn!(
+            "{} never registered, {} samples dropped from {}",
+            unresolved.name,
+            unresolved.samples,
+            entities.join(", "),
+        );
+    }
+}
+
+/// Applies `policy` to the data records of a part logged at a timestamp of 0, moving them to its
+/// earliest non-zero timestamp if they're clamped. Control records are left alone, as entries need
+/// starting whenever they're logged.
+fn handle_early_timestamps(
+    records: Vec<WpiRecord<'_>>,
+    policy: EarlyTimestamps,
+) -> Vec<WpiRecord<'_>> {
+    // records aren't written in timestamp order, so the first one logged may not be the earliest
+    let Some(first) = records
+        .iter()
+        .map(|record| record.timestamp)
+        .filter(|timestamp| timestamp.0 != 0)
+        .min()
+    else {
+        return records;
+    };
+
+    records
+        .into_iter()
+        .filter_map(|mut record| {
+            if record.timestamp.0 != 0 || !matches!(record.payload, Payload::Raw { .. }) {
+                return Some(record);
+            }
+
+            match policy {
+                EarlyTimestamps::Keep => Some(record),
+                EarlyTimestamps::Clamp => {
+                    record.timestamp = first;
+                    Some(record)
+                }
+                EarlyTimestamps::Drop => None,
+            }
+        })
+        .collect()
+}
+
+/// Parses the records of each part on up to `threads` threads, keeping the parts in order.
+fn parse_parts(parts: &[Vec<u8>], threads: usize) -> Vec<WpiLogFile<'_>> {
+    let parse = |parts: &[Vec<u8>]| {
+        parts
+            .iter()
+            .filter_map(|part| match WpiLogFile::parse(part, |_| {}) {
+                Ok((_, log)) => {
+                    if log.trailing > 0 {
+                        re_log::warn!(
+                            "ignoring {} bytes at the end of the log that aren't a whole record",
+                            log.trailing
+                        );
+                    }
+                    Some(log)
+                }
+                Err(e) => {
+                    re_log::error!("WPI DataLog file error: {e}");
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    if threads <= 1 || parts.len() <= 1 {
+        return parse(parts);
+    }
+
+    std::thread::scope(|scope| {
+        let workers = parts
+            .chunks(parts.len().div_ceil(threads))
+            .map(|parts| scope.spawn(move || parse(parts)))
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    })
+}
+
+/// Decodes the payloads of `records` on up to `threads` threads ahead of them being added to
+/// `log`, one for each record, or none at all on a single thread.
+///
+/// Payloads that depend on the struct schemas logged before them, and those of filtered out
+/// entries, are left to be decoded as they're added.
+fn decode_records(
+    records: &[WpiRecord<'_>],
+    log: &EntryLog,
+    threads: usize,
+) -> Vec<Option<Decoded>> {
+    if threads <= 1 {
+        return Vec::new();
+    }
+
+    // each data record's entry as `fill_log` will find it
+    let mut entries = HashMap::new();
+    let payloads = records
+        .iter()
+        .map(|record| match record.payload {
+            Payload::Start {
+                entry_id,
+                entry_name,
+                entry_type,
+                ..
+            } => {
+                entries.insert(
+                    entry_id,
+                    (
+                        self::entry_name(entry_name),
+                        entry_path(entry_name),
+                        entry_type,
+                    ),
+                );
+                None
+            }
+            Payload::Finish { entry_id } => {
+                entries.remove(&entry_id);
+                None
+            }
+            Payload::Raw { entry_id, data } => entries
+                .get(&entry_id)
+                .filter(|(name, _, ty)| {
+                    PayloadDecoder::is_independent(ty) && log.config().filter.allows(name)
+                })
+                .map(|(_, key, ty)| (key.clone(), *ty, data)),
+            Payload::SetMetadata { .. } => None,
+        })
+        .collect::<Vec<_>>();
+
+    let decoder = log.decoder();
+    std::thread::scope(|scope| {
+        let workers = payloads
+            .chunks(payloads.len().div_ceil(threads).max(1))
+            .map(|payloads| {
+                scope.spawn(move || {
+                    payloads
+                        .iter()
+                        .map(|payload| {
+                            payload
+                                .as_ref()
+                                .map(|(key, ty, data)| decoder.decode(key, ty, data))
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    })
+}
+
+/// Applies `filter` to the data records of a single-part log, for tools that use the records as
+/// they were logged rather than decoding them into an [`EntryLog`].
+#[must_use]
+pub fn wall_clock_records<'log>(
+    records: Vec<WpiRecord<'log>>,
+    filter: &WallClockFilter,
+) -> Vec<WpiRecord<'log>> {
+    let span = PartSpan::from_records(&records);
+    handle_wall_clock(records, &span, filter)
+}
+
+/// Applies `filter` to the data records of a part, using its first `systemTime` sample to tell
+/// the wall-clock time each was logged at. Records logged before that sample have no known
+/// wall-clock time.
+fn handle_wall_clock<'log>(
+    records: Vec<WpiRecord<'log>>,
+    span: &PartSpan,
+    filter: &WallClockFilter,
+) -> Vec<WpiRecord<'log>> {
+    if !filter.is_active() {
+        return records;
+    }
+
+    if span.system_time.is_none() {
+        re_log::warn!("filtering by wall-clock time, but a log part has no systemTime entry");
+    }
+
+    records
+        .into_iter()
+        .filter(|record| {
+            if !matches!(record.payload, Payload::Raw { .. }) {
+                return true;
+            }
+
+            let time = span.system_time.and_then(|(robot, system)| {
+                let since = record.timestamp.0.checked_sub(robot.0)?;
+                Some(WallTime(system.saturating_add_unsigned(since)))
+            });
+            filter.contains(time)
+        })
+        .collect()
+}
+
+/// Stands in for an empty entry name, or an empty segment of one.
+pub const UNNAMED_ENTRY: &str = "unnamed";
+
+/// The NetworkTables topic an entry mirrors, like `/a` for `NT:/a`, or `None` for an entry the
+/// robot program logged itself.
+#[must_use]
+pub fn nt_topic(name: &str) -> Option<&str> {
+    name.strip_prefix("NT:")
+}
+
+/// Strips exactly the `NT:` prefix and one leading `/` from an entry name, so `NT:/a` and `/a`
+/// both become `a`, but `NT://a` keeps its second slash.
+#[must_use]
+pub fn entry_name(name: &str) -> &str {
+    let name = nt_topic(name).unwrap_or(name);
+    name.strip_prefix('/').unwrap_or(name)
+}
+
+/// The entity an entry is logged to, one segment per `/`-separated part of its stripped name.
+#[must_use]
+pub fn entry_path(name: &str) -> EntityPath {
+    EntityPath::new(
+        entry_name(name)
+            .split('/')
+            .map(|part| EntityPathPart::from(if part.is_empty() { UNNAMED_ENTRY } else { part }))
+            .collect(),
+    )
+}
+
+/// A started entry, valid until its id is finished.
+pub struct EntryContext<'log> {
+    ty: &'log str,
+    name: &'log str,
+    metadata: &'log str,
+    start: Timestamp,
+}
+
+fn handle_data(
+    ty: &str,
+    timestamp: Timestamp,
+    key: EntityPath,
+    data: &[u8],
+    logger: &mut EntryLog,
+    decoded: Option<Decoded>,
+) {
+    let added = match decoded {
+        Some(decoded) => logger.add_decoded(key.clone(), timestamp, ty, data, decoded),
+        None => logger.add_entry(key.clone(), timestamp, ty, data),
+    };
+    if let Err(e) = added {
+        // only the first failure is logged, the rest are summarized once loading finishes
+        if logger.record_decode_error(&key) == 1 {
+            re_log::warn!(
+                "handle_data: Failed to parse entry type {ty} (data length: {}) (key: {key}): {e:#}",
+                data.len(),
+            );
+        }
+    }
+}
+
+/// Applies a single record to the log, tracking entry lifetimes in `ctxs`.
+pub fn fill_log<'file>(
+    ctxs: &mut HashMap<u32, EntryContext<'file>>,
+    nt_ctx: &mut EntryLog,
+    record: WpiRecord<'file>,
+) {
+    fill_log_decoded(ctxs, nt_ctx, record, None);
+}
+
+/// Applies a single record to the log like [`fill_log`], with its payload already decoded by
+/// [`decode_records`] if it was.
+fn fill_log_decoded<'file>(
+    ctxs: &mut HashMap<u32, EntryContext<'file>>,
+    nt_ctx: &mut EntryLog,
+    record: WpiRecord<'file>,
+    decoded: Option<Decoded>,
+) {
+    nt_ctx.count_record(record.timestamp);
+
+    match record.payload {
+        Payload::Start {
+            entry_id,
+            entry_name,
+            entry_type,
+            entry_metadata,
+        } => {
+            let entry_name = self::entry_name(entry_name);
+
+            // reconnecting NT clients republish every topic, which changes nothing
+            if ctxs.get(&entry_id).is_some_and(|ctx| {
+                ctx.name == entry_name && ctx.ty == entry_type && ctx.metadata == entry_metadata
+            }) {
+                return;
+            }
+
+            let key = entry_path(entry_name);
+            if entry_type == "raw"
+                && let Some(format) = FrameFormat::from_metadata(entry_metadata)
+            {
+                nt_ctx.set_frame_format(key.clone(), format);
+            }
+            if !entry_metadata.is_empty() {
+                nt_ctx.set_metadata(key, entry_metadata);
+            }
+
+            let previous = ctxs.insert(
+                entry_id,
+                EntryContext {
+                    ty: entry_type,
+                    name: entry_name,
+                    metadata: entry_metadata,
+                    start: record.timestamp,
+                },
+            );
+
+            // a reused id should have been finished first, so end the old entry here instead
+            if let Some(previous) = previous {
+                re_log::warn!(
+                    "entry id {entry_id} restarted as {entry_name} while {} (started at {:.3}s) was active",
+                    previous.name,
+                    previous.start.as_secs_f64(),
+                );
+                nt_ctx.clear_entry(entry_path(previous.name), record.timestamp);
+            }
+        }
+        Payload::Raw { entry_id, data } => {
+            let Some(ctx) = ctxs.get(&entry_id) else {
+                re_log::warn!("Data for entry id {entry_id}, which isn't started or was finished");
+                return;
+            };
+
+            // schemas are needed by the entries that use them, whatever they're named
+            if !matches!(ctx.ty, "structschema" | "jsonschema")
+                && !nt_ctx.config().filter.allows(ctx.name)
+            {
+                return;
+            }
+
+            let key = entry_path(ctx.name);
+
+            handle_data(ctx.ty, record.timestamp, key, data, nt_ctx, decoded);
+        }
+        Payload::Finish { entry_id } => {
+            let Some(ctx) = ctxs.remove(&entry_id) else {
+                re_log::warn!("Finish for unknown entry id {entry_id}");
+                return;
+            };
+
+            let key = entry_path(ctx.name);
+
+            nt_ctx.clear_entry(key, record.timestamp);
+        }
+        Payload::SetMetadata {
+            entry_id,
+            entry_metadata,
+        } => {
+            let Some(ctx) = ctxs.get_mut(&entry_id) else {
+                re_log::warn!("SetMetadata for unknown entry id {entry_id}");
+                return;
+            };
+
+            ctx.metadata = entry_metadata;
+            nt_ctx.set_metadata(entry_path(ctx.name), entry_metadata);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroUsize, path::Path};
+
+    use hashbrown::HashMap;
+    use rerun::{
+        ApplicationId, EntityPath, EntityPathPart, StoreId, StoreKind, Timeline,
+        external::arrow::{array::AsArray as _, datatypes::Float64Type},
+    };
+
+    use crate::{
+        config::{
+            Config, EarlyTimestamps, EntityPattern, EntryFilter, UnknownWallClock, WallClockFilter,
+        },
+        log::{EntryLog, Timestamp},
+        wpilog::{
+            parse::{Payload, WpiRecord},
+            split::PartSpan,
+        },
+    };
+
+    #[test]
+    fn decode_errors_are_counted() {
+        let mut log = EntryLog::new();
+        let key = EntityPath::from_single_string("config");
+
+        for timestamp in [1_000, 2_000] {
+            super::handle_data(
+                "json",
+                Timestamp(timestamp),
+                key.clone(),
+                b"{",
+                &mut log,
+                None,
+            );
+        }
+        super::handle_data(
+            "double",
+            Timestamp(3_000),
+            EntityPath::from_single_string("ok"),
+            &1.0f64.to_le_bytes(),
+            &mut log,
+            None,
+        );
+
+        assert_eq!(log.decode_errors().collect::<Vec<_>>(), [(&key, 2)]);
+    }
+
+    #[test]
+    fn filtered_entries_are_skipped() {
+        let pattern = |p: &str| EntityPattern(p.to_owned());
+        let mut log = EntryLog::with_config(Config {
+            filter: EntryFilter {
+                include: vec![pattern("drive/**")],
+                exclude: vec![pattern("**/debug/**")],
+            },
+            ..Config::default()
+        });
+
+        let translation = [1.0f64.to_le_bytes(), 2.0f64.to_le_bytes()].concat();
+        let entries: [(&str, &str, &[u8]); 4] = [
+            (
+                ".schema/struct:Translation2d",
+                "structschema",
+                b"double x;double y",
+            ),
+            (
+                "NT:/drive/translation",
+                "struct:Translation2d",
+                &translation,
+            ),
+            ("NT:/drive/debug/current", "double", &3.0f64.to_le_bytes()),
+            ("NT:/arm/angle", "double", &4.0f64.to_le_bytes()),
+        ];
+
+        let mut ctxs = HashMap::new();
+        for (entry_id, (entry_name, entry_type, data)) in (0..).zip(entries) {
+            for payload in [
+                Payload::Start {
+                    entry_id,
+                    entry_name,
+                    entry_type,
+                    entry_metadata: "",
+                },
+                Payload::Raw { entry_id, data },
+            ] {
+                super::fill_log(
+                    &mut ctxs,
+                    &mut log,
+                    WpiRecord {
+                        timestamp: Timestamp(1_000),
+                        payload,
+                    },
+                );
+            }
+        }
+
+        let path = |p: &str| EntityPath::from_file_path(Path::new(p));
+        assert!(log.get_entry(&path("drive/translation/x")).is_some());
+        assert!(log.get_entry(&path("drive/debug/current")).is_none());
+        assert!(log.get_entry(&path("arm/angle")).is_none());
+    }
+
+    #[test]
+    fn malformed_schema_doesnt_stop_loading() {
+        let mut log = EntryLog::new();
+
+        let translation = [1.0f64.to_le_bytes(), 2.0f64.to_le_bytes()].concat();
+        let entries: [(&str, &str, &[u8]); 4] = [
+            (
+                ".schema/struct:Translation2d",
+                "structschema",
+                b"\xff\xfe double x;;;",
+            ),
+            (".schema/struct:Config", "jsonschema", b"{not json"),
+            (
+                "NT:/drive/translation",
+                "struct:Translation2d",
+                &translation,
+            ),
+            ("NT:/arm/angle", "double", &4.0f64.to_le_bytes()),
+        ];
+
+        let mut ctxs = HashMap::new();
+        for (entry_id, (entry_name, entry_type, data)) in (0..).zip(entries) {
+            for payload in [
+                Payload::Start {
+                    entry_id,
+                    entry_name,
+                    entry_type,
+                    entry_metadata: "",
+                },
+                Payload::Raw { entry_id, data },
+            ] {
+                super::fill_log(
+                    &mut ctxs,
+                    &mut log,
+                    WpiRecord {
+                        timestamp: Timestamp(1_000),
+                        payload,
+                    },
+                );
+            }
+        }
+
+        assert!(log.structs().is_empty());
+        assert!(
+            log.get_entry(&EntityPath::from_file_path(Path::new("arm/angle")))
+                .is_some()
+        );
+        let unresolved = log.unresolved_structs();
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].name, "struct:Translation2d");
+    }
+
+    #[test]
+    fn early_timestamps() {
+        let real = 1.0f64.to_le_bytes();
+        let default = 0.0f64.to_le_bytes();
+
+        let load = |policy| {
+            let records = [
+                (0, "a"),
+                (0, "b"),
+                (1_000, "a"),
+                // the default `b` started with, logged after real data
+                (0, "b"),
+                // real data logged out of order
+                (500, "a"),
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (timestamp, name))| WpiRecord {
+                timestamp: Timestamp(timestamp),
+                payload: if i < 2 {
+                    Payload::Start {
+                        entry_id: if name == "a" { 1 } else { 2 },
+                        entry_name: name,
+                        entry_type: "double",
+                        entry_metadata: "",
+                    }
+                } else {
+                    Payload::Raw {
+                        entry_id: if name == "a" { 1 } else { 2 },
+                        data: if name == "a" { &real } else { &default },
+                    }
+                },
+            })
+            .collect::<Vec<_>>();
+
+            let mut log = EntryLog::new();
+            let mut ctxs = HashMap::new();
+            for record in super::handle_early_timestamps(records, policy) {
+                super::fill_log(&mut ctxs, &mut log, record);
+            }
+
+            let keys = |name| {
+                log.get_entry(&EntityPath::from_single_string(name))
+                    .map(|entry| entry.keys().copied().collect::<Vec<_>>())
+            };
+            (keys("a"), keys("b"))
+        };
+
+        let a = Some(vec![Timestamp(500), Timestamp(1_000)]);
+        assert_eq!(
+            load(EarlyTimestamps::Keep),
+            (a.clone(), Some(vec![Timestamp(0)]))
+        );
+        assert_eq!(
+            load(EarlyTimestamps::Clamp),
+            (a.clone(), Some(vec![Timestamp(500)]))
+        );
+        assert_eq!(load(EarlyTimestamps::Drop), (a, None));
+    }
+
+    #[test]
+    fn structs_decode_as_added() {
+        let log = EntryLog::new();
+        let translation = [1.0f64.to_le_bytes(), 2.0f64.to_le_bytes()].concat();
+        let entries: [(&str, &str, &[u8]); 3] = [
+            (
+                ".schema/struct:Translation2d",
+                "structschema",
+                b"double x;double y",
+            ),
+            ("NT:/translation", "struct:Translation2d", &translation),
+            ("NT:/speed", "double", &3.0f64.to_le_bytes()),
+        ];
+
+        let records = (0..)
+            .zip(entries)
+            .flat_map(|(entry_id, (entry_name, entry_type, data))| {
+                [
+                    Payload::Start {
+                        entry_id,
+                        entry_name,
+                        entry_type,
+                        entry_metadata: "",
+                    },
+                    Payload::Raw { entry_id, data },
+                ]
+            })
+            .map(|payload| WpiRecord {
+                timestamp: Timestamp(1_000),
+                payload,
+            })
+            .collect::<Vec<_>>();
+
+        assert!(super::decode_records(&records, &log, 1).is_empty());
+
+        // only the double doesn't need the schema logged before it
+        let decoded = super::decode_records(&records, &log, 2);
+        assert_eq!(
+            decoded.iter().map(Option::is_some).collect::<Vec<_>>(),
+            [false, false, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn threads_dont_change_output() {
+        // a single log of many entries, so its records are decoded across threads
+        let parts = [include_bytes!("../../test_data/FRC_TBD_d225b5377c70a88d.wpilog").to_vec()];
+
+        let convert = |threads| {
+            let mut log = EntryLog::with_config(Config {
+                threads: NonZeroUsize::new(threads),
+                deterministic: true,
+                ..Config::default()
+            });
+            super::load_parts(&parts, &mut log);
+
+            let chunks = crate::conv::log_changes_to_chunks(
+                &StoreId::random(StoreKind::Recording),
+                &ApplicationId::random(),
+                Timeline::new_duration("robotime"),
+                &mut log,
+            );
+            crate::conv::renumbered(chunks, &mut log)
+                .iter()
+                .map(|chunk| (chunk.id(), chunk.to_arrow_msg().unwrap().batch.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let single = convert(1);
+        assert!(single.len() > 1);
+        assert_eq!(single, convert(4));
+    }
+
+    #[test]
+    fn wall_clock_filter() {
+        // 2025-03-15T18:45:00Z, a second after the robot started logging
+        let system_time = 1_742_064_300_000_000i64.to_le_bytes();
+        let value = 1.0f64.to_le_bytes();
+
+        let mut records = vec![
+            WpiRecord {
+                timestamp: Timestamp(0),
+                payload: Payload::Start {
+                    entry_id: 1,
+                    entry_name: "systemTime",
+                    entry_type: "int64",
+                    entry_metadata: "",
+                },
+            },
+            WpiRecord {
+                timestamp: Timestamp(0),
+                payload: Payload::Start {
+                    entry_id: 2,
+                    entry_name: "speed",
+                    entry_type: "double",
+                    entry_metadata: "",
+                },
+            },
+            WpiRecord {
+                timestamp: Timestamp(1_000_000),
+                payload: Payload::Raw {
+                    entry_id: 1,
+                    data: &system_time,
+                },
+            },
+        ];
+        records.extend(
+            [500_000, 1_500_000, 2_500_000, 3_500_000].map(|timestamp| WpiRecord {
+                timestamp: Timestamp(timestamp),
+                payload: Payload::Raw {
+                    entry_id: 2,
+                    data: &value,
+                },
+            }),
+        );
+        let span = PartSpan::from_records(&records);
+
+        let load = |filter: WallClockFilter| {
+            let mut log = EntryLog::new();
+            let mut ctxs = HashMap::new();
+            for record in super::handle_wall_clock(records.clone(), &span, &filter) {
+                super::fill_log(&mut ctxs, &mut log, record);
+            }
+
+            log.get_entry(&EntityPath::from_single_string("speed"))
+                .map(|speed| speed.keys().map(|t| t.0).collect::<Vec<_>>())
+                .unwrap_or_default()
+        };
+
+        let since = "2025-03-15T18:45:01Z".parse().ok();
+        let until = "2025-03-15T18:45:02Z".parse().ok();
+
+        assert_eq!(
+            load(WallClockFilter::default()),
+            [500_000, 1_500_000, 2_500_000, 3_500_000]
+        );
+        assert_eq!(
+            load(WallClockFilter {
+                since,
+                until,
+                unknown: UnknownWallClock::Keep,
+            }),
+            [500_000, 2_500_000]
+        );
+        assert_eq!(
+            load(WallClockFilter {
+                since,
+                until,
+                unknown: UnknownWallClock::Drop,
+            }),
+            [2_500_000]
+        );
+        assert_eq!(
+            load(WallClockFilter {
+                since: None,
+                until,
+                unknown: UnknownWallClock::Drop,
+            }),
+            [1_500_000, 2_500_000]
+        );
+    }
+
+    #[test]
+    fn entry_names() {
+        let path = |parts: &[&str]| {
+            EntityPath::new(parts.iter().copied().map(EntityPathPart::from).collect())
+        };
+
+        assert_eq!(super::entry_path("NT:/a"), path(&["a"]));
+        assert_eq!(super::entry_path("NT:/a/b"), path(&["a", "b"]));
+        assert_eq!(super::entry_path("NT://a"), path(&["unnamed", "a"]));
+        assert_eq!(super::entry_path("NT:"), path(&["unnamed"]));
+        assert_eq!(super::entry_path("/"), path(&["unnamed"]));
+        assert_eq!(super::entry_path("a"), path(&["a"]));
+    }
+
+    #[test]
+    fn reused_entry_id() {
+        let value = 1i64.to_le_bytes();
+        let mut log = EntryLog::new();
+        let mut ctxs = HashMap::new();
+
+        let records = [
+            Payload::Start {
+                entry_id: 1,
+                entry_name: "a",
+                entry_type: "int64",
+                entry_metadata: "",
+            },
+            Payload::Raw {
+                entry_id: 1,
+                data: &value,
+            },
+            Payload::Finish { entry_id: 1 },
+            // ignored, the entry is finished
+            Payload::Raw {
+                entry_id: 1,
+                data: &value,
+            },
+            Payload::Start {
+                entry_id: 1,
+                entry_name: "b",
+                entry_type: "int64",
+                entry_metadata: "",
+            },
+            Payload::Raw {
+                entry_id: 1,
+                data: &value,
+            },
+        ];
+        for (timestamp, payload) in (1..).zip(records) {
+            super::fill_log(
+                &mut ctxs,
+                &mut log,
+                WpiRecord {
+                    timestamp: Timestamp(timestamp * 1_000),
+                    payload,
+                },
+            );
+        }
+
+        let a = log.get_entry(&EntityPath::from_single_string("a")).unwrap();
+        assert_eq!(a.keys().copied().collect::<Vec<_>>(), [Timestamp(2_000)]);
+        let b = log.get_entry(&EntityPath::from_single_string("b")).unwrap();
+        assert_eq!(b.keys().copied().collect::<Vec<_>>(), [Timestamp(6_000)]);
+    }
+
+    #[test]
+    fn republished_start() {
+        let value = [0; 24];
+        let mut log = EntryLog::new();
+        let mut ctxs = HashMap::new();
+
+        let start = || Payload::Start {
+            entry_id: 1,
+            entry_name: "NT:/pose",
+            entry_type: "struct:Pose2d",
+            entry_metadata: r#"{"source":"NT"}"#,
+        };
+        let records = [
+            start(),
+            Payload::Raw {
+                entry_id: 1,
+                data: &value,
+            },
+            start(),
+            start(),
+            Payload::Raw {
+                entry_id: 1,
+                data: &value,
+            },
+            start(),
+        ];
+        for (timestamp, payload) in (1..).zip(records) {
+            super::fill_log(
+                &mut ctxs,
+                &mut log,
+                WpiRecord {
+                    timestamp: Timestamp(timestamp * 1_000),
+                    payload,
+                },
+            );
+        }
+
+        // the entry is still the one started first, and was never ended
+        assert_eq!(ctxs.len(), 1);
+        assert_eq!(ctxs[&1].start, Timestamp(1_000));
+        assert_eq!(log.take_cleared(), []);
+
+        // only the two values are waiting on the schema
+        let unresolved = log.unresolved_structs();
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].samples, 2);
+    }
+
+    #[test]
+    fn record_rate() {
+        let value = 1.0f64.to_le_bytes();
+        let mut log = EntryLog::with_config(Config {
+            record_rate: Some(0.5),
+            ..Config::default()
+        });
+        let mut ctxs = HashMap::new();
+
+        let start = WpiRecord {
+            timestamp: Timestamp(0),
+            payload: Payload::Start {
+                entry_id: 1,
+                entry_name: "speed",
+                entry_type: "double",
+                entry_metadata: "",
+            },
+        };
+        // 100 Hz for half a second, nothing for half a second, then 10 Hz
+        let timestamps = (0..50)
+            .map(|i| i * 10_000)
+            .chain((0..5).map(|i| 1_000_000 + i * 100_000));
+        let records = std::iter::once(start).chain(timestamps.map(|timestamp| WpiRecord {
+            timestamp: Timestamp(timestamp),
+            payload: Payload::Raw {
+                entry_id: 1,
+                data: &value,
+            },
+        }));
+        for record in records {
+            super::fill_log(&mut ctxs, &mut log, record);
+        }
+        log.add_record_rate().unwrap();
+
+        let rate = log
+            .get_entry(&crate::log::record_rate_path())
+            .unwrap()
+            .iter()
+            .map(|(timestamp, value)| (timestamp.0, value.as_primitive::<Float64Type>().value(0)))
+            .collect::<Vec<_>>();
+        // the start record is counted too
+        assert_eq!(rate, [(0, 102.0), (500_000, 0.0), (1_000_000, 10.0)]);
+    }
+}
@@ -1 +1,4 @@
+pub mod load;
 pub mod parse;
+pub mod split;
+pub mod stream;
@@ -1,11 +1,12 @@
-use nom::{
-    IResult, Parser, bytes::streaming as bstreaming, error::ErrorKind,
-    number::streaming as nstreaming,
-};
+use hashbrown::HashMap;
+use nom::{IResult, Parser, bytes::streaming as bstreaming, number::streaming as nstreaming};
+use rerun::external::re_log;
 
 use crate::log;
 
-struct RecordHeaderLengths(u8);
+/// The widths of a record's header fields, as encoded in its first byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordHeaderLengths(u8);
 impl RecordHeaderLengths {
     /// Returns the size of the entry ID field in bytes.
     pub const fn size_entry_id(&self) -> u8 {
@@ -28,7 +29,21 @@ impl From<u8> for RecordHeaderLengths {
     }
 }
 
-fn parse_string(input: &[u8], len: usize) -> IResult<&[u8], &str, ParseError> {
+/// Parses a length-prefixed string in a control record, which must fit in what's left of its
+/// payload.
+fn parse_string<'a>(
+    input: &'a [u8],
+    len: u32,
+    field: &'static str,
+) -> IResult<&'a [u8], &'a str, ParseError> {
+    if len as usize > input.len() {
+        return Err(nom::Err::Failure(ParseError::StringLengthExceedsPayload {
+            field,
+            declared: len,
+            remaining: input.len(),
+        }));
+    }
+
     let (input, string_bytes) = bstreaming::take(len)(input)?;
     let string = std::str::from_utf8(string_bytes)
         .map_err(|_| nom::Err::Failure(ParseError::InvalidString))?;
@@ -41,6 +56,21 @@ pub enum ParseError {
     InvalidVersion,
     InvalidString,
     InvalidIntegerSize,
+    /// A record declared a payload longer than the rest of the file.
+    TruncatedPayload {
+        declared: u64,
+        available: usize,
+    },
+    /// A control record's payload ended before all of its fields did.
+    TruncatedControlRecord {
+        payload_len: usize,
+    },
+    /// A control record declared a string longer than the rest of its payload.
+    StringLengthExceedsPayload {
+        field: &'static str,
+        declared: u32,
+        remaining: usize,
+    },
     EOF,
 }
 
@@ -51,6 +81,25 @@ impl std::fmt::Display for ParseError {
             Self::InvalidVersion => write!(f, "Invalid version"),
             Self::InvalidString => write!(f, "Invalid string"),
             Self::InvalidIntegerSize => write!(f, "Invalid integer size"),
+            Self::TruncatedPayload {
+                declared,
+                available,
+            } => write!(
+                f,
+                "Truncated payload: declared {declared} bytes, but only {available} remain"
+            ),
+            Self::TruncatedControlRecord { payload_len } => write!(
+                f,
+                "Truncated control record: its fields don't fit in its {payload_len} byte payload"
+            ),
+            Self::StringLengthExceedsPayload {
+                field,
+                declared,
+                remaining,
+            } => write!(
+                f,
+                "String length exceeds payload: {field} declared {declared} bytes, but only {remaining} remain"
+            ),
             Self::EOF => write!(f, "EOF"),
         }
     }
@@ -67,7 +116,10 @@ impl<T> nom::error::ParseError<T> for ParseError {
     }
 }
 
+/// Deserializing borrows from the serialized bytes, so it needs a format that can lend them out,
+/// like bincode; JSON can't for `Raw` payloads.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum Payload<'log> {
     // control records
     /// The Start control record provides information about the specified entry ID. It must appear prior to any records using that entry ID. The format of the Start control record’s payload data is as follows:
@@ -93,10 +145,24 @@ pub enum Payload<'log> {
     },
 }
 
-#[derive(Debug, Clone)]
+impl Payload<'_> {
+    /// The entry the record is about, which for control records is the entry they control.
+    #[must_use]
+    pub const fn entry_id(&self) -> u32 {
+        match self {
+            Self::Start { entry_id, .. }
+            | Self::Finish { entry_id }
+            | Self::SetMetadata { entry_id, .. }
+            | Self::Raw { entry_id, .. } => *entry_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct WpiRecord<'log> {
     pub timestamp: log::Timestamp,
-
+    #[cfg_attr(feature = "serialize", serde(borrow))]
     pub payload: Payload<'log>,
 }
 
@@ -105,11 +171,13 @@ impl<'log> WpiRecord<'log> {
     const FINISH_CONTROL_RECORD: u8 = 0x01;
     const SET_METADATA_CONTROL_RECORD: u8 = 0x02;
 
+    /// Reads a little-endian integer of `size` bytes, which a record header always gives as 1
+    /// to 8. Any other size would misalign the rest of the record, so it's an error.
     fn parse_dyn_int(input: &[u8], size: u8) -> IResult<&[u8], u64, ParseError> {
-        let (input, s) = bstreaming::take(size)(input)?;
-        if s.len() > 8 {
+        if !(1..=8).contains(&size) {
             return Err(nom::Err::Failure(ParseError::InvalidIntegerSize));
         }
+        let (input, s) = bstreaming::take(size)(input)?;
 
         let mut buf = [0; std::mem::size_of::<u64>()];
         buf[..s.len()].copy_from_slice(s);
@@ -117,6 +185,30 @@ impl<'log> WpiRecord<'log> {
         Ok((input, u64::from_le_bytes(buf)))
     }
     pub fn parse(input: &'log [u8]) -> IResult<&'log [u8], Self, ParseError> {
+        Self::parse_with_header_info(input).map(|(input, (record, _))| (input, record))
+    }
+
+    /// Parses a record, also returning how its header was encoded.
+    ///
+    /// Control records of types this doesn't know, which newer versions of the format may add,
+    /// are skipped with a warning, as are ones whose fields don't fit in their payload.
+    pub fn parse_with_header_info(
+        mut input: &'log [u8],
+    ) -> IResult<&'log [u8], (Self, RecordHeaderLengths), ParseError> {
+        loop {
+            match Self::parse_known(input)? {
+                (leftover, Some(record)) => return Ok((leftover, record)),
+                (leftover, None) => input = leftover,
+            }
+        }
+    }
+
+    /// Parses a record, or returns `None` for a control record of an unknown type or a malformed
+    /// one.
+    #[allow(clippy::type_complexity)]
+    pub(super) fn parse_known(
+        input: &'log [u8],
+    ) -> IResult<&'log [u8], Option<(Self, RecordHeaderLengths)>, ParseError> {
         let (input, lengths) = match nstreaming::u8(input) {
             Ok((input, lengths)) => (input, lengths),
             Err(nom::Err::Incomplete(_)) => {
@@ -131,81 +223,120 @@ impl<'log> WpiRecord<'log> {
         let (input, timestamp) = Self::parse_dyn_int(input, lengths.size_timestamp())?;
         let timestamp = log::Timestamp(timestamp);
 
+        // the whole file is in memory, so a payload longer than what's left can never complete
+        if payload_len > input.len() as u64 {
+            return Err(nom::Err::Failure(ParseError::TruncatedPayload {
+                declared: payload_len,
+                available: input.len(),
+            }));
+        }
+
         let (leftover, input) = bstreaming::take(payload_len)(input)?;
 
         if entry_id == 0 {
-            let (input, control_record_type) = nstreaming::u8(input)?;
-            let (input, entry_id) = nstreaming::le_u32(input)?;
-
-            match control_record_type {
-                Self::START_CONTROL_RECORD => {
-                    let (input, entry_name_len) = nstreaming::le_u32(input)?;
-                    let (input, entry_name) = parse_string(input, entry_name_len as usize)?;
-                    let (input, entry_type_len) = nstreaming::le_u32(input)?;
-                    let (input, entry_type) = parse_string(input, entry_type_len as usize)?;
-                    let (input, entry_metadata_len) = nstreaming::le_u32(input)?;
-                    let (input, entry_metadata) = parse_string(input, entry_metadata_len as usize)?;
-
-                    debug_assert!(input.is_empty(), "didn't consume all input",);
-
-                    Ok((
-                        leftover,
-                        WpiRecord {
-                            timestamp,
-                            payload: Payload::Start {
-                                entry_id,
-                                entry_name,
-                                entry_type,
-                                entry_metadata,
-                            },
-                        },
-                    ))
-                }
-                Self::FINISH_CONTROL_RECORD => {
-                    debug_assert!(input.is_empty(), "didn't consume all input");
-
-                    Ok((
-                        leftover,
-                        WpiRecord {
-                            timestamp,
-                            payload: Payload::Finish { entry_id },
+            let payload = match Self::parse_control(input, timestamp) {
+                Ok((_, payload)) => payload,
+                Err(e) => {
+                    let e = match e {
+                        // the payload was already cut to its declared length, so running out of
+                        // it means the record is malformed, not that the log ended
+                        nom::Err::Incomplete(_) => ParseError::TruncatedControlRecord {
+                            payload_len: input.len(),
                         },
-                    ))
+                        nom::Err::Error(e) | nom::Err::Failure(e) => e,
+                    };
+                    // like one of an unknown type, its payload length is known, so the rest of
+                    // the log can still be read
+                    re_log::warn!("skipping malformed control record at {}: {e}", timestamp.0);
+                    return Ok((leftover, None));
                 }
-                Self::SET_METADATA_CONTROL_RECORD => {
-                    // the spec doesn't specify whether _this_ must be valid UTF-8 or not,
-                    // but the previous one does, so let's assume it is
-                    let (input, entry_metadata_len) = nstreaming::le_u32(input)?;
-                    let (input, entry_metadata) = parse_string(input, entry_metadata_len as usize)?;
-
-                    debug_assert!(input.is_empty(), "didn't consume all input");
-
-                    Ok((
-                        leftover,
-                        WpiRecord {
-                            timestamp,
-                            payload: Payload::SetMetadata {
-                                entry_id,
-                                entry_metadata,
-                            },
-                        },
-                    ))
-                }
-                _ => Err(nom::Err::Failure(ParseError::InvalidFormat(ErrorKind::Tag))),
-            }
+            };
+
+            Ok((
+                leftover,
+                payload.map(|payload| (WpiRecord { timestamp, payload }, lengths)),
+            ))
         } else {
             Ok((
                 leftover,
-                WpiRecord {
-                    timestamp,
-                    payload: Payload::Raw {
-                        entry_id: entry_id as u32,
-                        data: input,
+                Some((
+                    WpiRecord {
+                        timestamp,
+                        payload: Payload::Raw {
+                            entry_id: entry_id as u32,
+                            data: input,
+                        },
                     },
-                },
+                    lengths,
+                )),
             ))
         }
     }
+    /// Parses a control record's payload, or returns `None` for a control record of an unknown
+    /// type.
+    fn parse_control(
+        input: &'log [u8],
+        timestamp: log::Timestamp,
+    ) -> IResult<&'log [u8], Option<Payload<'log>>, ParseError> {
+        let (input, control_record_type) = nstreaming::u8(input)?;
+        if !matches!(
+            control_record_type,
+            Self::START_CONTROL_RECORD
+                | Self::FINISH_CONTROL_RECORD
+                | Self::SET_METADATA_CONTROL_RECORD
+        ) {
+            // the payload length is known, so the rest of the log can still be read
+            re_log::warn!(
+                "skipping control record of unknown type {control_record_type:#04x} at {}",
+                timestamp.0
+            );
+            return Ok((input, None));
+        }
+        let (input, entry_id) = nstreaming::le_u32(input)?;
+
+        let (input, payload) = match control_record_type {
+            Self::START_CONTROL_RECORD => {
+                let (input, entry_name_len) = nstreaming::le_u32(input)?;
+                let (input, entry_name) = parse_string(input, entry_name_len, "entry name")?;
+                let (input, entry_type_len) = nstreaming::le_u32(input)?;
+                let (input, entry_type) = parse_string(input, entry_type_len, "entry type")?;
+                let (input, entry_metadata_len) = nstreaming::le_u32(input)?;
+                let (input, entry_metadata) =
+                    parse_string(input, entry_metadata_len, "entry metadata")?;
+
+                (
+                    input,
+                    Payload::Start {
+                        entry_id,
+                        entry_name,
+                        entry_type,
+                        entry_metadata,
+                    },
+                )
+            }
+            Self::FINISH_CONTROL_RECORD => (input, Payload::Finish { entry_id }),
+            Self::SET_METADATA_CONTROL_RECORD => {
+                // the spec doesn't specify whether _this_ must be valid UTF-8 or not,
+                // but the previous one does, so let's assume it is
+                let (input, entry_metadata_len) = nstreaming::le_u32(input)?;
+                let (input, entry_metadata) =
+                    parse_string(input, entry_metadata_len, "entry metadata")?;
+
+                (
+                    input,
+                    Payload::SetMetadata {
+                        entry_id,
+                        entry_metadata,
+                    },
+                )
+            }
+            _ => unreachable!("unknown control record types are skipped above"),
+        };
+
+        debug_assert!(input.is_empty(), "didn't consume all input");
+
+        Ok((input, Some(payload)))
+    }
 }
 
 /// A simple binary logging format designed for high speed logging of timestamped data values (e.g. numeric sensor values).
@@ -226,6 +357,9 @@ pub struct WpiLogFile<'log> {
     /// There is no timestamp ordering requirement for records. This is true for control records as well—
     /// ​a Start control record with a later timestamp may be followed by data records for that entry with earlier timestamps.
     pub records: Vec<WpiRecord<'log>>,
+    /// How many bytes after the last whole record couldn't be parsed, like the partial record a
+    /// robot that lost power mid-write leaves behind. Zero for a clean log.
+    pub trailing: usize,
 }
 
 impl<'log> WpiLogFile<'log> {
@@ -234,7 +368,9 @@ impl<'log> WpiLogFile<'log> {
         input.starts_with(b"WPILOG")
     }
 
-    fn parse_header(input: &'log [u8]) -> IResult<&'log [u8], (u16, &'log str), ParseError> {
+    pub(super) fn parse_header(
+        input: &'log [u8],
+    ) -> IResult<&'log [u8], (u16, &'log str), ParseError> {
         let (input, _) = nom::bytes::streaming::tag(&b"WPILOG"[..])(input)?;
 
         let (input, version) = nom::number::streaming::le_u16(input)?;
@@ -243,7 +379,9 @@ impl<'log> WpiLogFile<'log> {
         }
 
         let (input, header_len) = nom::number::streaming::le_u32(input)?;
-        let (input, extra_header) = parse_string(input, header_len as usize)?;
+        let (input, extra_header) = bstreaming::take(header_len)(input)?;
+        let extra_header = std::str::from_utf8(extra_header)
+            .map_err(|_| nom::Err::Failure(ParseError::InvalidString))?;
         Ok((input, (version, extra_header)))
     }
 
@@ -255,7 +393,15 @@ impl<'log> WpiLogFile<'log> {
 
         let (input, records) =
             nom::multi::many0(|input| -> IResult<&[u8], WpiRecord, ParseError> {
-                let record = WpiRecord::parse(input)?;
+                let record = match WpiRecord::parse(input) {
+                    Ok(record) => record,
+                    // a record cut off by the end of the file ends the log, rather than failing it
+                    Err(
+                        nom::Err::Incomplete(_)
+                        | nom::Err::Failure(ParseError::TruncatedPayload { .. }),
+                    ) => return Err(nom::Err::Error(ParseError::EOF)),
+                    Err(e) => return Err(e),
+                };
                 record_cb(record.1.clone());
                 Ok(record)
             })
@@ -267,15 +413,246 @@ impl<'log> WpiLogFile<'log> {
                 version,
                 extra_header,
                 records,
+                trailing: input.len(),
             },
         ))
     }
+
+    /// Groups the records by the entry they're about, control records included.
+    #[must_use]
+    pub fn by_entry(&self) -> HashMap<u32, EntryRecords<'_, 'log>> {
+        let mut entries = HashMap::<u32, EntryRecords<'_, 'log>>::new();
+
+        for record in &self.records {
+            let entry = entries.entry(record.payload.entry_id()).or_default();
+            if let Payload::Start {
+                entry_name,
+                entry_type,
+                ..
+            } = record.payload
+            {
+                entry.name.get_or_insert(entry_name);
+                entry.ty.get_or_insert(entry_type);
+            }
+            entry.records.push(record);
+        }
+
+        entries
+    }
+
+    /// What each entry id was started as, from the control records alone. An id that was
+    /// finished and started again maps to its latest entry.
+    #[must_use]
+    pub fn entries(&self) -> HashMap<u32, EntryInfo<'log>> {
+        let mut entries = HashMap::new();
+
+        for record in &self.records {
+            match record.payload {
+                Payload::Start {
+                    entry_id,
+                    entry_name,
+                    entry_type,
+                    entry_metadata,
+                } => {
+                    entries.insert(
+                        entry_id,
+                        EntryInfo {
+                            name: entry_name,
+                            ty: entry_type,
+                            metadata: entry_metadata,
+                            finished: false,
+                        },
+                    );
+                }
+                Payload::Finish { entry_id } => {
+                    if let Some(entry) = entries.get_mut(&entry_id) {
+                        entry.finished = true;
+                    }
+                }
+                Payload::SetMetadata {
+                    entry_id,
+                    entry_metadata,
+                } => {
+                    if let Some(entry) = entries.get_mut(&entry_id) {
+                        entry.metadata = entry_metadata;
+                    }
+                }
+                Payload::Raw { .. } => {}
+            }
+        }
+
+        entries
+    }
+
+    /// Previews a log without parsing all of it, reading records until `max_records` have been
+    /// read or every entry started so far has a sample.
+    pub fn sample(
+        input: &'log [u8],
+        max_records: usize,
+    ) -> IResult<&'log [u8], LogSummary<'log>, ParseError> {
+        let (mut input, (version, extra_header)) = Self::parse_header(input)?;
+
+        let mut summary = LogSummary {
+            version,
+            extra_header,
+            ..LogSummary::default()
+        };
+        // entry ids can be reused once finished, so this maps to the latest entry started with one
+        let mut ids = HashMap::new();
+
+        while summary.records < max_records {
+            let record = match WpiRecord::parse(input) {
+                Ok((remaining, record)) => {
+                    input = remaining;
+                    record
+                }
+                Err(nom::Err::Error(ParseError::EOF)) => break,
+                Err(e) => return Err(e),
+            };
+            summary.records += 1;
+
+            match record.payload {
+                Payload::Start {
+                    entry_id,
+                    entry_name,
+                    entry_type,
+                    entry_metadata,
+                } => {
+                    ids.insert(entry_id, summary.entries.len());
+                    summary.entries.push(SampledEntry {
+                        name: entry_name,
+                        ty: entry_type,
+                        metadata: entry_metadata,
+                        sample: None,
+                    });
+                }
+                Payload::Raw { entry_id, data } => {
+                    if let Some(entry) = ids.get(&entry_id).map(|&i| &mut summary.entries[i]) {
+                        entry.sample.get_or_insert((record.timestamp, data));
+                    }
+                }
+                Payload::Finish { .. } | Payload::SetMetadata { .. } => {}
+            }
+
+            if !summary.entries.is_empty() && summary.entries.iter().all(|e| e.sample.is_some()) {
+                break;
+            }
+        }
+
+        Ok((input, summary))
+    }
+}
+
+/// The records of one entry id, made by [`WpiLogFile::by_entry`].
+#[derive(Debug, Clone, Default)]
+pub struct EntryRecords<'a, 'log> {
+    /// From the first `Start` record, if the id was ever started.
+    pub name: Option<&'log str>,
+    pub ty: Option<&'log str>,
+    /// In file order.
+    pub records: Vec<&'a WpiRecord<'log>>,
+}
+
+impl<'a, 'log> EntryRecords<'a, 'log> {
+    /// The data records, leaving out the control records.
+    pub fn raw(&self) -> impl Iterator<Item = &'a WpiRecord<'log>> + '_ {
+        self.records
+            .iter()
+            .copied()
+            .filter(|record| matches!(record.payload, Payload::Raw { .. }))
+    }
+}
+
+/// An entry as started, made by [`WpiLogFile::entries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryInfo<'log> {
+    pub name: &'log str,
+    pub ty: &'log str,
+    /// As last set, by the `Start` record or a later `SetMetadata`.
+    pub metadata: &'log str,
+    /// Whether a `Finish` record ended the entry.
+    pub finished: bool,
+}
+
+/// An entry found while sampling a log, with the first value logged to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampledEntry<'log> {
+    pub name: &'log str,
+    pub ty: &'log str,
+    pub metadata: &'log str,
+    /// The raw data of the entry's first value, if a record of one was read.
+    pub sample: Option<(log::Timestamp, &'log [u8])>,
+}
+
+/// A preview of the start of a log, made by [`WpiLogFile::sample`].
+#[derive(Debug, Clone, Default)]
+pub struct LogSummary<'log> {
+    pub version: u16,
+    pub extra_header: &'log str,
+    /// In the order they were started.
+    pub entries: Vec<SampledEntry<'log>>,
+    /// How many records were read.
+    pub records: usize,
 }
 
 #[cfg(test)]
 mod tests {
     use std::num::NonZeroUsize;
 
+    #[test]
+    fn test_dyn_int_sizes() {
+        let input = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09];
+
+        assert_eq!(
+            super::WpiRecord::parse_dyn_int(&input, 1),
+            Ok((&input[1..], 0x01))
+        );
+        assert_eq!(
+            super::WpiRecord::parse_dyn_int(&input, 8),
+            Ok((&input[8..], 0x0807_0605_0403_0201))
+        );
+
+        for size in [0, 9] {
+            assert_eq!(
+                super::WpiRecord::parse_dyn_int(&input, size),
+                Err(nom::Err::Failure(super::ParseError::InvalidIntegerSize))
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_fixture() {
+        let fixture = include_bytes!("../../test_data/entities.wpilog");
+
+        // the entries are all started up front, so only the first two have a value by then
+        let (_, summary) = super::WpiLogFile::sample(fixture, 7).unwrap();
+        assert_eq!(summary.records, 7);
+        assert_eq!(
+            summary
+                .entries
+                .iter()
+                .map(|e| (e.name, e.ty, e.sample.is_some()))
+                .collect::<Vec<_>>(),
+            [
+                ("/.schema/struct:Translation3d", "structschema", true),
+                ("NT:/speed/.type", "string", true),
+                ("NT:/speed/.components", "string", false),
+                ("NT:/speed/Scalar", "double", false),
+                ("NT:/target/Point3d", "struct:Translation3d", false),
+            ]
+        );
+
+        // stops once every entry has a value, before the second speed sample
+        let (remaining, summary) = super::WpiLogFile::sample(fixture, 100).unwrap();
+        assert_eq!(summary.records, 10);
+        assert!(!remaining.is_empty());
+        let speed = &summary.entries[3];
+        assert_eq!(
+            speed.sample,
+            Some((crate::log::Timestamp(1_000_000), &1.5f64.to_le_bytes()[..]))
+        );
+    }
+
     #[test]
     fn test_example_header() {
         // 57 50 49 4c 4f 47 00 01 00 00 00 00
@@ -410,6 +787,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_example_record_header_info() {
+        let example_record = [
+            0x20, // b0010_0000 - ID length = 1 byte, payload size length = 1 byte, timestamp length = 3 bytes
+            0x01, // b0000_0001 - entry ID = 1
+            0x08, // b0000_1000 - payload size = 8 bytes
+            0x40, 0x42, 0x0f, // timestamp = 1,000,000 us
+            //
+            0x03, 0x00, 0x00, 0x00, //
+            0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let (input, (_record, lengths)) =
+            super::WpiRecord::parse_with_header_info(&example_record).unwrap();
+
+        assert_eq!(input.len(), 0);
+
+        assert_eq!(lengths.size_entry_id(), 1);
+        assert_eq!(lengths.size_payload_len(), 1);
+        assert_eq!(lengths.size_timestamp(), 3);
+    }
+
+    #[test]
+    fn test_truncated_payload() {
+        let example_record = [
+            0x20, // b0010_0000 - ID length = 1 byte, payload size length = 1 byte, timestamp length = 3 bytes
+            0x01, // b0000_0001 - entry ID = 1
+            0x08, // b0000_1000 - payload size = 8 bytes
+            0x40, 0x42, 0x0f, // timestamp = 1,000,000 us
+            //
+            0x03, 0x00, 0x00, 0x00, // only 4 of the 8 bytes
+        ];
+
+        let err = super::WpiRecord::parse(&example_record).unwrap_err();
+
+        assert_eq!(
+            err,
+            nom::Err::Failure(super::ParseError::TruncatedPayload {
+                declared: 8,
+                available: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_string_length_exceeds_payload() {
+        let example_record = [
+            0x20, // b0010_0000 - ID length = 1 byte, payload size length = 1 byte, timestamp length = 3 bytes
+            0x00, // b0000_0000 - entry ID = 0
+            0x0d, // b0000_1101 - payload size = 13 bytes
+            0x40, 0x42, 0x0f, // timestamp = 1,000,000 us
+            //
+            0x00, // b0000_0000 - control record type = Start (0)
+            //
+            0x01, 0x00, 0x00, 0x00, // entry ID 1 being started
+            //
+            0x64, 0x00, 0x00, 0x00, // length of name string = 100
+            //
+            b't', b'e', b's', b't', // only 4 bytes of name left in the payload
+        ];
+
+        // a whole log skips it with a warning, so check what the payload's parser reports
+        let err =
+            super::WpiRecord::parse_control(&example_record[6..], crate::log::Timestamp(1_000_000))
+                .unwrap_err();
+
+        assert_eq!(
+            err,
+            nom::Err::Failure(super::ParseError::StringLengthExceedsPayload {
+                field: "entry name",
+                declared: 100,
+                remaining: 4,
+            })
+        );
+    }
+
     #[test]
     fn test_start_cr() {
         // 20 (ID length = 1 byte, payload size length = 1 byte, timestamp length = 3 bytes)
@@ -552,6 +1005,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unknown_control_record() {
+        let mut file = b"WPILOG\x00\x01\x00\x00\x00\x00".to_vec();
+
+        let mut start = vec![0x00];
+        start.extend(1u32.to_le_bytes());
+        for string in ["a", "int64", ""] {
+            start.extend(u32::try_from(string.len()).unwrap().to_le_bytes());
+            start.extend(string.as_bytes());
+        }
+        let records: [(u8, &[u8]); 4] = [
+            (0, &start),
+            // a control record type from a newer version, with a payload of its own
+            (0, &[0x7f, 0x01, 0x00, 0x00, 0x00, 0xaa, 0xbb]),
+            // shorter than any known control record
+            (0, &[0x7f]),
+            (1, &7i64.to_le_bytes()),
+        ];
+        for (entry_id, payload) in records {
+            file.extend([
+                0x00, // 1 byte each for the entry id, payload size and timestamp
+                entry_id,
+                u8::try_from(payload.len()).unwrap(),
+                0x01,
+            ]);
+            file.extend(payload);
+        }
+
+        let (input, wpi_log) = super::WpiLogFile::parse(&file, |_| {}).unwrap();
+
+        assert_eq!(input.len(), 0);
+        assert_eq!(wpi_log.records.len(), 2);
+        assert!(matches!(
+            wpi_log.records[0].payload,
+            super::Payload::Start { entry_id: 1, .. }
+        ));
+        assert_eq!(
+            wpi_log.records[1].payload,
+            super::Payload::Raw {
+                entry_id: 1,
+                data: &7i64.to_le_bytes(),
+            }
+        );
+    }
+
     #[test]
     fn test_multi_record() {
         let mut file = Vec::new();
@@ -638,7 +1136,6 @@ mod tests {
                 entry_metadata: r#"{"source":"log"}"#,
             }
         );
-        dbg!(&wpi_log.records);
         assert_eq!(wpi_log.records[1].timestamp.0, 1_000_050);
         assert_eq!(
             wpi_log.records[1].payload,
@@ -652,6 +1149,178 @@ mod tests {
             wpi_log.records[2].payload,
             super::Payload::Finish { entry_id: 1 }
         );
+
+        let entries = wpi_log.by_entry();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[&1];
+        assert_eq!(entry.name, Some("rerun"));
+        assert_eq!(entry.ty, Some("int64"));
+        // the start and finish are kept alongside the data
+        assert_eq!(entry.records.len(), 3);
+        assert_eq!(entry.raw().count(), 1);
+
+        assert_eq!(
+            wpi_log.entries()[&1],
+            super::EntryInfo {
+                name: "rerun",
+                ty: "int64",
+                metadata: r#"{"source":"log"}"#,
+                finished: true,
+            }
+        );
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_payload_serializes() {
+        let payloads = [
+            (
+                super::Payload::Start {
+                    entry_id: 1,
+                    entry_name: "rerun",
+                    entry_type: "int64",
+                    entry_metadata: "",
+                },
+                serde_json::json!({ "Start": {
+                    "entry_id": 1,
+                    "entry_name": "rerun",
+                    "entry_type": "int64",
+                    "entry_metadata": "",
+                } }),
+            ),
+            (
+                super::Payload::Finish { entry_id: 1 },
+                serde_json::json!({ "Finish": { "entry_id": 1 } }),
+            ),
+            (
+                super::Payload::SetMetadata {
+                    entry_id: 1,
+                    entry_metadata: "meta",
+                },
+                serde_json::json!({ "SetMetadata": { "entry_id": 1, "entry_metadata": "meta" } }),
+            ),
+            (
+                super::Payload::Raw {
+                    entry_id: 1,
+                    data: b"ok",
+                },
+                serde_json::json!({ "Raw": { "entry_id": 1, "data": [b'o', b'k'] } }),
+            ),
+        ];
+
+        for (payload, json) in payloads {
+            assert_eq!(serde_json::to_value(&payload).unwrap(), json);
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_record_round_trips() {
+        let payloads = [
+            super::Payload::Start {
+                entry_id: 1,
+                entry_name: "rerun",
+                entry_type: "int64",
+                entry_metadata: "",
+            },
+            super::Payload::Finish { entry_id: 1 },
+            super::Payload::SetMetadata {
+                entry_id: 1,
+                entry_metadata: "meta",
+            },
+            super::Payload::Raw {
+                entry_id: 1,
+                data: b"ok",
+            },
+        ];
+
+        for payload in payloads {
+            let record = super::WpiRecord {
+                timestamp: crate::log::Timestamp(1_000_000),
+                payload,
+            };
+            let bytes = bincode::serialize(&record).unwrap();
+            assert_eq!(
+                bincode::deserialize::<super::WpiRecord>(&bytes).unwrap(),
+                record
+            );
+        }
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        let mut file = vec![
+            b'W', b'P', b'I', b'L', b'O', b'G', // magic
+            0x00, 0x01, // version 1.0
+            0x00, 0x00, 0x00, 0x00, // no extra header
+            //
+            0x20, // b0010_0000 - ID length = 1 byte, payload size length = 1 byte, timestamp length = 3 bytes
+            0x00, // b0000_0000 - entry ID = 0
+            0x05, // b0000_0101 - payload size = 5 bytes
+            0x40, 0x42, 0x0f, // timestamp = 1,000,000 us
+            //
+            0x01, // b0000_0001 - control record type = Finish (1)
+            0x01, 0x00, 0x00, 0x00, // entry ID being finished
+        ];
+
+        let (input, wpi_log) = super::WpiLogFile::parse(&file, |_| {}).unwrap();
+        assert_eq!(input.len(), 0);
+        assert_eq!(wpi_log.trailing, 0);
+
+        // the start of a record header, cut off before its timestamp
+        file.extend_from_slice(&[0x20, 0x00, 0x05]);
+
+        let (input, wpi_log) = super::WpiLogFile::parse(&file, |_| {}).unwrap();
+        assert_eq!(input.len(), 3);
+        assert_eq!(wpi_log.trailing, 3);
+        assert_eq!(wpi_log.records.len(), 1);
+    }
+
+    #[test]
+    fn truncated_control_record() {
+        let mut file = b"WPILOG\x00\x01\x00\x00\x00\x00".to_vec();
+
+        let records: [(u8, &[u8]); 3] = [
+            // a Finish record whose payload ends halfway through the entry id
+            (0, &[0x01, 0x01, 0x00]),
+            (0, &[0x01, 0x02, 0x00, 0x00, 0x00]),
+            (1, &7i64.to_le_bytes()),
+        ];
+        for (entry_id, payload) in records {
+            file.extend([
+                0x00, // 1 byte each for the entry id, payload size and timestamp
+                entry_id,
+                u8::try_from(payload.len()).unwrap(),
+                0x01,
+            ]);
+            file.extend(payload);
+        }
+
+        // it's skipped like a control record of an unknown type, rather than ending the log
+        let (input, wpi_log) = super::WpiLogFile::parse(&file, |_| {}).unwrap();
+
+        assert_eq!(input.len(), 0);
+        assert_eq!(wpi_log.trailing, 0);
+        assert_eq!(
+            wpi_log
+                .records
+                .iter()
+                .map(|record| &record.payload)
+                .collect::<Vec<_>>(),
+            [
+                &super::Payload::Finish { entry_id: 2 },
+                &super::Payload::Raw {
+                    entry_id: 1,
+                    data: &7i64.to_le_bytes(),
+                },
+            ]
+        );
+
+        // the payload's parser still says why
+        assert_eq!(
+            super::WpiRecord::parse_control(&[0x01, 0x01, 0x00], crate::log::Timestamp(1)),
+            Err(nom::Err::Incomplete(nom::Needed::new(2)))
+        );
     }
 
     #[test]
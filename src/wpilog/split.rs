@@ -0,0 +1,354 @@
+//! Support for DataLogs that the RIO rotated into multiple parts
+//! (`match.wpilog`, `match.1.wpilog`, `match.2.wpilog`, ...).
+
+use std::path::{Path, PathBuf};
+
+use hashbrown::HashSet;
+
+use super::{
+    load::entry_name,
+    parse::{Payload, WpiRecord},
+};
+use crate::log::Timestamp;
+
+/// The name of the entry WPILib logs the wall-clock time under, in microseconds since the epoch.
+const SYSTEM_TIME_ENTRY: &str = "systemTime";
+
+/// 2000-01-01T00:00:00Z. Earlier `systemTime`s are from before the RIO's clock was set, or
+/// corrupt, and say nothing about when a part was logged.
+const MIN_SYSTEM_TIME: i64 = 946_684_800_000_000;
+
+/// How far apart in wall-clock time, in microseconds, a part can start from where the one before
+/// it ended and still continue it. The RIO starts the next part as soon as it rotates one.
+const MAX_PART_GAP: i64 = 60_000_000;
+
+/// Splits a part's file name into its base name and part index.
+///
+/// `match.wpilog` is part 0 of `match`, and `match.1.wpilog` is part 1.
+#[must_use]
+pub fn part_key(path: &Path) -> (String, u32) {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    match stem.rsplit_once('.') {
+        Some((base, index)) => index
+            .parse()
+            .map_or_else(|_| (stem.clone(), 0), |index| (base.to_owned(), index)),
+        None => (stem, 0),
+    }
+}
+
+/// Finds the `.wpilog` parts in a directory, grouped by base name, each ordered by part index.
+///
+/// A directory can hold the parts of several logs, and those aren't one recording.
+pub fn find_parts(dir: &Path) -> std::io::Result<Vec<Vec<PathBuf>>> {
+    let parts = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(group_parts(parts))
+}
+
+fn group_parts(mut parts: Vec<PathBuf>) -> Vec<Vec<PathBuf>> {
+    parts.retain(|path| path.extension().is_some_and(|ext| ext == "wpilog"));
+    parts.sort_by_cached_key(|path| part_key(path));
+
+    parts
+        .chunk_by(|a, b| part_key(a).0 == part_key(b).0)
+        .map(<[_]>::to_vec)
+        .collect()
+}
+
+/// The range of timestamps covered by a single part.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PartSpan {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    /// The first `systemTime` sample in the part, as `(robot time, wall-clock time)`.
+    pub system_time: Option<(Timestamp, i64)>,
+}
+
+impl PartSpan {
+    #[must_use]
+    pub fn from_records(records: &[WpiRecord<'_>]) -> Self {
+        let mut span: Option<Self> = None;
+        let mut system_time_ids = HashSet::new();
+
+        for record in records {
+            let span = span.get_or_insert(Self {
+                start: record.timestamp,
+                end: record.timestamp,
+                system_time: None,
+            });
+            span.start = span.start.min(record.timestamp);
+            span.end = span.end.max(record.timestamp);
+
+            match record.payload {
+                Payload::Start {
+                    entry_id,
+                    entry_name: name,
+                    entry_type,
+                    ..
+                } if entry_name(name) == SYSTEM_TIME_ENTRY && entry_type == "int64" => {
+                    system_time_ids.insert(entry_id);
+                }
+                Payload::Raw { entry_id, data }
+                    if span.system_time.is_none() && system_time_ids.contains(&entry_id) =>
+                {
+                    if let Some(Ok(bytes)) = data.get(0..8).map(TryInto::try_into) {
+                        span.system_time = Some((record.timestamp, i64::from_le_bytes(bytes)));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        span.unwrap_or_default()
+    }
+
+    /// The first `systemTime` sample, if it's a plausible wall-clock time within the part.
+    fn wall_clock(&self) -> Option<(Timestamp, i64)> {
+        self.system_time.filter(|&(robot, system)| {
+            system >= MIN_SYSTEM_TIME && (self.start..=self.end).contains(&robot)
+        })
+    }
+
+    /// The wall-clock time at robot time `timestamp`, if the part has a plausible one.
+    fn wall_time_at(&self, timestamp: Timestamp) -> Option<i64> {
+        let (robot, system) = self.wall_clock()?;
+        let since = i64::try_from(i128::from(timestamp.0) - i128::from(robot.0)).ok()?;
+        system.checked_add(since)
+    }
+}
+
+/// How many of the parts, from the first, continue one another.
+///
+/// A part that starts more than [`MAX_PART_GAP`] of wall-clock time away from where the one
+/// before it ended is from another log that happens to share their name. Parts without a
+/// plausible `systemTime` are taken to continue, as nothing says otherwise.
+#[must_use]
+pub fn continuous_parts(spans: &[PartSpan]) -> usize {
+    spans
+        .windows(2)
+        .position(|pair| {
+            let [prev, next] = pair else {
+                return false;
+            };
+            let (Some(end), Some(start)) =
+                (prev.wall_time_at(prev.end), next.wall_time_at(next.start))
+            else {
+                return false;
+            };
+
+            (i128::from(start) - i128::from(end)).abs() > i128::from(MAX_PART_GAP)
+        })
+        .map_or(spans.len(), |last| last + 1)
+}
+
+/// The offset that puts `span` at its wall-clock time after `prev`, which was moved by
+/// `prev_offset`, if both have a plausible one.
+fn wall_clock_offset(prev: &PartSpan, prev_offset: u64, span: &PartSpan) -> Option<u64> {
+    let (prev_robot, prev_system) = prev.wall_clock()?;
+    let (robot, system) = span.wall_clock()?;
+
+    let offset = i128::from(prev_robot.0) + i128::from(prev_offset) + i128::from(system)
+        - i128::from(prev_system)
+        - i128::from(robot.0);
+    u64::try_from(offset).ok()
+}
+
+/// Computes the offset to add to each part's timestamps so the parts form one continuous timeline.
+///
+/// When both a part and the one before it logged a plausible `systemTime`, the wall-clock time is
+/// used to line them up. Otherwise, a part that starts before the previous one ended is moved to
+/// start right after it.
+#[must_use]
+pub fn part_offsets(spans: &[PartSpan]) -> Vec<u64> {
+    let mut offsets = Vec::with_capacity(spans.len());
+    let mut prev: Option<(&PartSpan, u64)> = None;
+
+    for span in spans {
+        let offset = prev.map_or(0, |(prev, prev_offset)| {
+            wall_clock_offset(prev, prev_offset, span).unwrap_or_else(|| {
+                prev.end
+                    .0
+                    .saturating_add(prev_offset)
+                    .saturating_sub(span.start.0)
+            })
+        });
+
+        offsets.push(offset);
+        prev = Some((span, offset));
+    }
+
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::{
+        log::Timestamp,
+        wpilog::parse::{Payload, WpiRecord},
+    };
+
+    /// 2025-03-15T18:45:00Z, when the parts' clocks start.
+    const EPOCH: i64 = 1_742_064_300_000_000;
+
+    /// A `systemTime` sample `sys` microseconds after [`EPOCH`].
+    fn sample(timestamp: u64, sys: i64) -> (u64, [u8; 8]) {
+        (timestamp, (EPOCH + sys).to_le_bytes())
+    }
+
+    fn system_time_part(samples: &[(u64, [u8; 8])]) -> Vec<WpiRecord<'_>> {
+        let mut records = vec![WpiRecord {
+            timestamp: Timestamp(samples[0].0),
+            payload: Payload::Start {
+                entry_id: 1,
+                entry_name: "systemTime",
+                entry_type: "int64",
+                entry_metadata: "",
+            },
+        }];
+
+        records.extend(samples.iter().map(|(timestamp, sys)| WpiRecord {
+            timestamp: Timestamp(*timestamp),
+            payload: Payload::Raw {
+                entry_id: 1,
+                data: sys,
+            },
+        }));
+
+        records
+    }
+
+    #[test]
+    fn part_ordering() {
+        assert_eq!(
+            super::part_key(Path::new("match.wpilog")),
+            ("match".to_owned(), 0)
+        );
+        assert_eq!(
+            super::part_key(Path::new("match.1.wpilog")),
+            ("match".to_owned(), 1)
+        );
+        assert_eq!(
+            super::part_key(Path::new("FRC_TBD_d225b5377c70a88d.wpilog")),
+            ("FRC_TBD_d225b5377c70a88d".to_owned(), 0)
+        );
+    }
+
+    #[test]
+    fn parts_grouped_by_log() {
+        let groups = super::group_parts(
+            [
+                "qual.1.wpilog",
+                "match.wpilog",
+                "notes.txt",
+                "qual.wpilog",
+                "match.1.wpilog",
+            ]
+            .map(PathBuf::from)
+            .to_vec(),
+        );
+
+        assert_eq!(
+            groups,
+            [
+                ["match.wpilog", "match.1.wpilog"].map(PathBuf::from),
+                ["qual.wpilog", "qual.1.wpilog"].map(PathBuf::from),
+            ]
+        );
+    }
+
+    #[test]
+    fn two_parts_are_continuous() {
+        // the second part's robot time restarts, but its wall-clock time continues 1.5s after the
+        // first part's first sample
+        let first = [sample(1_000_000, 10_000_000), sample(2_000_000, 11_000_000)];
+        let second = [sample(500_000, 11_500_000), sample(1_500_000, 12_500_000)];
+        let (first, second) = (system_time_part(&first), system_time_part(&second));
+
+        let spans = [
+            super::PartSpan::from_records(&first),
+            super::PartSpan::from_records(&second),
+        ];
+        let offsets = super::part_offsets(&spans);
+
+        assert_eq!(offsets, vec![0, 2_000_000]);
+
+        let timeline = first
+            .iter()
+            .map(|r| r.timestamp.0 + offsets[0])
+            .chain(second.iter().map(|r| r.timestamp.0 + offsets[1]))
+            .collect::<Vec<_>>();
+
+        assert!(timeline.is_sorted());
+        assert_eq!(timeline.last(), Some(&3_500_000));
+    }
+
+    #[test]
+    fn parts_without_system_time_are_appended() {
+        let spans = [
+            super::PartSpan {
+                start: Timestamp(0),
+                end: Timestamp(1_000),
+                system_time: None,
+            },
+            super::PartSpan {
+                start: Timestamp(0),
+                end: Timestamp(500),
+                system_time: None,
+            },
+        ];
+
+        assert_eq!(super::part_offsets(&spans), vec![0, 1_000]);
+    }
+
+    #[test]
+    fn corrupt_system_times_are_appended() {
+        let span = |start, end, system_time| super::PartSpan {
+            start: Timestamp(start),
+            end: Timestamp(end),
+            system_time,
+        };
+
+        // neither overflows: a clock that was never set, and one far past the end of its part
+        let spans = [
+            span(0, u64::MAX - 1, Some((Timestamp(0), 0))),
+            span(0, 10, Some((Timestamp(u64::MAX), i64::MAX))),
+            span(5, 10, None),
+        ];
+        assert_eq!(
+            super::part_offsets(&spans),
+            vec![0, u64::MAX - 1, u64::MAX - 5]
+        );
+        assert_eq!(super::continuous_parts(&spans), 3);
+
+        let spans = [
+            span(0, 10, Some((Timestamp(0), i64::MAX))),
+            span(0, 10, Some((Timestamp(0), super::MIN_SYSTEM_TIME))),
+        ];
+        assert_eq!(super::part_offsets(&spans), vec![0, 10]);
+    }
+
+    #[test]
+    fn parts_of_another_log_arent_continuous() {
+        let first = [sample(1_000_000, 0), sample(2_000_000, 1_000_000)];
+        let next = [sample(0, 1_500_000)];
+        // named like the others, but logged an hour later
+        let other = [sample(0, 3_600_000_000)];
+        let parts = [&first[..], &next[..], &other[..]].map(system_time_part);
+        let spans = parts
+            .iter()
+            .map(|records| super::PartSpan::from_records(records))
+            .collect::<Vec<_>>();
+
+        assert_eq!(super::continuous_parts(&spans), 2);
+        assert_eq!(super::continuous_parts(&spans[..2]), 2);
+        assert_eq!(super::continuous_parts(&[]), 0);
+    }
+}
@@ -0,0 +1,249 @@
+//! Reading a DataLog's records one at a time from a [`Read`]er, for logs that are still being
+//! written or are too large to hold in memory.
+//!
+//! A record cut off by the end of what's been written so far is an error, but the bytes read of
+//! it are kept, so reading again once more has been written carries on from where it stopped.
+
+use std::io::{ErrorKind, Read};
+
+use rerun::external::anyhow::{self, anyhow, bail};
+
+use super::parse::{RecordHeaderLengths, WpiLogFile, WpiRecord};
+
+/// The largest payload a record may declare unless configured otherwise. Larger ones are taken
+/// to be corruption rather than buffered.
+pub const DEFAULT_MAX_PAYLOAD: usize = 64 * 1024 * 1024;
+
+/// The magic, version and extra header length that start every log.
+const HEADER_LEN: usize = 12;
+
+pub struct RecordReader<R> {
+    reader: R,
+    /// The record being read, grown to fit the largest one so far.
+    buf: Vec<u8>,
+    /// Whether `buf` holds a whole record, so the next one starts from scratch.
+    complete: bool,
+    max_payload: usize,
+    header_read: bool,
+}
+
+impl<R: Read> RecordReader<R> {
+    pub const fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            complete: false,
+            max_payload: DEFAULT_MAX_PAYLOAD,
+            header_read: false,
+        }
+    }
+
+    /// Sets the largest payload a record may declare, in bytes.
+    #[must_use]
+    pub const fn with_max_payload(mut self, max_payload: usize) -> Self {
+        self.max_payload = max_payload;
+        self
+    }
+
+    /// Reads the next record, or `None` once the log ends between records.
+    ///
+    /// Control records of unknown types, and malformed ones, are skipped, like [`WpiRecord::parse`]
+    /// does.
+    pub fn next_record(&mut self) -> Result<Option<WpiRecord<'_>>, anyhow::Error> {
+        if !self.header_read {
+            self.read_header()?;
+        }
+
+        loop {
+            if self.complete {
+                self.buf.clear();
+                self.complete = false;
+            }
+            match self.read_to(1) {
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                result => result?,
+            }
+
+            let lengths = RecordHeaderLengths::from(self.buf[0]);
+            let payload_start = 1 + usize::from(lengths.size_entry_id());
+            let header_len = payload_start
+                + usize::from(lengths.size_payload_len())
+                + usize::from(lengths.size_timestamp());
+            self.read_to(header_len)
+                .map_err(|e| anyhow!("log ends in a record header: {e}"))?;
+
+            let mut payload_len = [0; 8];
+            let declared =
+                &self.buf[payload_start..payload_start + usize::from(lengths.size_payload_len())];
+            payload_len[..declared.len()].copy_from_slice(declared);
+            let payload_len = u64::from_le_bytes(payload_len);
+            if payload_len > self.max_payload as u64 {
+                bail!(
+                    "record declares a {payload_len} byte payload, more than the {} byte limit",
+                    self.max_payload
+                );
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            self.read_to(header_len + payload_len as usize)
+                .map_err(|e| anyhow!("log ends in a {payload_len} byte payload: {e}"))?;
+            self.complete = true;
+
+            // the record is parsed again to be returned, as borrowing it here would keep `buf`
+            // borrowed across iterations
+            if let Ok((_, None)) = WpiRecord::parse_known(&self.buf) {
+                continue;
+            }
+            break;
+        }
+
+        let (_, record) =
+            WpiRecord::parse_known(&self.buf).map_err(|e| anyhow!("invalid record: {e}"))?;
+
+        Ok(record.map(|(record, _)| record))
+    }
+
+    fn read_header(&mut self) -> Result<(), anyhow::Error> {
+        self.read_to(HEADER_LEN)?;
+        if !WpiLogFile::is_wpilog(&self.buf) {
+            bail!("not a WPILib DataLog");
+        }
+
+        let extra_header_len = u32::from_le_bytes(self.buf[8..HEADER_LEN].try_into()?);
+        if u64::from(extra_header_len) > self.max_payload as u64 {
+            bail!(
+                "log header declares {extra_header_len} extra bytes, more than the {} byte limit",
+                self.max_payload
+            );
+        }
+        self.read_to(HEADER_LEN + usize::try_from(extra_header_len)?)?;
+        WpiLogFile::parse_header(&self.buf).map_err(|e| anyhow!("invalid log header: {e}"))?;
+
+        self.header_read = true;
+        self.complete = true;
+        Ok(())
+    }
+
+    /// Reads from the stream until `buf` is `len` bytes long, keeping whatever was read if the
+    /// stream ends first.
+    fn read_to(&mut self, len: usize) -> std::io::Result<()> {
+        while self.buf.len() < len {
+            let start = self.buf.len();
+            self.buf.resize(len, 0);
+            match self.reader.read(&mut self.buf[start..]) {
+                Ok(0) => {
+                    self.buf.truncate(start);
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
+                Ok(read) => self.buf.truncate(start + read),
+                Err(e) => {
+                    self.buf.truncate(start);
+                    if e.kind() != ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        io::{self, Cursor, Read},
+        rc::Rc,
+    };
+
+    use crate::wpilog::parse::Payload;
+
+    /// A log with a single `raw` record of `len` bytes for entry 1.
+    fn log_with_payload(len: u32) -> Vec<u8> {
+        let mut log = b"WPILOG".to_vec();
+        log.extend_from_slice(&[0x00, 0x01]); // version 1.0
+        log.extend_from_slice(&0u32.to_le_bytes()); // no extra header
+
+        log.push(0x0C); // b0000_1100 - ID length = 1 byte, payload size length = 4 bytes, timestamp length = 1 byte
+        log.push(0x01); // entry ID = 1
+        log.extend_from_slice(&len.to_le_bytes());
+        log.push(0x10); // timestamp = 16 us
+        log.resize(log.len() + len as usize, 0xAB);
+
+        log
+    }
+
+    #[test]
+    fn large_payload() {
+        let log = log_with_payload(4 * 1024 * 1024);
+        let mut reader = super::RecordReader::new(Cursor::new(log));
+
+        let record = reader.next_record().unwrap().expect("no record");
+        assert_eq!(record.timestamp.0, 16);
+        let Payload::Raw { entry_id, data } = record.payload else {
+            panic!("not a data record: {:?}", record.payload);
+        };
+        assert_eq!(entry_id, 1);
+        assert_eq!(data.len(), 4 * 1024 * 1024);
+        assert!(data.iter().all(|b| *b == 0xAB));
+
+        assert!(reader.next_record().unwrap().is_none());
+    }
+
+    #[test]
+    fn extra_header_over_limit() {
+        let mut log = log_with_payload(0);
+        log[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut reader = super::RecordReader::new(Cursor::new(log));
+
+        let err = reader.next_record().unwrap_err();
+        assert!(err.to_string().contains("byte limit"), "{err}");
+    }
+
+    #[test]
+    fn resumes_cut_off_record() {
+        /// A log being written, of which only `written` bytes can be read so far.
+        struct Growing {
+            log: Vec<u8>,
+            position: usize,
+            written: Rc<Cell<usize>>,
+        }
+
+        impl Read for Growing {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let available = &self.log[self.position..self.written.get()];
+                let len = available.len().min(buf.len());
+                buf[..len].copy_from_slice(&available[..len]);
+                self.position += len;
+                Ok(len)
+            }
+        }
+
+        let log = log_with_payload(16);
+        let written = Rc::new(Cell::new(log.len() - 5));
+        let mut reader = super::RecordReader::new(Growing {
+            log,
+            position: 0,
+            written: Rc::clone(&written),
+        });
+
+        assert!(reader.next_record().is_err());
+
+        written.set(written.get() + 5);
+        let record = reader.next_record().unwrap().expect("no record");
+        assert_eq!(record.timestamp.0, 16);
+        let Payload::Raw { data, .. } = record.payload else {
+            panic!("not a data record: {:?}", record.payload);
+        };
+        assert_eq!(data, [0xAB; 16]);
+    }
+
+    #[test]
+    fn payload_over_limit() {
+        let log = log_with_payload(4 * 1024 * 1024);
+        let mut reader = super::RecordReader::new(Cursor::new(log)).with_max_payload(1024 * 1024);
+
+        let err = reader.next_record().unwrap_err();
+        assert!(err.to_string().contains("byte limit"), "{err}");
+    }
+}
@@ -0,0 +1,67 @@
+//! Runs the command line tools as a user would, checking what they write to stdout.
+
+use std::process::Command;
+
+use firstrun::{cli, config::Config, log::EntryLog, wpilog};
+
+const LOG: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/test_data/FRC_TBD_d225b5377c70a88d.wpilog"
+);
+
+/// Runs the tool with `args`, returning its stdout.
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_firstrun"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn ndjson_stdout_is_only_json() {
+    let stdout = run(&["--to-ndjson", LOG, "-"]);
+    assert!(!stdout.is_empty());
+    for line in stdout.lines() {
+        assert!(
+            serde_json::from_str::<serde_json::Value>(line).is_ok(),
+            "not a JSON line: {line}"
+        );
+    }
+}
+
+/// Decodes the test log in process, as the tools do.
+fn load() -> EntryLog {
+    let mut log = EntryLog::with_config(Config::from_env().unwrap());
+    wpilog::load::load_parts(&[std::fs::read(LOG).unwrap()], &mut log);
+
+    log
+}
+
+#[test]
+fn entities_stdout_is_only_the_tree() {
+    assert_eq!(run(&["--entities", LOG]), cli::entity_tree(&load()));
+}
+
+#[test]
+fn print_schema_stdout_is_only_the_report() {
+    let stdout = run(&["--print-schema", LOG]);
+    assert!(!stdout.is_empty());
+    assert_eq!(stdout, cli::schema_report(&load()));
+}
+
+#[test]
+fn replay_needs_a_host() {
+    let output = Command::new(env!("CARGO_BIN_EXE_firstrun"))
+        .args(["--replay", LOG])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("usage: firstrun --replay"));
+}
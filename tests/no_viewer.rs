@@ -0,0 +1,129 @@
+//! Runs the loader's whole pipeline without a viewer, from a DataLog's bytes to the chunks it
+//! sends, checking the component values that come out.
+
+use std::path::Path;
+
+use firstrun::{
+    config::{ComponentOverride, Config, EntityPattern},
+    conv::log_changes_to_chunks,
+    log::EntryLog,
+    wpilog,
+};
+use rerun::{
+    ApplicationId, EntityPath, StoreId, StoreKind, Timeline,
+    components::{Position3D, Scalar},
+};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test_data/entities.wpilog");
+
+/// Appends a record with 4-byte entry IDs and payload sizes, and an 8-byte timestamp.
+fn record(log: &mut Vec<u8>, entry_id: u32, timestamp: u64, payload: &[u8]) {
+    log.push(0x7f);
+    log.extend(entry_id.to_le_bytes());
+    log.extend(u32::try_from(payload.len()).unwrap().to_le_bytes());
+    log.extend(timestamp.to_le_bytes());
+    log.extend(payload);
+}
+
+/// Builds `test_data/entities.wpilog`: a `speed` entity whose in-band metadata renders it as a
+/// `Scalar`, and a `Translation3d` struct under `target`.
+///
+/// Set `REGENERATE_FIXTURES` when running the tests to write it out again after changing it.
+fn entities_log() -> Vec<u8> {
+    let mut log = b"WPILOG".to_vec();
+    log.extend([0x00, 0x01]); // version 1.0
+    log.extend(0u32.to_le_bytes()); // no extra header
+
+    for (entry_id, name, ty) in [
+        (1u32, "/.schema/struct:Translation3d", "structschema"),
+        (2, "NT:/speed/.type", "string"),
+        (3, "NT:/speed/.components", "string"),
+        (4, "NT:/speed/Scalar", "double"),
+        (5, "NT:/target/Point3d", "struct:Translation3d"),
+    ] {
+        let mut start = vec![0x00];
+        start.extend(entry_id.to_le_bytes());
+        // no metadata
+        for field in [name, ty, ""] {
+            start.extend(u32::try_from(field.len()).unwrap().to_le_bytes());
+            start.extend(field.as_bytes());
+        }
+        record(&mut log, 0, 0, &start);
+    }
+
+    record(&mut log, 1, 0, b"double x;double y;double z");
+    record(&mut log, 2, 0, b"Entity");
+    record(&mut log, 3, 0, b"Scalar");
+    record(&mut log, 4, 1_000_000, &1.5f64.to_le_bytes());
+    let point = [1.0f64, 2.0, 3.0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<_>>();
+    record(&mut log, 5, 1_500_000, &point);
+    record(&mut log, 4, 2_000_000, &2.5f64.to_le_bytes());
+
+    log
+}
+
+#[test]
+fn fixture_is_up_to_date() {
+    let generated = entities_log();
+    if std::env::var_os("REGENERATE_FIXTURES").is_some() {
+        std::fs::write(FIXTURE, &generated).unwrap();
+    }
+
+    assert_eq!(std::fs::read(FIXTURE).unwrap(), generated);
+}
+
+#[test]
+fn component_values() {
+    let mut log = EntryLog::with_config(Config {
+        components: vec![ComponentOverride {
+            pattern: EntityPattern("target".to_owned()),
+            components: vec!["Point3d".to_owned()],
+        }],
+        ..Config::default()
+    });
+    wpilog::load::load_parts(&[entities_log()], &mut log);
+
+    let timeline = Timeline::new_duration("robotime");
+    let chunks = log_changes_to_chunks(
+        &StoreId::random(StoreKind::Recording),
+        &ApplicationId::random(),
+        timeline,
+        &mut log,
+    );
+    let chunk = |path: &str| {
+        chunks
+            .iter()
+            .find(|c| c.entity_path() == &EntityPath::from_file_path(Path::new(path)))
+            .unwrap_or_else(|| panic!("no chunk for {path}"))
+    };
+
+    let speed = chunk("speed");
+    assert_eq!(
+        speed.timelines().get(timeline.name()).unwrap().times_raw(),
+        &[1_000_000_000, 2_000_000_000]
+    );
+    assert_eq!(
+        speed
+            .iter_component::<Scalar>()
+            .flat_map(|batch| batch.to_vec())
+            .collect::<Vec<_>>(),
+        [Scalar::from(1.5), Scalar::from(2.5)]
+    );
+
+    // the struct's fields all changed at once, so the point is logged once with each of them
+    let target = chunk("target");
+    assert_eq!(
+        target.timelines().get(timeline.name()).unwrap().times_raw(),
+        &[1_500_000_000]
+    );
+    assert_eq!(
+        target
+            .iter_component::<Position3D>()
+            .flat_map(|batch| batch.to_vec())
+            .collect::<Vec<_>>(),
+        [Position3D::new(1.0, 2.0, 3.0)]
+    );
+}
@@ -1,21 +1,148 @@
-use std::path::Path;
+use std::collections::BTreeMap;
 
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
+use re_types_builder::{
+    CodeGenerator, GeneratedFiles, ObjectKind, Objects, Reporter, TypeRegistry,
+};
 
 static RE_DEFINITIONS_DIR_PATH: &str = "crates/store/re_types/definitions";
 
-pub fn gen_components(re_worktree: &Utf8Path) -> Result<(), anyhow::Error> {
-    let def_path = re_worktree.join(RE_DEFINITIONS_DIR_PATH);
+/// Generates the NetworkTables topic bindings for rerun's components into `output_path`.
+///
+/// With `dry_run`, the files are listed instead of written.
+pub fn gen_components(
+    re_worktree: &Utf8Path,
+    output_path: &Utf8Path,
+    dry_run: bool,
+) -> Result<(), anyhow::Error> {
+    let files = generate_files(&re_worktree.join(RE_DEFINITIONS_DIR_PATH), output_path);
 
+    for (path, contents) in files {
+        if dry_run {
+            println!("would write {path} ({} bytes)", contents.len());
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents)?;
+    }
+
+    Ok(())
+}
+
+/// Generates the bindings for the definitions in `def_path`, starting from its `entry_point.fbs`.
+fn generate_files(def_path: &Utf8Path, output_path: &Utf8Path) -> GeneratedFiles {
     let (report, reporter) = re_types_builder::report::init();
 
     let (objects, type_registry) = re_types_builder::generate_lang_agnostic(
         &reporter,
-        &def_path,
+        def_path,
         def_path.join("entry_point.fbs"),
     );
 
-    let mut generator = NetworkTablesCodeGenerator::new(output_path.as_ref());
+    let mut generator = NetworkTablesCodeGenerator::new(output_path);
+    let files = generator.generate(&reporter, &objects, &type_registry);
 
-    Ok(())
+    report.finalize();
+
+    files
+}
+
+/// Writes a Rust module per rerun component naming the topic it's published as.
+pub struct NetworkTablesCodeGenerator {
+    output_path: Utf8PathBuf,
+}
+
+impl NetworkTablesCodeGenerator {
+    pub fn new(output_path: impl Into<Utf8PathBuf>) -> Self {
+        Self {
+            output_path: output_path.into(),
+        }
+    }
+}
+
+impl CodeGenerator for NetworkTablesCodeGenerator {
+    fn generate(
+        &mut self,
+        _reporter: &Reporter,
+        objects: &Objects,
+        _arrow_registry: &TypeRegistry,
+    ) -> GeneratedFiles {
+        let mut files = BTreeMap::new();
+        let mut modules = Vec::new();
+
+        for object in objects.objects_of_kind(ObjectKind::Component) {
+            let module = object.snake_case_name();
+
+            files.insert(
+                self.output_path.join(format!("{module}.rs")),
+                render_component(&object.name, &object.fqname),
+            );
+            modules.push(module);
+        }
+
+        files.insert(self.output_path.join("mod.rs"), render_mod(&modules));
+
+        files
+    }
+}
+
+const HEADER: &str = "// DO NOT EDIT! This file was auto-generated by `cargo xtask codegen`.\n";
+
+fn render_component(name: &str, fqname: &str) -> String {
+    format!(
+        "{HEADER}
+/// The NetworkTables topic type for the rerun `{name}` component.
+pub struct {name};
+
+impl {name} {{
+    pub const COMPONENT: &str = \"{fqname}\";
+    pub const TOPIC_TYPE: &str = \"rerun:{name}\";
+}}
+"
+    )
+}
+
+fn render_mod(modules: &[String]) -> String {
+    let mut out = HEADER.to_owned();
+    for module in modules {
+        out.push_str(&format!("\nmod {module};\npub use {module}::*;\n"));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use camino::Utf8Path;
+
+    #[test]
+    fn renders_component() {
+        let out = super::render_component("Scalar", "rerun.components.Scalar");
+
+        assert!(out.starts_with(super::HEADER));
+        assert!(out.contains("pub struct Scalar;"));
+        assert!(out.contains("\"rerun.components.Scalar\""));
+    }
+
+    #[test]
+    #[ignore = "needs `flatc` on the path, like the codegen itself"]
+    fn generates_fixture_definitions() {
+        let def_path = Utf8Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/definitions");
+        let files = super::generate_files(&def_path, Utf8Path::new("out"));
+
+        assert_eq!(
+            files.keys().map(|path| path.as_str()).collect::<Vec<_>>(),
+            ["out/mod.rs", "out/scalar.rs"]
+        );
+
+        let component = &files[Utf8Path::new("out/scalar.rs")];
+        assert!(component.starts_with(super::HEADER));
+        assert!(component.contains("pub struct Scalar;"));
+        assert!(component.contains("\"rerun.components.Scalar\""));
+
+        assert!(files[Utf8Path::new("out/mod.rs")].contains("mod scalar;"));
+    }
 }
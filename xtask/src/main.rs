@@ -1,5 +1,21 @@
+use anyhow::{Context as _, bail};
+use camino::Utf8PathBuf;
+
 pub mod codegen;
 
-fn main() {
-    println!("Hello, world!");
+const USAGE: &str = "usage: cargo xtask codegen <rerun worktree> <output path> [--dry-run]";
+
+fn main() -> Result<(), anyhow::Error> {
+    let mut args = std::env::args().skip(1);
+
+    match args.next().as_deref() {
+        Some("codegen") => {
+            let re_worktree = args.next().map(Utf8PathBuf::from).context(USAGE)?;
+            let output_path = args.next().map(Utf8PathBuf::from).context(USAGE)?;
+            let dry_run = args.any(|arg| arg == "--dry-run");
+
+            codegen::gen_components(&re_worktree, &output_path, dry_run)
+        }
+        _ => bail!(USAGE),
+    }
 }